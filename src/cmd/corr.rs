@@ -0,0 +1,407 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::str;
+
+use stats::{merge_all, Commute};
+use threadpool::ThreadPool;
+
+use crate::config::{Config, Delimiter};
+use crate::index::Indexed;
+use crate::numeric;
+use crate::select::{SelectColumns, Selection};
+use crate::util;
+use crate::CliResult;
+use serde::Deserialize;
+
+static USAGE: &str = "
+Computes the correlation matrix of the numeric columns of CSV data.
+
+A column is numeric when all its values are numbers or empty, and the other
+columns are skipped. By default, the Pearson correlation coefficients of the
+columns are computed, in a single pass over the data. The Spearman rank
+correlation coefficients can be computed instead with --spearman, and the
+sample covariances with --covariance.
+
+The coefficient of two columns is computed from the rows where both have a
+value. With --spearman, the values of each column are ranked among those rows,
+ties getting their average rank. The coefficients that are undefined, e.g. because a column
+has a single distinct value, are empty.
+
+The matrix is formatted as CSV data, with a row and a column for each numeric
+column:
+
+    field,a,b,c
+    a,1,0.5,-0.2
+    ...
+
+Usage:
+    qsv corr [options] [<input>]
+
+corr options:
+    -s, --select <arg>     Select a subset of columns to correlate.
+                           See 'qsv select --help' for the format details.
+    --spearman             Compute the Spearman rank correlation coefficients.
+                           This requires storing the values in memory.
+    --covariance           Compute the covariance matrix instead.
+    -j, --jobs <arg>       The number of jobs to run in parallel.
+                           This works only when the given CSV has an index.
+                           Note that a file handle is opened for each job.
+                           When set to '0', the number of jobs is set to the
+                           number of CPUs detected divided by 3.
+                           When set to '-1', the number of jobs is set to the
+                           number of CPUs detected.
+                           [default: 0]
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will NOT be interpreted
+                           as column names, and the columns are named by their
+                           1-based indices instead.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(Clone, Deserialize)]
+struct Args {
+    arg_input: Option<String>,
+    flag_select: SelectColumns,
+    flag_spearman: bool,
+    flag_covariance: bool,
+    flag_jobs: isize,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_spearman && args.flag_covariance {
+        return fail!("Only one of --spearman and --covariance can be used.");
+    }
+    let rconfig = args.rconfig();
+    numeric::init(&rconfig)?;
+
+    // piped input is spooled by indexed, so it must be called first
+    let idx = rconfig.indexed()?;
+    let mut rdr = rconfig.reader()?;
+    let headers = rdr.byte_headers()?.clone();
+    let sel = rconfig.selection(&headers)?;
+    let names: Vec<String> = if args.flag_no_headers {
+        sel.iter().map(|i| (i + 1).to_string()).collect()
+    } else {
+        sel.select(&headers)
+            .map(|h| String::from_utf8_lossy(h).into_owned())
+            .collect()
+    };
+
+    let mut matrix = match idx {
+        Some(idx) if args.flag_jobs != 1 => args.parallel_matrix(&sel, idx)?,
+        _ => args.compute(&sel, rdr.byte_records())?,
+    };
+    if args.flag_spearman {
+        matrix = matrix.ranked();
+    }
+
+    let columns: Vec<usize> = (0..sel.len()).filter(|&i| matrix.is_numeric(i)).collect();
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+    let mut header = vec!["field"];
+    header.extend(columns.iter().map(|&i| &*names[i]));
+    wtr.write_record(&header)?;
+    for &i in &columns {
+        let mut row = vec![names[i].clone()];
+        for &j in &columns {
+            let moments = matrix.pair(i, j);
+            let value = if args.flag_covariance {
+                moments.covariance()
+            } else {
+                moments.correlation()
+            };
+            row.push(value.map_or_else(String::new, |v| v.to_string()));
+        }
+        wtr.write_record(&row)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+impl Args {
+    fn rconfig(&self) -> Config {
+        Config::new(&self.arg_input)
+            .delimiter(self.flag_delimiter)
+            .no_headers(self.flag_no_headers)
+            .select(self.flag_select.clone())
+    }
+
+    fn parallel_matrix(
+        &self,
+        sel: &Selection,
+        idx: Indexed<fs::File, fs::File>,
+    ) -> CliResult<Matrix> {
+        if idx.count() == 0 {
+            return Ok(Matrix::new(sel.len(), self.flag_spearman));
+        }
+        let chunk_size = util::chunk_size(idx.count() as usize, self.njobs());
+        let nchunks = util::num_of_chunks(idx.count() as usize, chunk_size);
+
+        let pool = ThreadPool::new(self.njobs());
+        let (send, recv) = channel::bounded(0);
+        for i in 0..nchunks {
+            let (send, args, sel) = (send.clone(), self.clone(), sel.clone());
+            pool.execute(move || {
+                let mut idx = args.rconfig().indexed().unwrap().unwrap();
+                idx.seek((i * chunk_size) as u64).unwrap();
+                let it = idx.byte_records().take(chunk_size);
+                send.send((i, args.compute(&sel, it).unwrap())).unwrap();
+            });
+        }
+        drop(send);
+        // the chunks are merged in order, so that the rows of the values of
+        // all the columns stay aligned
+        let mut chunks: Vec<(usize, Matrix)> = recv.iter().collect();
+        chunks.sort_by_key(|(i, _)| *i);
+        Ok(merge_all(chunks.into_iter().map(|(_, m)| m)).unwrap())
+    }
+
+    fn compute<I>(&self, sel: &Selection, it: I) -> CliResult<Matrix>
+    where
+        I: Iterator<Item = csv::Result<csv::ByteRecord>>,
+    {
+        let mut matrix = Matrix::new(sel.len(), self.flag_spearman);
+        let mut values = Vec::with_capacity(sel.len());
+        for row in it {
+            let row = row?;
+            values.clear();
+            values.extend(sel.select(&row).map(|field| {
+                if field.is_empty() {
+                    return Value::Null;
+                }
+                match str::from_utf8(field).ok().and_then(numeric::parse_float) {
+                    Some(n) if n.is_finite() => Value::Number(n),
+                    _ => Value::Invalid,
+                }
+            }));
+            matrix.add(&values);
+        }
+        Ok(matrix)
+    }
+
+    fn njobs(&self) -> usize {
+        let num_cpus = util::num_cpus();
+        match self.flag_jobs {
+            0 => util::max_jobs(),
+            flag_jobs if flag_jobs < 0 => num_cpus,
+            flag_jobs if flag_jobs > num_cpus as isize => num_cpus,
+            _ => self.flag_jobs as usize,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Value {
+    Null,
+    Number(f64),
+    Invalid,
+}
+
+/// The co-moments of each pair of columns, and whether they're numeric.
+/// With --spearman, the values are kept instead, to be ranked at the end.
+#[derive(Clone)]
+struct Matrix {
+    len: usize,
+    /// The co-moments of the columns i <= j, row by row.
+    pairs: Vec<CoMoments>,
+    numbers: Vec<bool>,
+    invalid: Vec<bool>,
+    /// The values of each column, with --spearman.
+    values: Option<Vec<Vec<Option<f64>>>>,
+}
+
+impl Matrix {
+    fn new(len: usize, spearman: bool) -> Matrix {
+        Matrix {
+            len,
+            pairs: vec![CoMoments::default(); len * (len + 1) / 2],
+            numbers: vec![false; len],
+            invalid: vec![false; len],
+            values: if spearman {
+                Some(vec![vec![]; len])
+            } else {
+                None
+            },
+        }
+    }
+
+    #[inline]
+    fn pair_index(&self, i: usize, j: usize) -> usize {
+        let (i, j) = if i <= j { (i, j) } else { (j, i) };
+        i * self.len - i * (i + 1) / 2 + j
+    }
+
+    fn pair(&self, i: usize, j: usize) -> &CoMoments {
+        &self.pairs[self.pair_index(i, j)]
+    }
+
+    fn is_numeric(&self, i: usize) -> bool {
+        self.numbers[i] && !self.invalid[i]
+    }
+
+    fn add(&mut self, row: &[Value]) {
+        for (i, value) in row.iter().enumerate() {
+            match value {
+                Value::Number(_) => self.numbers[i] = true,
+                Value::Invalid => self.invalid[i] = true,
+                Value::Null => {}
+            }
+        }
+        if let Some(values) = self.values.as_mut() {
+            for (column, value) in values.iter_mut().zip(row) {
+                column.push(match *value {
+                    Value::Number(n) => Some(n),
+                    _ => None,
+                });
+            }
+            return;
+        }
+        let mut k = 0;
+        for (i, x) in row.iter().enumerate() {
+            for y in &row[i..] {
+                if let (Value::Number(x), Value::Number(y)) = (*x, *y) {
+                    self.pairs[k].add(x, y);
+                }
+                k += 1;
+            }
+        }
+    }
+
+    /// The matrix of the ranks of the values, with --spearman.
+    fn ranked(mut self) -> Matrix {
+        let values = match self.values.take() {
+            None => return self,
+            Some(values) => values,
+        };
+        for i in 0..self.len {
+            for j in i..self.len {
+                let (x, y): (Vec<f64>, Vec<f64>) = values[i]
+                    .iter()
+                    .zip(&values[j])
+                    .filter_map(|(&x, &y)| Some((x?, y?)))
+                    .unzip();
+                let k = self.pair_index(i, j);
+                for (x, y) in ranks(&x).into_iter().zip(ranks(&y)) {
+                    self.pairs[k].add(x, y);
+                }
+            }
+        }
+        self
+    }
+}
+
+impl Commute for Matrix {
+    fn merge(&mut self, other: Matrix) {
+        for (pair, other) in self.pairs.iter_mut().zip(other.pairs) {
+            pair.merge(other);
+        }
+        for (numbers, other) in self.numbers.iter_mut().zip(other.numbers) {
+            *numbers |= other;
+        }
+        for (invalid, other) in self.invalid.iter_mut().zip(other.invalid) {
+            *invalid |= other;
+        }
+        if let (Some(values), Some(other)) = (self.values.as_mut(), other.values) {
+            for (column, other) in values.iter_mut().zip(other) {
+                column.extend(other);
+            }
+        }
+    }
+}
+
+/// The ranks of the values of a column, starting at 1, where ties get their
+/// average rank.
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut sorted: Vec<(usize, f64)> = values.iter().copied().enumerate().collect();
+    sorted.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < sorted.len() {
+        let end = start
+            + sorted[start..]
+                .iter()
+                .take_while(|v| v.1 == sorted[start].1)
+                .count();
+        // the average of the ranks start + 1 to end
+        let rank = (start + 1 + end) as f64 / 2.0;
+        for &(i, _) in &sorted[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+/// The means, and the sums of squared deviations and of products of the
+/// deviations, of pairs of values, computed in a streaming fashion.
+#[derive(Clone, Copy, Default)]
+struct CoMoments {
+    n: u64,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    c: f64,
+}
+
+impl CoMoments {
+    #[inline]
+    fn add(&mut self, x: f64, y: f64) {
+        self.n += 1;
+        let n = self.n as f64;
+        let dx = x - self.mean_x;
+        self.mean_x += dx / n;
+        let dy = y - self.mean_y;
+        self.mean_y += dy / n;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c += dx * (y - self.mean_y);
+    }
+
+    /// The sample covariance.
+    fn covariance(&self) -> Option<f64> {
+        if self.n < 2 {
+            return None;
+        }
+        Some(self.c / (self.n - 1) as f64)
+    }
+
+    /// The Pearson correlation coefficient, clamped to [-1, 1] against
+    /// rounding errors.
+    fn correlation(&self) -> Option<f64> {
+        let denominator = (self.m2_x * self.m2_y).sqrt();
+        if self.n < 2 || denominator == 0.0 {
+            return None;
+        }
+        Some((self.c / denominator).clamp(-1.0, 1.0))
+    }
+}
+
+impl Commute for CoMoments {
+    fn merge(&mut self, other: CoMoments) {
+        if other.n == 0 {
+            return;
+        }
+        if self.n == 0 {
+            *self = other;
+            return;
+        }
+        let (n1, n2) = (self.n as f64, other.n as f64);
+        let n = n1 + n2;
+        let dx = other.mean_x - self.mean_x;
+        let dy = other.mean_y - self.mean_y;
+        self.mean_x += dx * n2 / n;
+        self.mean_y += dy * n2 / n;
+        self.m2_x += other.m2_x + dx * dx * n1 * n2 / n;
+        self.m2_y += other.m2_y + dy * dy * n1 * n2 / n;
+        self.c += other.c + dx * dy * n1 * n2 / n;
+        self.n += other.n;
+    }
+}
@@ -12,12 +12,19 @@ pipe multiple qsv commands together. However, you may want the final result to
 have a specific delimiter or record separator, and this is where 'qsv fmt' is
 useful.
 
+The output delimiter and record terminator can also be multi-byte sequences
+(e.g. '¦', '§', '||' or '~|~'), for downstream systems that require them.
+Likewise, the output can be written in a legacy character encoding such as
+windows-1252 or shift_jis with --out-encoding.
+
 Usage:
     qsv fmt [options] [<input>]
 
 fmt options:
     -t, --out-delimiter <arg>  The field delimiter for writing CSV data.
                                [default: ,]
+    --out-terminator <arg>     The record terminator for writing CSV data.
+                               Overrides --crlf.
     --crlf                     Use '\\r\\n' line endings in the output.
     --ascii                    Use ASCII field and record separators.
     --quote <arg>              The quote character to use. [default: \"]
@@ -25,18 +32,24 @@ fmt options:
     --quote-never              Never put quotes around any value.
     --escape <arg>             The escape character to use. When not specified,
                                quotes are escaped by doubling them.
+    --encoding <arg>           The character encoding of the input, e.g.
+                               windows-1252, iso-8859-15 or shift_jis. Use
+                               'auto' to detect it. Overrides QSV_INPUT_ENCODING.
+    --out-encoding <arg>       The character encoding of the output.
+                               [default: utf-8]
 
 Common options:
     -h, --help             Display this message
     -o, --output <file>    Write output to <file> instead of stdout.
     -d, --delimiter <arg>  The field delimiter for reading CSV data.
-                           Must be a single character. (default: ,)
+                           Can be a multi-byte sequence. (default: ,)
 ";
 
 #[derive(Deserialize)]
 struct Args {
     arg_input: Option<String>,
     flag_out_delimiter: Option<Delimiter>,
+    flag_out_terminator: Option<Delimiter>,
     flag_crlf: bool,
     flag_ascii: bool,
     flag_output: Option<String>,
@@ -45,6 +58,8 @@ struct Args {
     flag_quote_always: bool,
     flag_quote_never: bool,
     flag_escape: Option<Delimiter>,
+    flag_encoding: Option<String>,
+    flag_out_encoding: String,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
@@ -52,14 +67,17 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 
     let rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
+        .input_encoding(args.flag_encoding)
         .no_headers(true);
     let mut wconfig = Config::new(&args.flag_output)
         .delimiter(args.flag_out_delimiter)
-        .crlf(args.flag_crlf);
+        .crlf(args.flag_crlf)
+        .record_terminator(args.flag_out_terminator)
+        .output_encoding(Some(args.flag_out_encoding));
 
     if args.flag_ascii {
         wconfig = wconfig
-            .delimiter(Some(Delimiter(b'\x1f')))
+            .delimiter(Some(Delimiter::from_byte(b'\x1f')))
             .terminator(csv::Terminator::Any(b'\x1e'));
     }
     if args.flag_quote_always {
@@ -67,6 +85,9 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     } else if args.flag_quote_never {
         wconfig = wconfig.quote_style(csv::QuoteStyle::Never);
     }
+    if args.flag_quote.is_multi_byte() || args.flag_escape.map_or(false, Delimiter::is_multi_byte) {
+        return fail!("The quote and escape characters must be a single ASCII character.");
+    }
     if let Some(escape) = args.flag_escape {
         wconfig = wconfig.escape(Some(escape.as_byte())).double_quote(false);
     }
@@ -1,12 +1,16 @@
 use std::fs;
 use std::io;
 
-use stats::{merge_all, Frequencies};
+use ahash::AHashMap;
+use itertools::Itertools;
+use stats::{merge_all, Commute};
 use threadpool::ThreadPool;
 
+use crate::cmd::stats::StatsCache;
 use crate::config::{Config, Delimiter};
 use crate::index::Indexed;
 use crate::select::{SelectColumns, Selection};
+use crate::sketch::SpaceSaving;
 use crate::util;
 use crate::CliResult;
 use serde::Deserialize;
@@ -16,14 +20,30 @@ Compute a frequency table on CSV data.
 
 The frequency table is formatted as CSV data:
 
-    field,value,count
+    field,value,count,percentage,cumulative_percentage
 
 By default, there is a row for the N most frequent values for each field in the
 data. The order and number of values can be tweaked with --asc and --limit,
-respectively.
+respectively, and the values can also be limited to those that make up a given
+percentage of their field with --pct-limit. The percentage of each value is
+that of all the values of its field, and the cumulative percentage that of all
+the values listed so far. The values that are left out by the limits are
+summed up in a final 'Other (N)' row, N being the number of those values.
 
 Since this computes an exact frequency table, memory proportional to the
-cardinality of each column is required.
+cardinality of each column is required. For columns with too many distinct
+values, e.g. IDs or free text in large files, the most frequent values can
+instead be found approximately in bounded memory with --approx, using a
+Space-Saving sketch with a fixed number of counters per column. Their counts
+may then overestimate the actual counts, by at most the value reported in an
+additional 'error' column. As the number of the other values isn't known then,
+their row is just named 'Other'.
+
+With --all-unique, when the statistics cache of the data (see the help of
+'qsv stats') has the exact cardinality of a column, and shows that all its
+values are unique, the column is not counted and a single row is reported for
+it instead, with the value <ALL_UNIQUE> and the number of values as count.
+This is not done when --limit is set to '0', so that all the values are listed.
 
 Usage:
     qsv frequency [options] [<input>]
@@ -37,9 +57,21 @@ frequency options:
     -l, --limit <arg>      Limit the frequency table to the N most common
                            items. Set to '0' to disable a limit.
                            [default: 10]
+    --pct-limit <pct>      Limit the frequency table to the values that make up
+                           at least this percentage of the values of their
+                           field, e.g. '1' for 1%, on top of the --limit.
     -a, --asc              Sort the frequency tables in ascending order by
                            count. The default is descending order.
     --no-nulls             Don't include NULLs in the frequency table.
+    --all-unique           Report the columns whose values are all unique as
+                           per the stats cache in a single <ALL_UNIQUE> row,
+                           instead of counting their values.
+    --approx               Approximate the most frequent values and their
+                           counts, with a bounded number of counters.
+                           This requires a limit, and can't be used with --asc.
+    --counters <n>         The number of counters per column with --approx.
+                           More counters are more accurate, but use more memory.
+                           [default: 10000]
     -j, --jobs <arg>       The number of jobs to run in parallel.
                            This works better when the given CSV data has
                            an index already created. Note that a file handle
@@ -66,8 +98,12 @@ pub struct Args {
     pub arg_input: Option<String>,
     pub flag_select: SelectColumns,
     pub flag_limit: usize,
+    pub flag_pct_limit: Option<f64>,
     pub flag_asc: bool,
     pub flag_no_nulls: bool,
+    pub flag_all_unique: bool,
+    pub flag_approx: bool,
+    pub flag_counters: usize,
     pub flag_jobs: isize,
     pub flag_output: Option<String>,
     pub flag_no_headers: bool,
@@ -76,36 +112,184 @@ pub struct Args {
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
-    let rconfig = args.rconfig();
-
-    let mut wtr = Config::new(&args.flag_output).writer()?;
-    let (headers, tables) = match args.rconfig().indexed()? {
-        Some(ref mut idx) if args.njobs() > 1 => args.parallel_ftables(idx),
-        _ => args.sequential_ftables(),
-    }?;
-
-    wtr.write_record(vec!["field", "value", "count"])?;
-    let head_ftables = headers.into_iter().zip(tables.into_iter());
-    for (i, (header, ftab)) in head_ftables.enumerate() {
-        let mut header = header.to_vec();
-        if rconfig.no_headers {
-            header = (i + 1).to_string().into_bytes();
-        }
-        for (value, count) in args.counts(&ftab).into_iter() {
-            let count = count.to_string();
-            let row = vec![&*header, &*value, count.as_bytes()];
-            wtr.write_record(row)?;
-        }
+    if !args.flag_approx {
+        return args.write_tables::<FTable>();
+    }
+    if args.flag_limit == 0 {
+        return fail!("--approx requires a limit.");
+    }
+    if args.flag_asc {
+        return fail!("--approx can't be used with --asc.");
     }
-    Ok(())
+    if args.flag_counters < args.flag_limit {
+        return fail!("--counters can't be less than --limit.");
+    }
+    args.write_tables::<SpaceSaving<ByteString>>()
 }
 
+/// The value reported for a column whose values are all unique.
+const ALL_UNIQUE: &[u8] = b"<ALL_UNIQUE>";
+
 type ByteString = Vec<u8>;
 type Headers = csv::ByteRecord;
-type FTable = Frequencies<Vec<u8>>;
-type FTables = Vec<Frequencies<Vec<u8>>>;
+pub type FTables = Vec<FTable>;
+type UniqueCounts = Vec<Option<u64>>;
+
+/// The counts of the values of a column, either exact or approximate.
+trait Table: Commute + Send + 'static {
+    fn with_args(args: &Args) -> Self;
+
+    fn add(&mut self, value: ByteString);
+
+    /// The values sorted by descending count, or ascending with `asc`, with
+    /// their counts and the maximum overestimation of their counts, and the
+    /// total count of all the values.
+    fn sorted(&self, asc: bool) -> (Vec<(&ByteString, u64, u64)>, u64);
+}
+
+/// FTable is the exact counts of the values of a column.
+#[derive(Clone, Default)]
+pub struct FTable(AHashMap<ByteString, u64>);
+
+impl FTable {
+    /// The values and their counts, in no particular order.
+    pub fn counts(&self) -> impl Iterator<Item = (&ByteString, u64)> {
+        self.0.iter().map(|(value, &count)| (value, count))
+    }
+}
+
+impl Commute for FTable {
+    fn merge(&mut self, other: FTable) {
+        for (value, count) in other.0 {
+            *self.0.entry(value).or_insert(0) += count;
+        }
+    }
+}
+
+impl Table for FTable {
+    fn with_args(_: &Args) -> FTable {
+        FTable::default()
+    }
+
+    #[inline]
+    fn add(&mut self, value: ByteString) {
+        *self.0.entry(value).or_insert(0) += 1;
+    }
+
+    fn sorted(&self, asc: bool) -> (Vec<(&ByteString, u64, u64)>, u64) {
+        let mut counts: Vec<_> = self.counts().collect();
+        // ties are broken by value, for the cumulative percentages to be
+        // reproducible
+        counts.sort_unstable_by(|a, b| {
+            let by_count = if asc { a.1.cmp(&b.1) } else { b.1.cmp(&a.1) };
+            by_count.then_with(|| a.0.cmp(b.0))
+        });
+        let total = counts.iter().map(|&(_, c)| c).sum();
+        (counts.into_iter().map(|(v, c)| (v, c, 0)).collect(), total)
+    }
+}
+
+impl Table for SpaceSaving<ByteString> {
+    fn with_args(args: &Args) -> SpaceSaving<ByteString> {
+        SpaceSaving::new(args.flag_counters)
+    }
+
+    #[inline]
+    fn add(&mut self, value: ByteString) {
+        SpaceSaving::add(self, value);
+    }
+
+    fn sorted(&self, _asc: bool) -> (Vec<(&ByteString, u64, u64)>, u64) {
+        (self.most_frequent(), self.total())
+    }
+}
 
 impl Args {
+    fn write_tables<T: Table>(&self) -> CliResult<()> {
+        let rconfig = self.rconfig();
+        let mut wtr = Config::new(&self.flag_output).writer()?;
+        let unique = if self.flag_all_unique && self.flag_limit > 0 {
+            self.unique_counts()?
+        } else {
+            None
+        };
+        let (headers, tables, unique) = match unique {
+            None => {
+                let (headers, tables) = self.compute_tables::<T>()?;
+                (headers, tables, vec![])
+            }
+            Some((headers, sel, unique)) => {
+                // only the columns whose values aren't all unique are counted
+                let counted: Vec<_> = sel
+                    .iter()
+                    .zip(&unique)
+                    .filter(|(_, count)| count.is_none())
+                    .map(|(i, _)| (i + 1).to_string())
+                    .collect();
+                let tables = if counted.is_empty() {
+                    vec![]
+                } else {
+                    let mut counted_args = self.clone();
+                    counted_args.flag_select = SelectColumns::parse(&counted.join(","))?;
+                    counted_args.compute_tables::<T>()?.1
+                };
+                (headers, tables, unique)
+            }
+        };
+
+        let mut header = vec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage",
+        ];
+        if self.flag_approx {
+            header.push("error");
+        }
+        wtr.write_record(header)?;
+        let mut tables = tables.into_iter();
+        for (i, header) in headers.iter().enumerate() {
+            let mut header = header.to_vec();
+            if rconfig.no_headers {
+                header = (i + 1).to_string().into_bytes();
+            }
+            if let Some(&Some(count)) = unique.get(i) {
+                let count = count.to_string();
+                let mut row = vec![&*header, ALL_UNIQUE, count.as_bytes(), b"100.00", b"100.00"];
+                if self.flag_approx {
+                    row.push(b"0");
+                }
+                wtr.write_record(row)?;
+                continue;
+            }
+            let table = match tables.next() {
+                Some(table) => table,
+                None => break,
+            };
+            let (counts, total) = self.counts(&table);
+            let mut cumulative = 0;
+            for (value, count, error) in counts {
+                cumulative = total.min(cumulative + count);
+                let pct = format!("{:.2}", percentage(count, total));
+                let cumulative_pct = format!("{:.2}", percentage(cumulative, total));
+                let (count, error) = (count.to_string(), error.to_string());
+                let mut row = vec![
+                    &*header,
+                    &*value,
+                    count.as_bytes(),
+                    pct.as_bytes(),
+                    cumulative_pct.as_bytes(),
+                ];
+                if self.flag_approx {
+                    row.push(error.as_bytes());
+                }
+                wtr.write_record(row)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn rconfig(&self) -> Config {
         Config::new(&self.arg_input)
             .delimiter(self.flag_delimiter)
@@ -113,37 +297,93 @@ impl Args {
             .select(self.flag_select.clone())
     }
 
-    fn counts(&self, ftab: &FTable) -> Vec<(ByteString, u64)> {
-        let mut counts = if self.flag_asc {
-            ftab.least_frequent()
-        } else {
-            ftab.most_frequent()
+    /// The headers of the selected columns, their positions and the number
+    /// of values of those whose values are all unique, as per the stats
+    /// cache. `None` if there's no (valid) stats cache.
+    fn unique_counts(&self) -> CliResult<Option<(Headers, Selection, UniqueCounts)>> {
+        let rconfig = self.rconfig();
+        let cache = match StatsCache::of(&rconfig)? {
+            None => return Ok(None),
+            Some(cache) => cache,
         };
-        if self.flag_limit > 0 {
-            counts = counts.into_iter().take(self.flag_limit).collect();
+        let (headers, sel) = self.sel_headers(&mut rconfig.reader()?)?;
+        let unique = sel
+            .iter()
+            .map(|&i| cache.unique_count(i, self.flag_no_nulls))
+            .collect();
+        Ok(Some((headers, sel, unique)))
+    }
+
+    fn compute_tables<T: Table>(&self) -> CliResult<(Headers, Vec<T>)> {
+        match self.rconfig().indexed()? {
+            Some(ref mut idx) if self.njobs() > 1 => self.parallel_tables(idx),
+            _ => self.sequential_tables(),
         }
-        counts
-            .into_iter()
-            .map(|(bs, c)| {
+    }
+
+    /// The values of a column to report as per the limits, with their counts
+    /// and errors, followed by the sum of the counts of the values left out,
+    /// if any, and the total count of all the values.
+    fn counts<T: Table>(&self, table: &T) -> (Vec<(ByteString, u64, u64)>, u64) {
+        let (counts, total) = table.sorted(self.flag_asc);
+        let limit = match self.flag_limit {
+            0 => counts.len(),
+            limit => limit,
+        };
+        let mut rows: Vec<_> = counts
+            .iter()
+            .filter(|&&(_, c, _)| match self.flag_pct_limit {
+                Some(pct_limit) => percentage(c, total) >= pct_limit,
+                None => true,
+            })
+            .take(limit)
+            .map(|&(bs, c, e)| {
                 if b"" == &**bs {
-                    (b"(NULL)"[..].to_vec(), c)
+                    (b"(NULL)"[..].to_vec(), c, e)
                 } else {
-                    (bs.clone(), c)
+                    (bs.clone(), c, e)
                 }
             })
-            .collect()
+            .collect();
+
+        // The listed counts may be overestimated by their errors, so the
+        // other values may be underestimated by as much.
+        let listed: u64 = rows.iter().map(|&(_, c, e)| c - e).sum();
+        let error: u64 = rows.iter().map(|&(_, _, e)| e).sum();
+        let others = counts.len() - rows.len();
+        let count = total.saturating_sub(listed);
+        if count > 0 {
+            let value = if self.flag_approx {
+                "Other".to_string()
+            } else {
+                format!("Other ({})", others)
+            };
+            rows.push((value.into_bytes(), count, error));
+        }
+        (rows, total)
     }
 
     pub fn sequential_ftables(&self) -> CliResult<(Headers, FTables)> {
-        let mut rdr = self.rconfig().reader()?;
-        let (headers, sel) = self.sel_headers(&mut rdr)?;
-        Ok((headers, self.ftables(&sel, rdr.byte_records())?))
+        self.sequential_tables()
     }
 
     pub fn parallel_ftables(
         &self,
         idx: &mut Indexed<fs::File, fs::File>,
     ) -> CliResult<(Headers, FTables)> {
+        self.parallel_tables(idx)
+    }
+
+    fn sequential_tables<T: Table>(&self) -> CliResult<(Headers, Vec<T>)> {
+        let mut rdr = self.rconfig().reader()?;
+        let (headers, sel) = self.sel_headers(&mut rdr)?;
+        Ok((headers, self.tables(&sel, rdr.byte_records())?))
+    }
+
+    fn parallel_tables<T: Table>(
+        &self,
+        idx: &mut Indexed<fs::File, fs::File>,
+    ) -> CliResult<(Headers, Vec<T>)> {
         let mut rdr = self.rconfig().reader()?;
         let (headers, sel) = self.sel_headers(&mut rdr)?;
 
@@ -162,20 +402,27 @@ impl Args {
                 let mut idx = args.rconfig().indexed().unwrap().unwrap();
                 idx.seek((i * chunk_size) as u64).unwrap();
                 let it = idx.byte_records().take(chunk_size);
-                send.send(args.ftables(&sel, it).unwrap()).unwrap();
+                send.send((i, args.tables::<T, _>(&sel, it).unwrap()))
+                    .unwrap();
             });
         }
         drop(send);
-        Ok((headers, merge_all(recv.iter()).unwrap()))
+        // the chunks are merged in order, as approximate counts depend on it
+        let chunks = recv.iter().sorted_by_key(|(i, _)| *i);
+        Ok((
+            headers,
+            merge_all(chunks.map(|(_, tables)| tables)).unwrap(),
+        ))
     }
 
-    fn ftables<I>(&self, sel: &Selection, it: I) -> CliResult<FTables>
+    fn tables<T, I>(&self, sel: &Selection, it: I) -> CliResult<Vec<T>>
     where
+        T: Table,
         I: Iterator<Item = csv::Result<csv::ByteRecord>>,
     {
         let null = &b""[..].to_vec();
         let nsel = sel.normal();
-        let mut tabs: Vec<_> = (0..nsel.len()).map(|_| Frequencies::new()).collect();
+        let mut tabs: Vec<_> = (0..nsel.len()).map(|_| T::with_args(self)).collect();
         for row in it {
             let row = row?;
             for (i, field) in nsel.select(row.into_iter()).enumerate() {
@@ -210,6 +457,14 @@ impl Args {
     }
 }
 
+fn percentage(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
 fn trim(bs: ByteString) -> ByteString {
     match String::from_utf8(bs) {
         Ok(s) => s.trim().as_bytes().to_vec(),
@@ -0,0 +1,381 @@
+use std::cmp::Ordering;
+use std::str;
+
+use tabwriter::TabWriter;
+
+use crate::cmd::stats::OutputFormat;
+use crate::config::{Config, Delimiter};
+use crate::numeric;
+use crate::select::SelectColumns;
+use crate::util;
+use crate::CliResult;
+use serde::Deserialize;
+
+static USAGE: &str = "
+Computes histograms of the numeric columns of CSV data.
+
+The values of each numeric column (Integer or Float, as inferred by 'qsv stats')
+are binned and counted, and other columns are skipped. By default, there are
+bins of equal width between the min and the max of the column. With --quantiles,
+the bins are between quantiles instead, so that they have about the same count,
+and with --edges, they are between the given values.
+
+The histograms are formatted as CSV data:
+
+    field,bin_start,bin_end,count,percent
+
+where percent is the percentage of the values of the column in the bin. The
+bins include their start but not their end, except the last one which includes
+both. Values outside of the given --edges aren't counted in any bin.
+
+They can also be rendered in the terminal, as a table with a bar for each bin
+with --bars, or as a sparkline for each column with --sparkline:
+
+  $ qsv histogram --bins 20 --sparkline data.csv
+
+Usage:
+    qsv histogram [options] [<input>]
+
+histogram options:
+    -s, --select <arg>     Select a subset of columns to compute histograms
+                           for. See 'qsv select --help' for the format details.
+    -b, --bins <n>         The number of bins of each column.
+                           [default: 10]
+    --quantiles            Make bins between quantiles, with about the same
+                           number of values. The bins of repeated values are
+                           merged, so there can be fewer bins.
+                           This requires storing the values in memory.
+    --edges <list>         Make bins between the given comma-separated edges,
+                           e.g. '0,10,100,1000', for all the columns.
+    --log                  Make bins of equal width on a log10 scale, e.g.
+                           1-10, 10-100, 100-1000. The values must be positive.
+    --bars                 Render the histograms as a table with bars.
+    --sparkline            Render each histogram as a sparkline.
+    --bar-width <n>        The width of the longest bar of each histogram.
+                           [default: 40]
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will NOT be interpreted
+                           as column names. i.e., They will be included
+                           in the histograms.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+";
+
+#[derive(Clone, Deserialize)]
+struct Args {
+    arg_input: Option<String>,
+    flag_select: SelectColumns,
+    flag_bins: usize,
+    flag_quantiles: bool,
+    flag_edges: Option<String>,
+    flag_log: bool,
+    flag_bars: bool,
+    flag_sparkline: bool,
+    flag_bar_width: usize,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+/// The eighths of a block, to draw bars and sparklines.
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const PARTIAL_BARS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_bins == 0 {
+        return fail!("The number of bins must be at least 1.");
+    }
+    if [
+        args.flag_quantiles,
+        args.flag_edges.is_some(),
+        args.flag_log,
+    ]
+    .iter()
+    .filter(|&&b| b)
+    .count()
+        > 1
+    {
+        return fail!("Only one of --quantiles, --edges and --log can be used.");
+    }
+    if args.flag_bars && args.flag_sparkline {
+        return fail!("Only one of --bars and --sparkline can be used.");
+    }
+    let edges = match args.flag_edges {
+        Some(ref edges) => Some(parse_edges(edges)?),
+        None => None,
+    };
+
+    let mut histograms = args.histograms(edges)?;
+    let rconfig = args.rconfig();
+    let mut rdr = rconfig.reader()?;
+    let sel = rconfig.selection(rdr.byte_headers()?)?;
+    let mut record = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut record)? {
+        for (histogram, field) in histograms.iter_mut().zip(sel.select(&record)) {
+            if let Some(histogram) = histogram {
+                let value = str::from_utf8(field).ok().and_then(numeric::parse_float);
+                if let Some(value) = value {
+                    histogram.add(value);
+                }
+            }
+        }
+    }
+    let histograms: Vec<Histogram> = histograms
+        .into_iter()
+        .flatten()
+        .map(|mut h| {
+            h.finish(args.flag_bins);
+            h
+        })
+        .collect();
+
+    if args.flag_bars || args.flag_sparkline {
+        let wconfig = Config::new(&args.flag_output).delimiter(Some(Delimiter::from_byte(b'\t')));
+        let mut wtr = wconfig.from_writer(TabWriter::new(wconfig.io_writer()?));
+        if args.flag_sparkline {
+            wtr.write_record(["field", "min", "max", "histogram"])?;
+            for h in &histograms {
+                let (min, max) = (h.edges[0], h.edges[h.edges.len() - 1]);
+                let sparkline = h.sparkline();
+                wtr.write_record([&h.field, &min.to_string(), &max.to_string(), &sparkline])?;
+            }
+        } else {
+            wtr.write_record(["field", "bin_start", "bin_end", "count", "percent", "bar"])?;
+            for h in &histograms {
+                for (mut row, bar) in h.rows().zip(h.bars(args.flag_bar_width)) {
+                    row.push(bar);
+                    wtr.write_record(&row)?;
+                }
+            }
+        }
+        wtr.flush()?;
+        return Ok(());
+    }
+
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+    wtr.write_record(["field", "bin_start", "bin_end", "count", "percent"])?;
+    for h in &histograms {
+        for row in h.rows() {
+            wtr.write_record(&row)?;
+        }
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Parses ascending comma-separated bin edges.
+fn parse_edges(s: &str) -> CliResult<Vec<f64>> {
+    let mut edges = Vec::new();
+    for edge in s.split(',') {
+        match edge.trim().parse::<f64>() {
+            Ok(edge) if edge.is_finite() => edges.push(edge),
+            _ => return fail!(format!("Invalid edge '{edge}' in --edges.")),
+        }
+    }
+    if edges.len() < 2 || edges.windows(2).any(|w| w[0] >= w[1]) {
+        return fail!("The --edges must be at least 2 increasing numbers.");
+    }
+    Ok(edges)
+}
+
+impl Args {
+    fn rconfig(&self) -> Config {
+        Config::new(&self.arg_input)
+            .delimiter(self.flag_delimiter)
+            .no_headers(self.flag_no_headers)
+            .select(self.flag_select.clone())
+    }
+
+    /// The empty histograms of the selected columns, or None for the columns
+    /// that aren't numeric, with their bins from the min and max of `stats`,
+    /// except with --quantiles.
+    fn histograms(&self, edges: Option<Vec<f64>>) -> CliResult<Vec<Option<Histogram>>> {
+        let stats_args = crate::cmd::stats::Args {
+            arg_input: self.arg_input.clone(),
+            flag_select: self.flag_select.clone(),
+            flag_groupby: None,
+            flag_everything: false,
+            flag_mode: false,
+            flag_cardinality: false,
+            flag_median: false,
+            flag_quartiles: false,
+            flag_percentiles: None,
+            flag_moments: false,
+            flag_mad: false,
+            flag_cv: false,
+            flag_means: false,
+            flag_signs: false,
+            flag_sort_order: false,
+            flag_approx: false,
+            flag_nulls: false,
+            flag_nullcount: false,
+            flag_dates: false,
+            flag_cache: false,
+            flag_no_cache: false,
+            flag_jobs: util::max_jobs() as isize,
+            flag_output_format: OutputFormat::Csv,
+            flag_output: None,
+            flag_no_headers: self.flag_no_headers,
+            flag_delimiter: self.flag_delimiter,
+        };
+        let (headers, values) = stats_args.stats_values()?;
+
+        let mut histograms = Vec::with_capacity(values.len());
+        for stats in stats_args.typed_stats(&headers, &values) {
+            let typ = stats["type"].as_str().unwrap_or_default();
+            let (min, max) = match (stats["min"].as_f64(), stats["max"].as_f64()) {
+                (Some(min), Some(max)) if typ == "Integer" || typ == "Float" => (min, max),
+                _ => {
+                    histograms.push(None);
+                    continue;
+                }
+            };
+            let field = stats["field"].as_str().unwrap_or_default().to_owned();
+            let edges = match edges {
+                Some(ref edges) => edges.clone(),
+                None if self.flag_quantiles => vec![],
+                None if self.flag_log => {
+                    if min <= 0.0 {
+                        return fail!(format!(
+                            "--log requires positive values, but the min of {field} is {min}."
+                        ));
+                    }
+                    let edges = equal_width_edges(min.log10(), max.log10(), self.flag_bins);
+                    let mut edges: Vec<f64> = edges.into_iter().map(|e| 10f64.powf(e)).collect();
+                    // without rounding errors
+                    edges[0] = min;
+                    *edges.last_mut().unwrap() = max;
+                    edges
+                }
+                None => equal_width_edges(min, max, self.flag_bins),
+            };
+            histograms.push(Some(Histogram {
+                field,
+                edges,
+                counts: vec![],
+                total: 0,
+                values: vec![],
+            }));
+        }
+        Ok(histograms)
+    }
+}
+
+/// The edges of `bins` bins of equal width between min and max, or of a
+/// single bin if they're equal.
+fn equal_width_edges(min: f64, max: f64, bins: usize) -> Vec<f64> {
+    if min >= max {
+        return vec![min, max];
+    }
+    let width = (max - min) / bins as f64;
+    let mut edges: Vec<f64> = (0..bins).map(|i| min + width * i as f64).collect();
+    edges.push(max);
+    edges
+}
+
+struct Histogram {
+    field: String,
+    /// The edges of the bins, which are only known once all the values have
+    /// been seen with --quantiles.
+    edges: Vec<f64>,
+    counts: Vec<u64>,
+    total: u64,
+    /// The values, with --quantiles.
+    values: Vec<f64>,
+}
+
+impl Histogram {
+    fn add(&mut self, value: f64) {
+        if self.edges.is_empty() {
+            self.values.push(value);
+            return;
+        }
+        if self.counts.is_empty() {
+            self.counts = vec![0; self.edges.len() - 1];
+        }
+        self.total += 1;
+        if let Some(i) = self.bin(value) {
+            self.counts[i] += 1;
+        }
+    }
+
+    /// The index of the bin of `value`, if it's between the edges.
+    fn bin(&self, value: f64) -> Option<usize> {
+        let last = self.edges.len() - 1;
+        if value < self.edges[0] || value > self.edges[last] {
+            return None;
+        }
+        let i = self.edges.partition_point(|&e| e <= value);
+        Some(i.saturating_sub(1).min(last - 1))
+    }
+
+    /// Bins the values between quantiles, with --quantiles.
+    fn finish(&mut self, bins: usize) {
+        if !self.edges.is_empty() || self.values.is_empty() {
+            if self.counts.is_empty() {
+                self.counts = vec![0; self.edges.len().saturating_sub(1)];
+            }
+            return;
+        }
+        let mut values = std::mem::take(&mut self.values);
+        values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let last = values.len() - 1;
+        let mut edges: Vec<f64> = (0..=bins)
+            .map(|i| values[(i * last + bins / 2) / bins])
+            .collect();
+        edges.dedup();
+        if edges.len() == 1 {
+            edges.push(edges[0]);
+        }
+        self.edges = edges;
+        for value in values {
+            self.add(value);
+        }
+    }
+
+    /// The CSV rows of the bins.
+    fn rows(&self) -> impl Iterator<Item = Vec<String>> + '_ {
+        self.edges
+            .windows(2)
+            .zip(&self.counts)
+            .map(move |(edges, &count)| {
+                let percent = if self.total == 0 {
+                    0.0
+                } else {
+                    count as f64 * 100.0 / self.total as f64
+                };
+                vec![
+                    self.field.clone(),
+                    edges[0].to_string(),
+                    edges[1].to_string(),
+                    count.to_string(),
+                    format!("{percent:.2}"),
+                ]
+            })
+    }
+
+    /// The bars of the bins, scaled to the largest count.
+    fn bars(&self, width: usize) -> impl Iterator<Item = String> + '_ {
+        let max = self.counts.iter().copied().max().unwrap_or_default().max(1);
+        self.counts.iter().map(move |&count| {
+            let eighths = (count * width as u64 * 8 + max / 2) / max;
+            let mut bar = BLOCKS[8].to_string().repeat((eighths / 8) as usize);
+            bar.push_str(PARTIAL_BARS[(eighths % 8) as usize]);
+            bar
+        })
+    }
+
+    /// A sparkline of the counts of the bins, scaled to the largest count.
+    fn sparkline(&self) -> String {
+        let max = self.counts.iter().copied().max().unwrap_or_default().max(1);
+        let level = |count: u64| ((count * 8 + max - 1) / max) as usize;
+        self.counts
+            .iter()
+            .map(|&count| BLOCKS[level(count)])
+            .collect()
+    }
+}
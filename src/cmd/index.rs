@@ -2,11 +2,12 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
-use csv_index::RandomAccessSimple;
-
 use crate::config::{Config, Delimiter};
+use crate::index::{append_index, create_index, create_key_index, IndexHeader};
+use crate::select::SelectColumns;
 use crate::util;
 use crate::CliResult;
+use log::info;
 use serde::Deserialize;
 
 static USAGE: &str = "
@@ -15,9 +16,25 @@ slicing, splitting and gathering statistics much faster.
 
 Note that this does not accept CSV data on stdin. You must give a file
 path. The index is created at 'path/to/input.csv.idx'. The index will be
-automatically used by commands that can benefit from it. If the original CSV
-data changes after the index is made, commands that try to use it will result
-in an error (you have to regenerate the index before it can be used again).
+automatically used by commands that can benefit from it.
+
+The index records the size of the CSV data, a hash of its start and end, its
+delimiter and its number of columns. If the CSV data changes after the index
+is made, or is read with another delimiter, commands that try to use the index
+will result in an error explaining why the index was rejected (you have to
+regenerate the index before it can be used again).
+
+With --column, a key index of the values of the given column(s) is created
+instead, at 'path/to/input.csv.kidx'. It's used by the 'lookup' command (and
+by 'search' and 'join' with --use-index) to find the rows with a given key
+without scanning the CSV data.
+
+For CSV data that only grows by appending rows (e.g. logs), use --append to
+update an existing index by only indexing the new rows. If the CSV data was
+changed in any other way, the index is re-created from scratch instead.
+
+Set the QSV_AUTOINDEX_SIZE environment variable to have files larger than
+that size (in bytes) indexed automatically, and stale indexes rebuilt.
 
 Usage:
     qsv index [options] <input>
@@ -28,31 +45,90 @@ index options:
                            Generally, this is not currently useful because
                            the only way to use an index is if it is specially
                            named <input>.idx.
+    -c, --column <sel>     Create a key index of the values of the selected
+                           column(s) at <input>.kidx instead.
+                           See 'qsv select --help' for the format details.
+    -a, --append           Update the existing index of CSV data that was only
+                           appended to since it was indexed, by only indexing
+                           the new rows. Can't be combined with --column.
 
 Common options:
     -h, --help             Display this message
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers, and is indexed by --column too.
     -d, --delimiter <arg>  The field delimiter for reading CSV data.
-                           Must be a single character. (default: ,)
+                           Must be a single ASCII character, as data with
+                           multi-byte delimiters can't be indexed. (default: ,)
 ";
 
 #[derive(Deserialize)]
 struct Args {
     arg_input: String,
     flag_output: Option<String>,
+    flag_column: Option<SelectColumns>,
+    flag_no_headers: bool,
+    flag_append: bool,
     flag_delimiter: Option<Delimiter>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
 
+    if util::is_url(&args.arg_input) {
+        return fail!("Cannot index a URL. Please download the file first.");
+    }
     let pidx = match args.flag_output {
+        None if args.flag_column.is_some() => util::key_idx_path(Path::new(&args.arg_input)),
         None => util::idx_path(Path::new(&args.arg_input)),
         Some(p) => PathBuf::from(&p),
     };
 
-    let rconfig = Config::new(&Some(args.arg_input)).delimiter(args.flag_delimiter);
+    if args.flag_append && args.flag_column.is_some() {
+        return fail!("--append can't be combined with --column.");
+    }
+
+    let mut rconfig = Config::new(&Some(args.arg_input.clone()))
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
     let mut rdr = rconfig.reader_file()?;
-    let mut wtr = io::BufWriter::new(fs::File::create(&pidx)?);
-    RandomAccessSimple::create(&mut rdr, &mut wtr)?;
-    Ok(())
+    let mut csv_data = fs::File::open(&args.arg_input)?;
+    let header = IndexHeader::describe(&mut csv_data, &mut rdr, rconfig.get_delimiter())?;
+    if args.flag_append {
+        if append(&pidx, &header, &mut csv_data, &mut rdr)? {
+            return Ok(());
+        }
+        info!("re-creating index {}", pidx.display());
+    }
+    let wtr = io::BufWriter::new(fs::File::create(&pidx)?);
+    match args.flag_column {
+        None => create_index(&header, &mut rdr, wtr),
+        Some(column) => {
+            rconfig = rconfig.select(column);
+            let key = rconfig.selection(rdr.byte_headers()?)?;
+            create_key_index(&header, &key, &mut rdr, wtr)
+        }
+    }
+}
+
+/// Update the existing index `pidx` of the CSV data described by `header`,
+/// if the CSV data was only appended to since. Returns `false` if the index
+/// has to be re-created instead.
+fn append<R: io::Read + io::Seek>(
+    pidx: &Path,
+    header: &IndexHeader,
+    csv_data: &mut fs::File,
+    rdr: &mut csv::Reader<R>,
+) -> CliResult<bool> {
+    let mut idx = match fs::OpenOptions::new().read(true).write(true).open(pidx) {
+        Ok(idx) => idx,
+        Err(_) => return Ok(false),
+    };
+    let old = match IndexHeader::read(&mut idx) {
+        Ok(old) => old,
+        Err(_) => return Ok(false),
+    };
+    if !old.is_prefix_of(csv_data, header)? {
+        return Ok(false);
+    }
+    append_index(header, rdr, &mut idx)
 }
@@ -9,7 +9,12 @@ Read CSV data with special quoting rules.
 Generally, all qsv commands support basic options like specifying the delimiter
 used in CSV data. This does not cover all possible types of CSV data. For
 example, some CSV files don't use '\"' for quotes or use different escaping
-styles.
+styles. Others use multi-byte field delimiters or record terminators
+(e.g. '¦', '§', '||' or '~|~'), which can be converted to standard CSV with
+the --delimiter and --terminator options.
+
+Data in a legacy character encoding (e.g. windows-1252, iso-8859-15 or
+shift_jis) can be converted to UTF-8 with the --encoding option.
 
 Usage:
     qsv input [options] [<input>]
@@ -19,12 +24,16 @@ input options:
     --escape <arg>         The escape character to use. When not specified,
                            quotes are escaped by doubling them.
     --no-quoting           Disable quoting completely.
+    --terminator <arg>     The record terminator to use. When not specified,
+                           '\\n' and '\\r\\n' are both recognized.
+    --encoding <arg>       The character encoding of the input. Use 'auto'
+                           to detect it. Overrides QSV_INPUT_ENCODING.
 
 Common options:
     -h, --help             Display this message
     -o, --output <file>    Write output to <file> instead of stdout.
     -d, --delimiter <arg>  The field delimiter for reading CSV data.
-                           Must be a single character. (default: ,)
+                           Can be a multi-byte sequence. (default: ,)
 ";
 
 #[derive(Deserialize)]
@@ -35,12 +44,19 @@ struct Args {
     flag_quote: Delimiter,
     flag_escape: Option<Delimiter>,
     flag_no_quoting: bool,
+    flag_terminator: Option<Delimiter>,
+    flag_encoding: Option<String>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_quote.is_multi_byte() || args.flag_escape.map_or(false, Delimiter::is_multi_byte) {
+        return fail!("The quote and escape characters must be a single ASCII character.");
+    }
     let mut rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
+        .record_terminator(args.flag_terminator)
+        .input_encoding(args.flag_encoding)
         .no_headers(true)
         .quote(args.flag_quote.as_byte());
     let wconfig = Config::new(&args.flag_output);
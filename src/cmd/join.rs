@@ -1,5 +1,6 @@
 use std::collections::hash_map::Entry;
 use std::fmt;
+use std::fs;
 use std::io;
 use std::iter::repeat;
 use std::str;
@@ -8,7 +9,7 @@ use ahash::AHashMap;
 use byteorder::{BigEndian, WriteBytesExt};
 
 use crate::config::{Config, Delimiter, SeekRead};
-use crate::index::Indexed;
+use crate::index::{Indexed, KeyIndexed};
 use crate::select::{SelectColumns, Selection};
 use crate::util;
 use crate::CliResult;
@@ -70,6 +71,11 @@ join options:
                            Otherwise, empty fields are completely ignored.
                            (In fact, any row that has an empty field in the
                            key specified is ignored.)
+    --use-index            Look up the rows of <input1> in the key index of
+                           <input2> (see 'qsv index --column'), which must
+                           be on <columns2>, instead of reading all of
+                           <input2> into memory. Only works for inner, left,
+                           left-anti and left-semi joins.
 
 Common options:
     -h, --help             Display this message
@@ -100,11 +106,41 @@ struct Args {
     flag_no_case: bool,
     flag_nulls: bool,
     flag_delimiter: Option<Delimiter>,
+    flag_use_index: bool,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
     let mut state = args.new_io_state()?;
+    if args.flag_use_index {
+        let mut keyidx = args.key_indexed(&state.sel2)?;
+        return match (
+            args.flag_left,
+            args.flag_left_anti,
+            args.flag_left_semi,
+            args.flag_right,
+            args.flag_full,
+            args.flag_cross,
+        ) {
+            (true, false, false, false, false, false) => {
+                state.write_headers()?;
+                state.key_index_join(&mut keyidx, true)
+            }
+            (false, true, false, false, false, false) => {
+                state.write_headers1()?;
+                state.key_index_left_join(&mut keyidx, true)
+            }
+            (false, false, true, false, false, false) => {
+                state.write_headers1()?;
+                state.key_index_left_join(&mut keyidx, false)
+            }
+            (false, false, false, false, false, false) => {
+                state.write_headers()?;
+                state.key_index_join(&mut keyidx, false)
+            }
+            _ => fail!("--use-index only works for inner, left, left-anti and left-semi joins."),
+        };
+    }
     match (
         args.flag_left,
         args.flag_left_anti,
@@ -233,30 +269,48 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
         Ok(())
     }
 
-    fn left_join(mut self, anti: bool) -> CliResult<()> {
+    fn left_join(self, anti: bool) -> CliResult<()> {
         let validx = ValueIndex::new(self.rdr2, &self.sel2, self.casei, self.nulls)?;
-        let mut first_row: bool = true;
+        let (sel1, casei) = (self.sel1, self.casei);
+        left_join_with(self.rdr1, self.wtr, anti, |row| {
+            Ok(validx.values.contains_key(&get_row_key(&sel1, row, casei)))
+        })
+    }
+
+    /// Like `outer_join(false)` (if `left` is set) or `inner_join`, but
+    /// looks up the rows of rdr1 in the key index of rdr2.
+    fn key_index_join<I: io::Read + io::Seek>(
+        mut self,
+        keyidx: &mut KeyIndexed<fs::File, I>,
+        left: bool,
+    ) -> CliResult<()> {
+        let (_, pad2) = self.get_padding()?;
         for row in self.rdr1.byte_records() {
             let row = row?;
-            let key = get_row_key(&self.sel1, &row, self.casei);
-            if validx.values.get(&key).is_none() {
-                if anti {
-                    self.wtr.write_record(&row)?;
-                }
-            } else if !anti {
-                // semi_join
-                if !first_row {
-                    // since the first row in a left-semi is
-                    // the header, even if no-header is on
-                    self.wtr.write_record(&row)?;
-                } else {
-                    first_row = false;
-                }
+            let rows2 = key_index_rows(keyidx, &self.sel1, &row, self.casei, self.nulls)?;
+            if rows2.is_empty() && left {
+                self.wtr.write_record(row.iter().chain(&pad2))?;
+            }
+            for row2 in rows2 {
+                self.wtr.write_record(row.iter().chain(&row2))?;
             }
         }
         Ok(())
     }
 
+    /// Like `left_join`, but looks up the rows of rdr1 in the key index of
+    /// rdr2.
+    fn key_index_left_join<I: io::Read + io::Seek>(
+        self,
+        keyidx: &mut KeyIndexed<fs::File, I>,
+        anti: bool,
+    ) -> CliResult<()> {
+        let (sel1, casei, nulls) = (self.sel1, self.casei, self.nulls);
+        left_join_with(self.rdr1, self.wtr, anti, |row| {
+            Ok(!key_index_rows(keyidx, &sel1, row, casei, nulls)?.is_empty())
+        })
+    }
+
     fn full_outer_join(mut self) -> CliResult<()> {
         let mut scratch = csv::ByteRecord::new();
         let (pad1, pad2) = self.get_padding()?;
@@ -324,7 +378,73 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
     }
 }
 
+fn left_join_with<R: io::Read, W: io::Write, F>(
+    mut rdr1: csv::Reader<R>,
+    mut wtr: csv::Writer<W>,
+    anti: bool,
+    mut has_match: F,
+) -> CliResult<()>
+where
+    F: FnMut(&csv::ByteRecord) -> CliResult<bool>,
+{
+    let mut first_row: bool = true;
+    for row in rdr1.byte_records() {
+        let row = row?;
+        if !has_match(&row)? {
+            if anti {
+                wtr.write_record(&row)?;
+            }
+        } else if !anti {
+            // semi_join
+            if !first_row {
+                // since the first row in a left-semi is
+                // the header, even if no-header is on
+                wtr.write_record(&row)?;
+            } else {
+                first_row = false;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The rows of a key index matching the key of `row`. As with `ValueIndex`,
+/// keys with empty fields never match, unless `nulls` is set.
+fn key_index_rows<I: io::Read + io::Seek>(
+    keyidx: &mut KeyIndexed<fs::File, I>,
+    sel: &Selection,
+    row: &csv::ByteRecord,
+    casei: bool,
+    nulls: bool,
+) -> CliResult<Vec<csv::ByteRecord>> {
+    let key = get_row_key(sel, row, false);
+    if !nulls && key.iter().any(|f| f.is_empty()) {
+        return Ok(vec![]);
+    }
+    let key: Vec<&[u8]> = key.iter().map(Vec::as_slice).collect();
+    keyidx.lookup(&key, casei)
+}
+
 impl Args {
+    fn key_indexed(&self, sel2: &Selection) -> CliResult<KeyIndexed<fs::File, fs::File>> {
+        let rconf2 = Config::new(&Some(self.arg_input2.clone()))
+            .delimiter(self.flag_delimiter)
+            .no_headers(self.flag_no_headers);
+        match rconf2.key_indexed(None)? {
+            Some(keyidx) if keyidx.key() == &**sel2 => Ok(keyidx),
+            Some(_) => fail!(format!(
+                "The key index of {} is not on <columns2>. Please re-create it with \
+                 'qsv index --column <columns2> {}'.",
+                self.arg_input2, self.arg_input2
+            )),
+            None => fail!(format!(
+                "No key index found for {}. Please create it with \
+                 'qsv index --column <columns2> {}'.",
+                self.arg_input2, self.arg_input2
+            )),
+        }
+    }
+
     fn new_io_state(
         &self,
     ) -> CliResult<IoState<Box<dyn SeekRead + 'static>, Box<dyn io::Write + 'static>>> {
@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use crate::config::{Config, Delimiter};
+use crate::util;
+use crate::CliResult;
+use serde::Deserialize;
+
+static USAGE: &str = "
+Returns the rows whose key column(s) match the given key(s), using the key
+index created with 'qsv index --column', without scanning the CSV data.
+
+The rows are written in the order of the given keys, and for each key in the
+order they appear in the CSV data. Leading and trailing whitespace is ignored
+when matching keys.
+
+When the key index has several key columns, each key is given as a CSV record
+of their values, e.g. 'US,New York'.
+
+For example, to index a CSV file by its 'id' column and look up two ids:
+
+  $ qsv index --column id data.csv
+  $ qsv lookup data.csv 1234 5678
+
+Usage:
+    qsv lookup [options] <input> <key>...
+    qsv lookup --help
+
+lookup options:
+    -i, --ignore-case      Case insensitive matching of the keys.
+    --index <file>         Use the key index in <file> instead of
+                           <input>.kidx.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. The key index must have been created
+                           with --no-headers too.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single ASCII character, as data with
+                           multi-byte delimiters can't be indexed. (default: ,)
+";
+
+#[derive(Deserialize)]
+struct Args {
+    arg_input: String,
+    arg_key: Vec<String>,
+    flag_ignore_case: bool,
+    flag_index: Option<String>,
+    flag_output: Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter: Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(&Some(args.arg_input.clone()))
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+
+    let mut idx = match rconfig.key_indexed(args.flag_index.as_deref().map(Path::new))? {
+        Some(idx) => idx,
+        None => {
+            return fail!(format!(
+                "No key index found for {}. Please create it with \
+                 'qsv index --column <column> {}'.",
+                args.arg_input, args.arg_input
+            ))
+        }
+    };
+    let mut wtr = Config::new(&args.flag_output).writer()?;
+
+    if !rconfig.no_headers {
+        wtr.write_byte_record(idx.byte_headers()?)?;
+    }
+    for key in &args.arg_key {
+        let values = idx.split_key(key, rconfig.get_delimiter())?;
+        let values: Vec<&[u8]> = values.iter().map(Vec::as_slice).collect();
+        for record in idx.lookup(&values, args.flag_ignore_case)? {
+            wtr.write_byte_record(&record)?;
+        }
+    }
+    Ok(wtr.flush()?)
+}
@@ -2,6 +2,7 @@
 pub mod apply;
 pub mod behead;
 pub mod cat;
+pub mod corr;
 pub mod count;
 pub mod dedup;
 pub mod enumerate;
@@ -19,10 +20,12 @@ pub mod frequency;
 #[cfg(all(feature = "generate", not(feature = "lite")))]
 pub mod generate;
 pub mod headers;
+pub mod histogram;
 pub mod index;
 pub mod input;
 pub mod join;
 pub mod jsonl;
+pub mod lookup;
 #[cfg(all(feature = "lua", not(feature = "lite")))]
 pub mod lua;
 pub mod partition;
@@ -1,4 +1,5 @@
-use crate::cmd::stats::Stats;
+use crate::cmd::frequency::FTables;
+use crate::cmd::stats::OutputFormat;
 use crate::config::{Config, Delimiter};
 use crate::select::SelectColumns;
 use crate::util;
@@ -7,10 +8,9 @@ use crate::CliResult;
 use ahash::AHashMap;
 use csv::ByteRecord;
 use grex::RegExpBuilder;
-use log::{debug, error, info, warn};
+use log::{debug, error, warn};
 use serde::Deserialize;
 use serde_json::{json, value::Number, Map, Value};
-use stats::Frequencies;
 use std::{collections::HashSet, fs::File, io::Write, path::Path};
 
 macro_rules! fail {
@@ -36,7 +36,10 @@ Usage:
     qsv schema [options] [<input>]
 
 Schema options:
-    --enum-threshold NUM       Cardinality threshold for adding enum constraints [default: 50]
+    --enum-threshold NUM       Cardinality threshold for adding enum constraints.
+                               The cardinality is first estimated in bounded
+                               memory, so only the values of the candidate
+                               columns are kept in memory. [default: 50]
     --strict-dates             Enforce Internet Datetime format (RFC-3339) for detected datetime columns
     --pattern-columns <args>   Select columns to add pattern constraints
 
@@ -50,6 +53,10 @@ Common options:
                                Must be a single character. [default: ,]
 ";
 
+/// The keyword that marks the generated schemas, for `validate` to cast the
+/// truthy and falsy values of their boolean fields to booleans.
+pub const TRUTHY_FALSY_KEYWORD: &str = "x-qsv-truthy-falsy";
+
 #[derive(Deserialize, Debug)]
 struct Args {
     flag_enum_threshold: usize,
@@ -109,6 +116,7 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         "$schema": "https://json-schema.org/draft-07/schema",
         "title": format!("JSON Schema for {input_filename}"),
         "description": "Inferred JSON Schema from QSV schema command",
+        TRUTHY_FALSY_KEYWORD: true,
         "type": "object",
         "properties": Value::Object(properties_map),
         "required": Value::Array(required_fields)
@@ -139,15 +147,11 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 #[allow(clippy::len_zero)]
 fn infer_schema_from_stats(args: &Args, input_filename: &str) -> CliResult<Map<String, Value>> {
     // invoke cmd::stats
-    let (csv_fields, csv_stats, stats_col_index_map) = get_stats_records(args)?;
+    let (csv_fields, csv_stats) = get_stats_records(args)?;
 
     // build column selector arg to invoke cmd::frequency with
-    let column_select_arg: String = build_low_cardinality_column_selector_arg(
-        args.flag_enum_threshold,
-        &csv_fields,
-        &csv_stats,
-        &stats_col_index_map,
-    );
+    let column_select_arg: String =
+        build_low_cardinality_column_selector_arg(args.flag_enum_threshold, &csv_stats);
 
     // invoke cmd::frequency to get unique values for each field
     let unique_values_map = get_unique_values(args, &column_select_arg)?;
@@ -156,23 +160,16 @@ fn infer_schema_from_stats(args: &Args, input_filename: &str) -> CliResult<Map<S
     let mut properties_map: Map<String, Value> = Map::new();
 
     // generate definition for each CSV column/field and add to properties_map
-    for i in 0..csv_fields.len() {
-        let header_byte_slice = csv_fields.get(i).unwrap();
+    for (header_byte_slice, stats) in csv_fields.iter().zip(&csv_stats) {
         // convert csv header to string
         let header_string = convert_to_string(header_byte_slice)?;
 
-        // grab stats record for current column
-        let stats_record = csv_stats.get(i).unwrap().clone().to_record();
-
-        debug!("stats[{header_string}]: {stats_record:?}");
+        debug!("stats[{header_string}]: {stats:?}");
 
-        // get Type from stats record
-        let col_type = stats_record.get(stats_col_index_map["type"]).unwrap();
+        // get Type from stats
+        let col_type = stats["type"].as_str().unwrap_or_default();
         // get NullCount
-        let col_null_count = match stats_record.get(stats_col_index_map["nullcount"]) {
-            Some(s) => s.parse::<usize>().unwrap_or(0_usize),
-            None => 0_usize,
-        };
+        let col_null_count = stats["nullcount"].as_u64().unwrap_or(0);
 
         // debug!(
         //     "{header_string}: type={col_type}, optional={}",
@@ -193,8 +190,7 @@ fn infer_schema_from_stats(args: &Args, input_filename: &str) -> CliResult<Map<S
                 type_list.push(Value::String("string".to_string()));
 
                 // minLength constraint
-                if let Some(min_length_str) = stats_record.get(stats_col_index_map["min_length"]) {
-                    let min_length = min_length_str.parse::<u32>().unwrap();
+                if let Some(min_length) = stats["min_length"].as_u64() {
                     field_map.insert(
                         "minLength".to_string(),
                         Value::Number(Number::from(min_length)),
@@ -202,8 +198,7 @@ fn infer_schema_from_stats(args: &Args, input_filename: &str) -> CliResult<Map<S
                 };
 
                 // maxLength constraint
-                if let Some(max_length_str) = stats_record.get(stats_col_index_map["max_length"]) {
-                    let max_length = max_length_str.parse::<u32>().unwrap();
+                if let Some(max_length) = stats["max_length"].as_u64() {
                     field_map.insert(
                         "maxLength".to_string(),
                         Value::Number(Number::from(max_length)),
@@ -234,35 +229,28 @@ fn infer_schema_from_stats(args: &Args, input_filename: &str) -> CliResult<Map<S
             "Integer" => {
                 type_list.push(Value::String("integer".to_string()));
 
-                if let Some(min_str) = stats_record.get(stats_col_index_map["min"]) {
-                    let min = min_str.parse::<i64>().unwrap();
+                if let Some(min) = stats["min"].as_i64() {
                     field_map.insert("minimum".to_string(), Value::Number(Number::from(min)));
                 };
 
-                if let Some(max_str) = stats_record.get(stats_col_index_map["max"]) {
-                    let max = max_str.parse::<i64>().unwrap();
+                if let Some(max) = stats["max"].as_i64() {
                     field_map.insert("maximum".to_string(), Value::Number(Number::from(max)));
                 };
             }
             "Float" => {
                 type_list.push(Value::String("number".to_string()));
 
-                if let Some(min_str) = stats_record.get(stats_col_index_map["min"]) {
-                    let min = min_str.parse::<f64>().unwrap();
-                    field_map.insert(
-                        "minimum".to_string(),
-                        Value::Number(Number::from_f64(min).unwrap()),
-                    );
+                if let Some(min) = stats["min"].as_f64().and_then(Number::from_f64) {
+                    field_map.insert("minimum".to_string(), Value::Number(min));
                 };
 
-                if let Some(max_str) = stats_record.get(stats_col_index_map["max"]) {
-                    let max = max_str.parse::<f64>().unwrap();
-                    field_map.insert(
-                        "maximum".to_string(),
-                        Value::Number(Number::from_f64(max).unwrap()),
-                    );
+                if let Some(max) = stats["max"].as_f64().and_then(Number::from_f64) {
+                    field_map.insert("maximum".to_string(), Value::Number(max));
                 };
             }
+            "Boolean" => {
+                type_list.push(Value::String("boolean".to_string()));
+            }
             "NULL" => {
                 type_list.push(Value::String("null".to_string()));
             }
@@ -300,77 +288,58 @@ fn infer_schema_from_stats(args: &Args, input_filename: &str) -> CliResult<Map<S
     Ok(properties_map)
 }
 
-/// get stats records from cmd::stats
-/// returns tuple (csv_fields, csv_stats, stats_col_index_map)
-fn get_stats_records(args: &Args) -> CliResult<(ByteRecord, Vec<Stats>, AHashMap<String, usize>)> {
+/// get typed stats from cmd::stats
+/// returns tuple (csv_fields, csv_stats), with the stats of each field keyed by name
+fn get_stats_records(args: &Args) -> CliResult<(ByteRecord, Vec<Map<String, Value>>)> {
     let stats_args = crate::cmd::stats::Args {
         arg_input: args.arg_input.clone(),
         flag_select: crate::select::SelectColumns::parse("").unwrap(),
+        flag_groupby: None,
         flag_everything: false,
         flag_mode: false,
         flag_cardinality: true,
         flag_median: false,
         flag_quartiles: false,
+        flag_percentiles: None,
+        flag_moments: false,
+        flag_mad: false,
+        flag_cv: false,
+        flag_means: false,
+        flag_signs: false,
+        flag_sort_order: false,
+        // estimate the cardinality in bounded memory, the enum values are
+        // only collected for the candidate columns
+        flag_approx: true,
         flag_nulls: false,
         flag_nullcount: true,
         flag_dates: true,
+        flag_cache: false,
+        flag_no_cache: false,
         flag_jobs: util::max_jobs() as isize,
+        flag_output_format: OutputFormat::Csv,
         flag_output: None,
         flag_no_headers: args.flag_no_headers,
         flag_delimiter: args.flag_delimiter,
     };
 
-    let (csv_fields, csv_stats) = match stats_args.rconfig().indexed() {
-        Ok(o) => match o {
-            None => {
-                info!("no index, triggering sequential stats");
-                stats_args.sequential_stats()
-            }
-            Some(idx) => {
-                info!("has index, triggering parallel stats");
-                stats_args.parallel_stats(idx)
-            }
-        },
-        Err(e) => {
-            warn!("error determining if indexed, triggering sequential stats: {e}");
-            stats_args.sequential_stats()
-        }
-    }?;
-
-    let stats_columns = stats_args.stat_headers();
-    debug!("stats columns: {stats_columns:?}");
-
-    let mut stats_col_index_map = AHashMap::new();
-
-    for (i, col) in stats_columns.iter().enumerate() {
-        if col != "field" {
-            // need offset by 1 due to extra "field" column in headers that's not in stats records
-            stats_col_index_map.insert(col.to_owned(), i - 1);
-        }
-    }
+    // reuses the stats cache, e.g. of a previous `qsv stats --dates --cache` run
+    let (csv_fields, stats_values) = stats_args.stats_values()?;
+    let csv_stats = stats_args.typed_stats(&csv_fields, &stats_values);
 
-    Ok((csv_fields, csv_stats, stats_col_index_map))
+    Ok((csv_fields, csv_stats))
 }
 
 /// get column selector argument string for low cardinality columns
 fn build_low_cardinality_column_selector_arg(
     enum_cardinality_threshold: usize,
-    csv_fields: &ByteRecord,
-    csv_stats: &[Stats],
-    stats_col_index_map: &AHashMap<String, usize>,
+    csv_stats: &[Map<String, Value>],
 ) -> String {
     let mut low_cardinality_column_indices = Vec::new();
 
     // identify low cardinality columns
-    for i in 0..csv_fields.len() {
-        // grab stats record for current column
-        let stats_record = csv_stats.get(i).unwrap().clone().to_record();
-
+    for (i, stats) in csv_stats.iter().enumerate() {
         // get Cardinality
-        let col_cardinality = match stats_record.get(stats_col_index_map["cardinality"]) {
-            Some(s) => s.parse::<usize>().unwrap_or(0_usize),
-            None => 0_usize,
-        };
+        let col_cardinality = stats["cardinality"].as_u64().unwrap_or(0) as usize;
         // debug!("column_{i}: cardinality={col_cardinality}");
 
         if col_cardinality <= enum_cardinality_threshold {
@@ -401,8 +370,12 @@ fn get_unique_values(
         arg_input: args.arg_input.clone(),
         flag_select: crate::select::SelectColumns::parse(column_select_arg).unwrap(),
         flag_limit: args.flag_enum_threshold,
+        flag_pct_limit: None,
         flag_asc: false,
         flag_no_nulls: true,
+        flag_all_unique: false,
+        flag_approx: false,
+        flag_counters: 0,
         flag_jobs: util::max_jobs() as isize,
         flag_output: None,
         flag_no_headers: args.flag_no_headers,
@@ -414,15 +387,19 @@ fn get_unique_values(
         _ => freq_args.sequential_ftables(),
     }?;
 
-    let unique_values_map = construct_map_of_unique_values(headers, ftables)?;
+    let unique_values_map =
+        construct_map_of_unique_values(headers, ftables, args.flag_enum_threshold)?;
 
     Ok(unique_values_map)
 }
 
-/// construct map of unique values keyed by header
+/// construct map of unique values keyed by header, for the fields with at most
+/// `enum_cardinality_threshold` unique values, as the cardinality from stats is
+/// only an estimate
 fn construct_map_of_unique_values(
     freq_csv_fields: ByteRecord,
-    frequency_tables: Vec<Frequencies<Vec<u8>>>,
+    frequency_tables: FTables,
+    enum_cardinality_threshold: usize,
 ) -> CliResult<AHashMap<String, Vec<String>>> {
     let mut unique_values_map: AHashMap<String, Vec<String>> = AHashMap::new();
 
@@ -430,13 +407,21 @@ fn construct_map_of_unique_values(
     for (i, header_byte_slice) in freq_csv_fields.iter().enumerate() {
         let mut unique_values = Vec::new();
 
-        for (val_byte_vec, _count) in frequency_tables[i].most_frequent() {
+        for (val_byte_vec, _count) in frequency_tables[i].counts() {
             let val_string = convert_to_string(val_byte_vec.as_slice())?;
             unique_values.push(val_string);
         }
 
         let header_string = convert_to_string(header_byte_slice)?;
 
+        if unique_values.len() > enum_cardinality_threshold {
+            debug!(
+                "enum[{header_string}]: len={} is above the threshold",
+                unique_values.len()
+            );
+            continue;
+        }
+
         // sort the values so enum list so schema can be diff'ed between runs
         unique_values.sort();
 
@@ -28,6 +28,12 @@ search options:
     -u, --unicode          Enable unicode support. When enabled, character classes
                            will match all unicode word characters instead of only
                            ASCII word characters. Decreases performance.    
+    --use-index            Look up <regex> in the key index of <input> (see
+                           'qsv index --column') instead of scanning all rows.
+                           <regex> is then not a regex, but the exact value
+                           of the indexed column(s), which --select must
+                           select if given. Can't be combined with
+                           the --invert-match and --flag options.
 
 Common options:
     -h, --help             Display this message
@@ -55,10 +61,14 @@ struct Args {
     flag_unicode: bool,
     flag_ignore_case: bool,
     flag_flag: Option<String>,
+    flag_use_index: bool,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_use_index {
+        return args.search_key_index();
+    }
     let regex_unicode = match env::var("QSV_REGEX_UNICODE") {
         Ok(_) => true,
         Err(_) => args.flag_unicode,
@@ -109,3 +119,41 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     }
     Ok(wtr.flush()?)
 }
+
+impl Args {
+    fn search_key_index(&self) -> CliResult<()> {
+        if self.flag_invert_match || self.flag_flag.is_some() {
+            return fail!("--use-index can't be combined with --invert-match or --flag.");
+        }
+        let rconfig = Config::new(&self.arg_input)
+            .delimiter(self.flag_delimiter)
+            .no_headers(self.flag_no_headers)
+            .select(self.flag_select.clone());
+        let mut idx = match rconfig.key_indexed(None)? {
+            Some(idx) => idx,
+            None => {
+                return fail!(
+                    "No key index found. Please create it with 'qsv index --column <column> \
+                     <input>'."
+                )
+            }
+        };
+        if !self.flag_select.is_empty() {
+            let sel = rconfig.selection(idx.byte_headers()?)?;
+            if idx.key() != &*sel {
+                return fail!("The key index is not on the columns selected by --select.");
+            }
+        }
+        let mut wtr = Config::new(&self.flag_output).writer()?;
+
+        if !rconfig.no_headers {
+            wtr.write_byte_record(idx.byte_headers()?)?;
+        }
+        let key = idx.split_key(&self.arg_regex, rconfig.get_delimiter())?;
+        let key: Vec<&[u8]> = key.iter().map(Vec::as_slice).collect();
+        for record in idx.lookup(&key, self.flag_ignore_case)? {
+            wtr.write_byte_record(&record)?;
+        }
+        Ok(wtr.flush()?)
+    }
+}
@@ -31,10 +31,36 @@ selected using regular expressions.
   $ qsv select /^a/
   $ qsv select '/^.*\d.*$/'
 
+  Select columns using a pattern, where '*' matches any characters and '?'
+  a single character:
+  $ qsv select 'addr_*'
+
+  Select the last column, and the second to last column, with '_':
+  $ qsv select _
+  $ qsv select _-1
+
+  Exclude columns from the selection by prefixing them with '!':
+  $ qsv select 'id,/^addr/,!/^addr_tmp/'
+  When every column is prefixed with '!', they are excluded from all columns:
+  $ qsv select '!id,!name'
+
+  Select columns by their type (as inferred by 'qsv stats --dates'), with
+  ':numeric', ':integer', ':float', ':string', ':boolean', ':date' or ':null'.
+  Like the other selectors, they work with every command that selects columns,
+  but not on piped input:
+  $ qsv select 'id,:numeric'
+  $ qsv stats --select ':numeric' data.csv
+
+  Rename the selected columns in the output with 'as':
+  $ qsv select 'id,name as full_name'
+
   Re-order and duplicate columns arbitrarily:
   $ qsv select 3-1,Header3-Header1,Header1,Foo[2],Header1
 
-  Quote column names that conflict with selector syntax:
+  A column whose name is the whole selector (e.g. 'Price as Reported' or
+  'Total*') is selected by that name, rather than with the alias, pattern or
+  type syntax. Quote other column names that conflict with selector syntax
+  (e.g. a column named '_'):
   $ qsv select '\"Date - Opening\",\"Date - Actual Closing\"'
 
 Usage:
@@ -66,7 +92,7 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
         .no_headers(args.flag_no_headers)
-        .select(args.arg_selection);
+        .select(args.arg_selection.clone());
 
     let mut rdr = rconfig.reader()?;
     let mut wtr = Config::new(&args.flag_output).writer()?;
@@ -75,7 +101,11 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let sel = rconfig.selection(&headers)?;
 
     if !rconfig.no_headers {
-        wtr.write_record(sel.iter().map(|&i| &headers[i]))?;
+        let headers = sel
+            .iter()
+            .enumerate()
+            .map(|(j, &i)| sel.alias(j).map_or(&headers[i], str::as_bytes));
+        wtr.write_record(headers)?;
     }
     let mut record = csv::ByteRecord::new();
     while rdr.read_byte_record(&mut record)? {
@@ -1,6 +1,7 @@
 use std::cmp;
 
 use crate::config::{Config, Delimiter};
+use crate::numeric;
 use crate::select::SelectColumns;
 use crate::util;
 use crate::CliResult;
@@ -21,7 +22,9 @@ Usage:
 sort options:
     -s, --select <arg>     Select a subset of columns to sort.
                            See 'qsv select --help' for the format details.
-    -N, --numeric          Compare according to string numerical value
+    -N, --numeric          Compare according to string numerical value.
+                           Numbers can be locale-formatted, as set by the
+                           QSV_NUMERIC_LOCALE environment variable.
     -R, --reverse          Reverse order
     --random               Random order
     --seed <number>        RNG seed
@@ -62,6 +65,9 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         .delimiter(args.flag_delimiter)
         .no_headers(args.flag_no_headers)
         .select(args.flag_select);
+    if numeric {
+        numeric::init(&rconfig)?;
+    }
 
     let mut rdr = rconfig.reader()?;
 
@@ -194,12 +200,8 @@ where
     xs.next()
         .and_then(|bytes| from_utf8(bytes).ok())
         .and_then(|s| {
-            if let Ok(i) = s.parse::<i64>() {
-                Some(Number::Int(i))
-            } else if let Ok(f) = s.parse::<f64>() {
-                Some(Number::Float(f))
-            } else {
-                None
-            }
+            numeric::parse_int(s)
+                .map(Number::Int)
+                .or_else(|| numeric::parse_float(s).map(Number::Float))
         })
 }
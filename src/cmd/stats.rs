@@ -1,31 +1,45 @@
 use std::borrow::ToOwned;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
 use std::default::Default;
+use std::env;
 use std::fmt;
 use std::fs;
-use std::io;
-use std::iter::{repeat, FromIterator};
-use std::str::{self, FromStr};
+use std::io::{self, Write};
+use std::iter::{self, repeat, FromIterator};
+use std::path::Path;
+use std::str;
 
+use ahash::AHashMap;
 use itertools::Itertools;
-use stats::{merge_all, Commute, MinMax, OnlineStats, Unsorted};
+use stats::{Commute, MinMax, OnlineStats, Unsorted};
 use threadpool::ThreadPool;
 
-use crate::config::{Config, Delimiter};
+use crate::config::{Config, Delimiter, StatsFingerprint};
 use crate::index::Indexed;
+use crate::numeric;
 use crate::select::{SelectColumns, Selection};
+use crate::sketch::{HyperLogLog, TDigest};
 use crate::util;
 use crate::CliResult;
+use chrono::{DateTime, NaiveTime, SecondsFormat, Utc};
 use dateparser::DateTimeUtc;
-use serde::Deserialize;
+use log::{debug, warn};
+use once_cell::sync::OnceCell;
+use serde::de::{Deserializer, Error};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Number, Value};
 
-use self::FieldType::{TDate, TDateTime, TFloat, TInteger, TNull, TString, TUnknown};
+use self::FieldType::{TBoolean, TDate, TDateTime, TFloat, TInteger, TNull, TString, TUnknown};
 
 static USAGE: &str = "
 Computes basic statistics on CSV data.
 
 Basic statistics includes sum, min/max, min/max length, mean, stddev, variance,
-quartiles, median, modes, cardinality & nullcount. Note that some statistics are
-expensive to compute and requires loading the entire file into memory,
+quartiles, median, percentiles, modes, cardinality & nullcount, as well as the
+skewness, kurtosis, median absolute deviation, coefficient of variation, geometric
+and harmonic means, number of zeros/negatives and sort order. Note that some
+statistics are expensive to compute and requires loading the entire file into memory,
 so they must be enabled explicitly. 
 
 By default, the following statistics are reported for *every* column in the CSV data:
@@ -33,13 +47,63 @@ sum, min/max values, min/max length, mean, stddev & variance. The default set of
 corresponds to statistics that can be computed efficiently on a stream of data (i.e., constant memory).
 
 The data type of each column is also inferred (Unknown, NULL, Integer, String,
-Float, Date and DateTime). Note that the Date and DateTime data types are only inferred with
+Float, Boolean, Date and DateTime). Note that the Date and DateTime data types are only inferred with
 the --dates option as its an expensive operation. The date formats recognized can be found at
 https://docs.rs/dateparser/0.1.6/dateparser/#accepted-date-formats.
 
+With --dates, the min/max of Date and DateTime columns are the earliest and
+latest dates, in RFC3339 format (dates without a timezone are taken as UTC).
+The following statistics are also reported for them: the number of days
+between the min and the max (date_span_days), the distinct source formats of
+the dates in strftime syntax, e.g. '%m/%d/%Y' (date_formats, up to 10), and
+whether All, Some or None of the dates have a timezone (timezones).
+
+A column is Boolean when all its values are truthy or falsy values, compared
+case-insensitively. They are 'true,t,yes,y,1' and 'false,f,no,n,0' by default,
+and can be changed with the QSV_TRUTHY_VALUES and QSV_FALSY_VALUES environment
+variables. Numbers like 1 and 0 only make a Boolean column if both are found.
+
+Numbers can be locale-formatted, e.g. 1,234.56 or 1.234,56, by setting the
+QSV_NUMERIC_LOCALE environment variable to the thousands separator followed by
+the decimal separator, e.g. ',.' or '.,' (a space matches non-breaking spaces
+too). Negative numbers can then be in parentheses, e.g. (500), and percentages
+like 45% are Floats divided by 100. Set it to 'auto' to detect the separators
+from the first 1000 rows. This is also used by schema, validate and sort -N.
+
+The cardinality, median, quartiles and percentiles can instead be approximated
+in bounded memory with --approx, e.g. for files too large to fit in memory:
+
+  $ qsv stats --approx --cardinality --quartiles --percentiles 1,5,95,99 data.csv
+
+With --output-format json, the statistics are written as a JSON document
+instead, with the parameters of the run and an object for each field, whose
+statistics are typed (numbers as numbers and missing values as null):
+
+  {
+    \"parameters\": { \"input\": \"data.csv\", \"dates\": false, ... },
+    \"fields\": [
+      { \"field\": \"id\", \"type\": \"Integer\", \"sum\": 55, \"min\": 1, ... },
+      ...
+    ]
+  }
+
+With --groupby, the statistics are computed for each group of rows that have
+the same values in the given columns, e.g. per state. The group columns are
+output in front of the usual ones, and the groups are sorted by their values.
+The group columns are included in the statistics, unless they are excluded
+with the select option, e.g. '--groupby state --select !state'. The stats cache
+isn't used with --groupby.
+
 Computing statistics on a large file can be made much faster if you create
 an index for it first with 'qsv index' to enable multithreading.
 
+With --cache, the statistics of all the columns of a file are cached in
+<input>.stats.json, with a fingerprint of the file (its size, modification
+time, inode and hashes of its start and end). As long as the file doesn't
+change, they are reused instead of being computed again by stats (when they
+include the requested statistics), as well as by schema, frequency --all-unique
+and type selectors. Nothing is written next to the input without --cache.
+
 Usage:
     qsv stats [options] [<input>]
 
@@ -48,22 +112,56 @@ stats options:
                            See 'qsv select --help' for the format details.
                            This is provided here because piping 'qsv select'
                            into 'qsv stats' will disable the use of indexing.
+    -g, --groupby <keys>   Compute the stats of each group of rows with the same
+                           values in the given columns.
     --everything           Show all statistics available.
     --mode                 Show the mode/s. Multimodal-aware.
                            This requires storing all CSV data in memory.
     --cardinality          Show the cardinality.
-                           This requires storing all CSV data in memory.
+                           This requires storing all CSV data in memory,
+                           unless --approx is given.
     --median               Show the median.
-                           This requires storing all CSV data in memory.
+                           This requires storing all CSV data in memory,
+                           unless --approx is given.
     --nullcount            Show the number of NULLs.
     --quartiles            Show the quartiles, the IQR, the lower/upper fences
                            and skew.
-                           This requires storing all CSV data in memory.
+                           This requires storing all CSV data in memory,
+                           unless --approx is given.
+    --percentiles <list>   Show the given percentiles, e.g. '1,5,95,99', each
+                           from 0 to 100. They are interpolated linearly
+                           between the closest values.
+                           This requires storing all CSV data in memory,
+                           unless --approx is given.
+    --moments              Show the skewness and the excess kurtosis.
+    --mad                  Show the median absolute deviation.
+                           This requires storing all CSV data in memory,
+                           unless --approx is given.
+    --cv                   Show the coefficient of variation, i.e. the
+                           standard deviation divided by the mean.
+    --means                Show the geometric and harmonic means, for columns
+                           whose numbers are all positive.
+    --signs                Show the number of zeros and of negative numbers.
+    --sort-order           Show whether the values are sorted, i.e. Ascending,
+                           Descending or Unsorted. Numbers are compared
+                           numerically, other values as strings, and NULLs
+                           are ignored.
+    --approx               Approximate the cardinality, median, quartiles,
+                           percentiles and median absolute deviation with
+                           sketches that use bounded memory
+                           (HyperLogLog and t-digest). The cardinality is
+                           typically within 1% of the exact one, and the rank
+                           of the quantiles within 0.1%. They are (nearly)
+                           exact for small columns.
     --nulls                Include NULLs in the population size for computing
                            mean and standard deviation.
     --dates                Infer date/datetime datatypes. This is a very expensive
                            option and should only be used when you know there
                            are date/datetime fields.
+    --cache                Write the statistics to the statistics cache.
+                           Only done when the input is a file and all its
+                           columns are selected, and not with --groupby.
+    --no-cache             Don't read the statistics cache.
     -j, --jobs <arg>       The number of jobs to run in parallel.
                            This works only when the given CSV has an index.
                            Note that a file handle is opened for each job.
@@ -73,6 +171,9 @@ stats options:
                            number of CPUs detected.
                            [default: 0]
 
+    --output-format <fmt>  The format of the output, either \"csv\" or \"json\".
+                           [default: csv]
+
 Common options:
     -h, --help             Display this message
     -o, --output <file>    Write output to <file> instead of stdout.
@@ -87,36 +188,67 @@ Common options:
 pub struct Args {
     pub arg_input: Option<String>,
     pub flag_select: SelectColumns,
+    pub flag_groupby: Option<SelectColumns>,
     pub flag_everything: bool,
     pub flag_mode: bool,
     pub flag_cardinality: bool,
     pub flag_median: bool,
     pub flag_quartiles: bool,
+    pub flag_percentiles: Option<Percentiles>,
+    pub flag_moments: bool,
+    pub flag_mad: bool,
+    pub flag_cv: bool,
+    pub flag_means: bool,
+    pub flag_signs: bool,
+    pub flag_sort_order: bool,
+    pub flag_approx: bool,
     pub flag_nulls: bool,
     pub flag_dates: bool,
     pub flag_nullcount: bool,
+    pub flag_cache: bool,
+    pub flag_no_cache: bool,
     pub flag_jobs: isize,
+    pub flag_output_format: OutputFormat,
     pub flag_output: Option<String>,
     pub flag_no_headers: bool,
     pub flag_delimiter: Option<Delimiter>,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+}
+
+/// The values of the --groupby columns of a group of rows, which is empty
+/// without --groupby.
+type GroupKey = Vec<Vec<u8>>;
+
+/// The statistics of the selected columns for each group of rows.
+pub type GroupStats = AHashMap<GroupKey, Vec<Stats>>;
+
+type GroupValues = Vec<(GroupKey, Vec<Vec<Value>>)>;
+
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_groupby.is_some() {
+        return args.run_groupby();
+    }
+    let (headers, stats) = args.stats_values()?;
 
-    let mut wtr = Config::new(&args.flag_output).writer()?;
-    let (headers, stats) = match args.rconfig().indexed()? {
-        None => args.sequential_stats(),
-        Some(idx) => {
-            if args.flag_jobs == 1 {
-                args.sequential_stats()
-            } else {
-                args.parallel_stats(idx)
-            }
-        }
-    }?;
-    let stats = args.stats_to_records(stats);
+    if args.flag_output_format == OutputFormat::Json {
+        let mut wtr = Config::new(&args.flag_output).io_writer()?;
+        let doc = json!({
+            "parameters": args.parameters(),
+            "fields": args.typed_stats(&headers, &stats),
+        });
+        serde_json::to_writer_pretty(&mut wtr, &doc).map_err(io::Error::from)?;
+        writeln!(wtr)?;
+        wtr.flush()?;
+        return Ok(());
+    }
 
+    let mut wtr = Config::new(&args.flag_output).writer()?;
     wtr.write_record(&args.stat_headers())?;
     let fields = headers.iter().zip(stats.into_iter());
     for (i, (header, stat)) in fields.enumerate() {
@@ -125,25 +257,164 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         } else {
             header.to_vec()
         };
-        let stat = stat.iter().map(|f| f.as_bytes());
-        wtr.write_record(vec![&*header].into_iter().chain(stat))?;
+        let stat = stats_record(&stat);
+        wtr.write_record(
+            vec![&*header]
+                .into_iter()
+                .chain(stat.iter().map(str::as_bytes)),
+        )?;
     }
     wtr.flush()?;
     Ok(())
 }
 
 impl Args {
-    pub fn sequential_stats(&self) -> CliResult<(csv::ByteRecord, Vec<Stats>)> {
+    fn run_groupby(&self) -> CliResult<()> {
+        let (group_headers, headers, groups) = self.group_values()?;
+
+        if self.flag_output_format == OutputFormat::Json {
+            let mut wtr = Config::new(&self.flag_output).io_writer()?;
+            let mut fields = vec![];
+            for (key, records) in groups {
+                let group: Map<String, Value> = group_headers
+                    .iter()
+                    .zip(&key)
+                    .map(|(name, value)| {
+                        let value = String::from_utf8_lossy(value).into_owned();
+                        (name.to_owned(), Value::String(value))
+                    })
+                    .collect();
+                for stats in self.typed_stats(&headers, &records) {
+                    let mut field = Map::new();
+                    field.insert("group".to_owned(), Value::Object(group.clone()));
+                    field.extend(stats);
+                    fields.push(field);
+                }
+            }
+            let doc = json!({
+                "parameters": self.parameters(),
+                "fields": fields,
+            });
+            serde_json::to_writer_pretty(&mut wtr, &doc).map_err(io::Error::from)?;
+            writeln!(wtr)?;
+            wtr.flush()?;
+            return Ok(());
+        }
+
+        let mut wtr = Config::new(&self.flag_output).writer()?;
+        let mut header = group_headers.clone();
+        header.extend(self.stat_headers().iter());
+        wtr.write_record(&header)?;
+        for (key, records) in groups {
+            for (i, (field, stat)) in headers.iter().zip(records).enumerate() {
+                let field = if self.flag_no_headers {
+                    i.to_string().into_bytes()
+                } else {
+                    field.to_vec()
+                };
+                let row = key.iter().map(|v| &v[..]);
+                let stat = stats_record(&stat);
+                let row = row
+                    .chain(Some(&field[..]))
+                    .chain(stat.iter().map(str::as_bytes));
+                wtr.write_record(row)?;
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// The names of the --groupby columns, the headers of the selected
+    /// columns, and their statistics for each group, sorted by group.
+    fn group_values(&self) -> CliResult<(csv::StringRecord, csv::ByteRecord, GroupValues)> {
+        let rconfig = self.rconfig();
+        numeric::init(&rconfig)?;
+        // piped input is spooled by indexed, so it must be called first
+        let idx = rconfig.indexed()?;
+        let headers = rconfig.reader()?.byte_headers()?.clone();
+        let group_headers = match self.groupby(&headers)? {
+            None => csv::StringRecord::new(),
+            Some(groupby) if self.flag_no_headers => {
+                groupby.iter().map(ToString::to_string).collect()
+            }
+            Some(groupby) => groupby
+                .select(&headers)
+                .map(|h| String::from_utf8_lossy(h).into_owned())
+                .collect(),
+        };
+
+        let (headers, groups) = self.compute_stats(idx)?;
+        let mut groups = groups
+            .into_iter()
+            .map(|(key, stats)| (key, self.stats_to_values(stats)))
+            .collect::<GroupValues>();
+        groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok((group_headers, headers, groups))
+    }
+
+    /// The headers and statistics of the selected columns, from the stats
+    /// cache if it's still valid and has them. Otherwise they're computed,
+    /// and cached with --cache if all the columns are selected.
+    pub fn stats_values(&self) -> CliResult<(csv::ByteRecord, Vec<Vec<Value>>)> {
+        let rconfig = self.rconfig();
+        numeric::init(&rconfig)?;
+        let cache = if (self.flag_no_cache && !self.flag_cache) || self.flag_groupby.is_some() {
+            None
+        } else {
+            rconfig.stats_cache()?
+        };
+        if let (Some((path, fingerprint)), false) = (&cache, self.flag_no_cache) {
+            if let Some(cached) = StatsCache::load(path, fingerprint).filter(|c| c.covers(self)) {
+                debug!("using the stats cache {}", path.display());
+                let (headers, sel) = self.sel_headers(&mut rconfig.reader()?)?;
+                let stat_headers = self.stat_headers();
+                let values = sel.iter().map(|&i| cached.values(i, &stat_headers));
+                return Ok((headers, values.collect()));
+            }
+        }
+
+        let (headers, mut groups) = self.compute_stats(rconfig.indexed()?)?;
+        let stats = groups.remove(&GroupKey::new()).unwrap_or_default();
+        let rows = stats.first().map_or(0, |s| s.rows);
+        let values = self.stats_to_values(stats);
+
+        let write = self.flag_cache && self.flag_select.is_empty();
+        if let (Some((path, fingerprint)), true) = (cache, write) {
+            let cached = StatsCache {
+                fingerprint,
+                rows,
+                parameters: self.parameters(),
+                fields: self.typed_stats(&headers, &values),
+            };
+            if let Err(e) = cached.save(&path) {
+                warn!("Cannot write the stats cache {}: {e}", path.display());
+            }
+        }
+        Ok((headers, values))
+    }
+
+    fn compute_stats(
+        &self,
+        idx: Option<Indexed<fs::File, fs::File>>,
+    ) -> CliResult<(csv::ByteRecord, GroupStats)> {
+        match idx {
+            Some(idx) if self.flag_jobs != 1 => self.parallel_stats(idx),
+            _ => self.sequential_stats(),
+        }
+    }
+
+    pub fn sequential_stats(&self) -> CliResult<(csv::ByteRecord, GroupStats)> {
         let mut rdr = self.rconfig().reader()?;
+        let groupby = self.groupby(rdr.byte_headers()?)?;
         let (headers, sel) = self.sel_headers(&mut rdr)?;
-        let stats = self.compute(&sel, rdr.byte_records())?;
+        let stats = self.compute(&sel, groupby.as_ref(), rdr.byte_records())?;
         Ok((headers, stats))
     }
 
     pub fn parallel_stats(
         &self,
         idx: Indexed<fs::File, fs::File>,
-    ) -> CliResult<(csv::ByteRecord, Vec<Stats>)> {
+    ) -> CliResult<(csv::ByteRecord, GroupStats)> {
         // N.B. This method doesn't handle the case when the number of records
         // is zero correctly. So we use `sequential_stats` instead.
         if idx.count() == 0 {
@@ -151,6 +422,7 @@ impl Args {
         }
 
         let mut rdr = self.rconfig().reader()?;
+        let groupby = self.groupby(rdr.byte_headers()?)?;
         let (headers, sel) = self.sel_headers(&mut rdr)?;
 
         let chunk_size = util::chunk_size(idx.count() as usize, self.njobs());
@@ -159,52 +431,97 @@ impl Args {
         let pool = ThreadPool::new(self.njobs());
         let (send, recv) = channel::bounded(0);
         for i in 0..nchunks {
-            let (send, args, sel) = (send.clone(), self.clone(), sel.clone());
+            let (send, args) = (send.clone(), self.clone());
+            let (sel, groupby) = (sel.clone(), groupby.clone());
             pool.execute(move || {
                 let mut idx = args.rconfig().indexed().unwrap().unwrap();
                 idx.seek((i * chunk_size) as u64).unwrap();
                 let it = idx.byte_records().take(chunk_size);
-                send.send(args.compute(&sel, it).unwrap()).unwrap();
+                let stats = args.compute(&sel, groupby.as_ref(), it).unwrap();
+                send.send((i, stats)).unwrap();
             });
         }
         drop(send);
-        Ok((headers, merge_all(recv.iter()).unwrap_or_default()))
+        // the chunks are merged in order, as the sort order depends on it
+        let mut groups = GroupStats::new();
+        for (_, chunk) in recv.iter().sorted_by_key(|(i, _)| *i) {
+            for (key, stats) in chunk {
+                match groups.entry(key) {
+                    Entry::Occupied(mut e) => e.get_mut().merge(stats),
+                    Entry::Vacant(e) => {
+                        e.insert(stats);
+                    }
+                }
+            }
+        }
+        Ok((headers, groups))
     }
 
-    pub fn stats_to_records(&self, stats: Vec<Stats>) -> Vec<csv::StringRecord> {
-        let mut records: Vec<_> = repeat(csv::StringRecord::new()).take(stats.len()).collect();
+    pub fn stats_to_values(&self, stats: Vec<Stats>) -> Vec<Vec<Value>> {
+        let mut values: Vec<_> = repeat(vec![]).take(stats.len()).collect();
         let pool = ThreadPool::new(self.njobs());
         let mut results = vec![];
         for mut stat in stats {
             let (send, recv) = channel::bounded(0);
             results.push(recv);
             pool.execute(move || {
-                send.send(stat.to_record()).unwrap();
+                send.send(stat.to_values()).unwrap();
             });
         }
         for (i, recv) in results.into_iter().enumerate() {
-            records[i] = recv.recv().unwrap();
+            values[i] = recv.recv().unwrap();
         }
-        records
+        values
     }
 
     #[inline]
-    fn compute<I>(&self, sel: &Selection, it: I) -> CliResult<Vec<Stats>>
+    fn compute<I>(
+        &self,
+        sel: &Selection,
+        groupby: Option<&Selection>,
+        it: I,
+    ) -> CliResult<GroupStats>
     where
         I: Iterator<Item = csv::Result<csv::ByteRecord>>,
     {
-        let mut stats = self.new_stats(sel.len());
+        let mut groups = GroupStats::new();
+        let groupby = match groupby {
+            Some(groupby) => groupby,
+            None => {
+                let mut stats = self.new_stats(sel.len());
+                for row in it {
+                    add_row(&mut stats, sel, &row?);
+                }
+                groups.insert(GroupKey::new(), stats);
+                return Ok(groups);
+            }
+        };
+        // the key of each row is read into the same buffers, which are only
+        // copied for new groups
+        let mut key: GroupKey = vec![vec![]; groupby.len()];
         for row in it {
             let row = row?;
-            for (i, field) in sel.select(&row).enumerate() {
-                unsafe {
-                    // we use unsafe here so we skip unnecessary
-                    // bounds checking for this hot loop
-                    stats.get_unchecked_mut(i).add(field);
+            for (k, field) in key.iter_mut().zip(groupby.select(&row)) {
+                k.clear();
+                k.extend_from_slice(field);
+            }
+            match groups.get_mut(&key) {
+                Some(stats) => add_row(stats, sel, &row),
+                None => {
+                    let mut stats = self.new_stats(sel.len());
+                    add_row(&mut stats, sel, &row);
+                    groups.insert(key.clone(), stats);
                 }
             }
         }
-        Ok(stats)
+        Ok(groups)
+    }
+
+    fn groupby(&self, headers: &csv::ByteRecord) -> CliResult<Option<Selection>> {
+        match self.flag_groupby {
+            Some(ref groupby) => Ok(Some(groupby.selection(headers, !self.flag_no_headers)?)),
+            None => Ok(None),
+        }
     }
 
     fn sel_headers<R: io::Read>(
@@ -244,6 +561,14 @@ impl Args {
             nullcount: self.flag_nullcount || self.flag_everything,
             median: self.flag_median && !self.flag_quartiles && !self.flag_everything,
             quartiles: self.flag_quartiles || self.flag_everything,
+            percentiles: self.percentiles().to_vec(),
+            moments: self.flag_moments || self.flag_everything,
+            mad: self.flag_mad || self.flag_everything,
+            cv: self.flag_cv || self.flag_everything,
+            means: self.flag_means || self.flag_everything,
+            signs: self.flag_signs || self.flag_everything,
+            sort_order: self.flag_sort_order || self.flag_everything,
+            approx: self.flag_approx,
             mode: self.flag_mode || self.flag_everything,
             dates: self.flag_dates,
         }))
@@ -277,6 +602,8 @@ impl Args {
             fields.push("upper_fence");
             fields.push("skew");
         }
+        let percentiles: Vec<_> = self.percentiles().iter().map(|p| format!("p{p}")).collect();
+        fields.extend(percentiles.iter().map(String::as_str));
         if self.flag_mode || all {
             fields.push("mode");
         }
@@ -286,11 +613,249 @@ impl Args {
         if self.flag_nullcount || all {
             fields.push("nullcount");
         }
+        if self.flag_moments || all {
+            fields.push("skewness");
+            fields.push("kurtosis");
+        }
+        if self.flag_mad || all {
+            fields.push("mad");
+        }
+        if self.flag_cv || all {
+            fields.push("cv");
+        }
+        if self.flag_means || all {
+            fields.push("geometric_mean");
+            fields.push("harmonic_mean");
+        }
+        if self.flag_signs || all {
+            fields.push("zeros");
+            fields.push("negatives");
+        }
+        if self.flag_sort_order || all {
+            fields.push("sort_order");
+        }
+        if self.flag_dates {
+            fields.push("date_span_days");
+            fields.push("date_formats");
+            fields.push("timezones");
+        }
         csv::StringRecord::from(fields)
     }
+
+    /// The statistics of each field, as JSON objects keyed by the names of
+    /// `stat_headers`, with typed values.
+    pub fn typed_stats(
+        &self,
+        headers: &csv::ByteRecord,
+        values: &[Vec<Value>],
+    ) -> Vec<Map<String, Value>> {
+        let stat_headers = self.stat_headers();
+        let mut fields = Vec::with_capacity(values.len());
+        for (i, (header, values)) in headers.iter().zip(values).enumerate() {
+            let field = if self.flag_no_headers {
+                i.to_string()
+            } else {
+                String::from_utf8_lossy(header).into_owned()
+            };
+            let mut map = Map::new();
+            map.insert("field".to_owned(), Value::String(field));
+            for (name, value) in stat_headers.iter().skip(1).zip(values) {
+                map.insert(name.to_owned(), value.clone());
+            }
+            fields.push(map);
+        }
+        fields
+    }
+
+    /// The parameters of the run, for the JSON output.
+    fn parameters(&self) -> Value {
+        json!({
+            "input": self.arg_input,
+            "no_headers": self.flag_no_headers,
+            "delimiter": self
+                .flag_delimiter
+                .map(|d| String::from_utf8_lossy(d.as_bytes()).into_owned()),
+            "everything": self.flag_everything,
+            "mode": self.flag_mode,
+            "cardinality": self.flag_cardinality,
+            "median": self.flag_median,
+            "quartiles": self.flag_quartiles,
+            "percentiles": self.percentiles(),
+            "moments": self.flag_moments,
+            "mad": self.flag_mad,
+            "cv": self.flag_cv,
+            "means": self.flag_means,
+            "signs": self.flag_signs,
+            "sort_order": self.flag_sort_order,
+            "approx": self.flag_approx,
+            "nulls": self.flag_nulls,
+            "nullcount": self.flag_nullcount,
+            "dates": self.flag_dates,
+            "truthy": boolean_values().0,
+            "falsy": boolean_values().1,
+            "numeric_locale": numeric_locale(),
+        })
+    }
+
+    fn percentiles(&self) -> &[f64] {
+        self.flag_percentiles.as_ref().map_or(&[], |p| &p.0)
+    }
+}
+
+/// Percentiles represents the `--percentiles` to compute, e.g. `1,5,95,99`.
+#[derive(Clone, Debug)]
+pub struct Percentiles(Vec<f64>);
+
+impl<'de> Deserialize<'de> for Percentiles {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Percentiles, D::Error> {
+        let s = String::deserialize(d)?;
+        let mut percentiles = vec![];
+        for p in s.split(',') {
+            match p.trim().parse::<f64>() {
+                Ok(p) if (0.0..=100.0).contains(&p) => percentiles.push(p),
+                _ => {
+                    return Err(D::Error::custom(format!(
+                        "Invalid percentile '{p}'. Percentiles must be numbers from 0 to 100."
+                    )))
+                }
+            }
+        }
+        Ok(Percentiles(percentiles))
+    }
+}
+
+/// StatsCache is the statistics of all the columns of a CSV file, cached by
+/// `qsv stats` in `<input>.stats.json` with the fingerprint of the file, and
+/// the number of rows.
+#[derive(Deserialize, Serialize)]
+pub struct StatsCache {
+    fingerprint: StatsFingerprint,
+    rows: u64,
+    parameters: Value,
+    fields: Vec<Map<String, Value>>,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+impl StatsCache {
+    /// The stats cache of the CSV data of `rconfig`, if there's one and the
+    /// data didn't change since.
+    pub fn of(rconfig: &Config) -> CliResult<Option<StatsCache>> {
+        Ok(match rconfig.stats_cache()? {
+            None => None,
+            Some((path, fingerprint)) => StatsCache::load(&path, &fingerprint),
+        })
+    }
+
+    fn load(path: &Path, fingerprint: &StatsFingerprint) -> Option<StatsCache> {
+        let file = fs::File::open(path).ok()?;
+        let cached: StatsCache = serde_json::from_reader(io::BufReader::new(file)).ok()?;
+        // unlike offsets, statistics depend on whether there's a header row,
+        // which is part of the fingerprint
+        let valid = cached.fingerprint == *fingerprint
+            && cached.fields.len() as u64 == fingerprint.data.column_count;
+        if !valid {
+            debug!("the stats cache {} is stale", path.display());
+            return None;
+        }
+        Some(cached)
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let mut wtr = io::BufWriter::new(fs::File::create(path)?);
+        serde_json::to_writer_pretty(&mut wtr, self)?;
+        writeln!(wtr)?;
+        wtr.flush()
+    }
+
+    /// Whether the cached statistics were computed the way `args` asks for,
+    /// and include all of its statistics. Exact ones can stand in for
+    /// approximations, but not the other way around.
+    fn covers(&self, args: &Args) -> bool {
+        let stat_headers = args.stat_headers();
+        self.same_inference()
+            && self.parameters["dates"] == args.flag_dates
+            && self.parameters["nulls"] == args.flag_nulls
+            && (args.flag_approx || self.parameters["approx"] == false)
+            && self.fields.first().map_or(true, |field| {
+                stat_headers.iter().all(|name| field.contains_key(name))
+            })
+    }
+
+    /// Whether the types were inferred with the current truthy and falsy
+    /// values and number format.
+    fn same_inference(&self) -> bool {
+        let (truthy, falsy) = boolean_values();
+        self.parameters["truthy"] == json!(truthy)
+            && self.parameters["falsy"] == json!(falsy)
+            && self.parameters["numeric_locale"] == json!(numeric_locale())
+    }
+
+    /// The statistics of column `i` named by `stat_headers`.
+    fn values(&self, i: usize, stat_headers: &csv::StringRecord) -> Vec<Value> {
+        let field = &self.fields[i];
+        stat_headers
+            .iter()
+            .skip(1)
+            .map(|name| field.get(name).cloned().unwrap_or(Value::Null))
+            .collect()
+    }
+
+    /// The types of all the columns, if they were inferred with `--dates`.
+    fn types(&self) -> Option<Vec<String>> {
+        if self.parameters["dates"] != true || !self.same_inference() {
+            return None;
+        }
+        let types = self.fields.iter().map(|field| field["type"].as_str());
+        types.map(|t| t.map(ToOwned::to_owned)).collect()
+    }
+
+    /// The number of values of column `i` if they're all unique as per its
+    /// exact cardinality, e.g. so that `qsv frequency` doesn't count them.
+    /// With `no_nulls`, the NULLs aren't included.
+    pub fn unique_count(&self, i: usize, no_nulls: bool) -> Option<u64> {
+        let field = self.fields.get(i)?;
+        if self.parameters["approx"] != false
+            || self.rows == 0
+            || field.get("cardinality")?.as_u64()? != self.rows
+        {
+            return None;
+        }
+        if no_nulls {
+            Some(self.rows - field.get("nullcount")?.as_u64()?)
+        } else {
+            Some(self.rows)
+        }
+    }
+}
+
+/// The types of all columns of the CSV data of `rconfig`, as reported by
+/// `qsv stats --dates`, e.g. for type selectors like `:numeric`.
+pub fn column_types(rconfig: &Config) -> CliResult<Vec<String>> {
+    if rconfig.is_std() {
+        return fail!("Type selectors can't be used with <stdin>, as the data is read twice.");
+    }
+    numeric::init(rconfig)?;
+    if let Some(types) = StatsCache::of(rconfig)?.and_then(|c| c.types()) {
+        return Ok(types);
+    }
+    let mut rdr = rconfig.reader()?;
+    let mut types = vec![(FieldType::default(), Booleans::default()); rdr.byte_headers()?.len()];
+    let mut record = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut record)? {
+        for ((typ, booleans), field) in types.iter_mut().zip(record.iter()) {
+            let sample_type = FieldType::from_sample(true, field);
+            typ.merge(sample_type);
+            if !sample_type.is_null() {
+                booleans.add(field);
+            }
+        }
+    }
+    let types = types
+        .iter()
+        .map(|(typ, booleans)| booleans.field_type(*typ));
+    Ok(types.map(|typ| typ.to_string()).collect())
+}
+
+#[derive(Clone, Debug, PartialEq)]
 struct WhichStats {
     include_nulls: bool,
     sum: bool,
@@ -300,6 +865,14 @@ struct WhichStats {
     nullcount: bool,
     median: bool,
     quartiles: bool,
+    percentiles: Vec<f64>,
+    moments: bool,
+    mad: bool,
+    cv: bool,
+    means: bool,
+    signs: bool,
+    sort_order: bool,
+    approx: bool,
     mode: bool,
     dates: bool,
 }
@@ -314,19 +887,27 @@ impl Commute for WhichStats {
 #[derive(Clone)]
 pub struct Stats {
     typ: FieldType,
+    booleans: Booleans,
     sum: Option<TypedSum>,
     minmax: Option<TypedMinMax>,
     online: Option<OnlineStats>,
     modes: Option<Unsorted<Vec<u8>>>,
-    median: Option<Unsorted<f64>>,
+    cardinality: Option<HyperLogLog>,
+    quantiles: Option<Quantiles>,
+    rows: u64,
     nullcount: u64,
-    quartiles: Option<Unsorted<f64>>,
+    moments: Option<Moments>,
+    means: Option<Means>,
+    zeros: u64,
+    negatives: u64,
+    sort_order: Option<SortOrder>,
+    date_formats: Option<DateFormats>,
     which: WhichStats,
 }
 
 impl Stats {
     fn new(which: WhichStats) -> Stats {
-        let (mut sum, mut minmax, mut online, mut modes, mut median, mut quartiles) =
+        let (mut sum, mut minmax, mut online, mut modes, mut cardinality, mut quantiles) =
             (None, None, None, None, None, None);
         if which.sum {
             sum = Some(Default::default());
@@ -337,24 +918,55 @@ impl Stats {
         if which.dist {
             online = Some(Default::default());
         }
-        if which.mode || which.cardinality {
+        if which.mode || (which.cardinality && !which.approx) {
             modes = Some(Default::default());
         }
-        if which.median {
-            median = Some(Default::default());
+        if which.cardinality && which.approx {
+            cardinality = Some(HyperLogLog::new());
         }
-        if which.quartiles {
-            quartiles = Some(Default::default());
+        if which.median || which.quartiles || which.mad || !which.percentiles.is_empty() {
+            quantiles = Some(if which.approx {
+                Quantiles::Approx(TDigest::new())
+            } else {
+                Quantiles::Exact {
+                    values: Vec::new(),
+                    sorted: true,
+                }
+            });
         }
         Stats {
             typ: Default::default(),
+            booleans: Booleans::default(),
             sum,
             minmax,
             online,
             modes,
-            median,
+            cardinality,
+            quantiles,
+            rows: 0,
             nullcount: 0,
-            quartiles,
+            moments: if which.moments {
+                Some(Moments::default())
+            } else {
+                None
+            },
+            means: if which.means {
+                Some(Means::default())
+            } else {
+                None
+            },
+            zeros: 0,
+            negatives: 0,
+            sort_order: if which.sort_order {
+                Some(SortOrder::default())
+            } else {
+                None
+            },
+            date_formats: if which.dates {
+                Some(DateFormats::default())
+            } else {
+                None
+            },
             which,
         }
     }
@@ -364,6 +976,7 @@ impl Stats {
     fn add(&mut self, sample: &[u8]) {
         let sample_type = FieldType::from_sample(self.which.dates, sample);
         self.typ.merge(sample_type);
+        self.rows += 1;
 
         let t = self.typ;
         if let Some(v) = self.sum.as_mut() {
@@ -375,8 +988,19 @@ impl Stats {
         if let Some(v) = self.modes.as_mut() {
             v.add(sample.to_vec())
         };
+        if let Some(v) = self.cardinality.as_mut() {
+            v.add(sample)
+        };
         if sample_type.is_null() {
             self.nullcount += 1;
+        } else {
+            self.booleans.add(sample);
+            if let Some(v) = self.sort_order.as_mut() {
+                v.add(sample_type, sample);
+            }
+            if let (Some(v), TDate | TDateTime) = (self.date_formats.as_mut(), sample_type) {
+                v.add(sample);
+            }
         }
         match self.typ {
             TUnknown => {}
@@ -396,164 +1020,938 @@ impl Stats {
                         };
                     }
                 } else {
-                    let n = from_bytes::<f64>(sample).unwrap();
-                    self.median.as_mut().map(|v| {
+                    let n = float_from_bytes(sample).unwrap();
+                    self.quantiles.as_mut().map(|v| {
                         v.add(n);
                     });
-                    self.quartiles.as_mut().map(|v| {
+                    self.online.as_mut().map(|v| {
                         v.add(n);
                     });
-                    self.online.as_mut().map(|v| {
+                    self.moments.as_mut().map(|v| {
+                        v.add(n);
+                    });
+                    self.means.as_mut().map(|v| {
                         v.add(n);
                     });
+                    if n == 0.0 {
+                        self.zeros += 1;
+                    } else if n < 0.0 {
+                        self.negatives += 1;
+                    }
                 }
             }
             _ => {}
         }
     }
 
+    /// The statistics, as typed values in the order of `Args::stat_headers`
+    /// after the field name, e.g. a number for the mean, a string for the min
+    /// of a String field, or null when there's none.
     #[allow(clippy::wrong_self_convention)]
-    pub fn to_record(&mut self) -> csv::StringRecord {
+    pub fn to_values(&mut self) -> Vec<Value> {
         let typ = self.typ;
-        let mut pieces = Vec::with_capacity(20);
-        let empty = || "".to_owned();
+        let mut values = Vec::with_capacity(20);
 
-        pieces.push(self.typ.to_string());
-        match self.sum.as_ref().and_then(|sum| sum.show(typ)) {
-            Some(sum) => {
-                pieces.push(sum);
+        values.push(Value::String(self.booleans.field_type(typ).to_string()));
+        values.push(self.sum.as_ref().map_or(Value::Null, |sum| sum.value(typ)));
+        match self.minmax.as_ref().and_then(|mm| mm.values(typ)) {
+            Some((min, max)) => {
+                values.push(min);
+                values.push(max);
             }
             None => {
-                pieces.push(empty());
-            }
-        }
-        match self.minmax.as_ref().and_then(|mm| mm.show(typ)) {
-            Some(mm) => {
-                pieces.push(mm.0);
-                pieces.push(mm.1);
-            }
-            None => {
-                pieces.push(empty());
-                pieces.push(empty());
+                values.push(Value::Null);
+                values.push(Value::Null);
             }
         }
         match self.minmax.as_ref().and_then(|mm| mm.len_range()) {
-            Some(mm) => {
-                pieces.push(mm.0);
-                pieces.push(mm.1);
+            Some((min, max)) => {
+                values.push(min.into());
+                values.push(max.into());
             }
             None => {
-                pieces.push(empty());
-                pieces.push(empty());
+                values.push(Value::Null);
+                values.push(Value::Null);
             }
         }
 
-        if !self.typ.is_number() {
-            pieces.push(empty());
-            pieces.push(empty());
-            pieces.push(empty());
-        } else {
-            match self.online {
-                Some(ref v) => {
-                    pieces.push(v.mean().to_string());
-                    pieces.push(v.stddev().to_string());
-                    pieces.push(v.variance().to_string());
-                }
-                None => {
-                    pieces.push(empty());
-                    pieces.push(empty());
-                    pieces.push(empty());
-                }
+        match self.online {
+            Some(ref v) if self.typ.is_number() => {
+                values.push(float(v.mean()));
+                values.push(float(v.stddev()));
+                values.push(float(v.variance()));
             }
-        }
-        match self.median.as_mut().and_then(|v| match self.typ {
-            TInteger | TFloat => v.median(),
-            _ => None,
-        }) {
-            None => {
-                if self.which.median {
-                    pieces.push(empty());
-                }
-            }
-            Some(v) => {
-                pieces.push(v.to_string());
+            _ => {
+                values.push(Value::Null);
+                values.push(Value::Null);
+                values.push(Value::Null);
             }
         }
-        match self.quartiles.as_mut().and_then(|v| match self.typ {
-            TInteger | TFloat => v.quartiles(),
+        let quantiles = match self.typ {
+            TInteger | TFloat => self.quantiles.as_mut(),
             _ => None,
-        }) {
+        };
+        let (median, quartiles, percentiles, mad) = match quantiles {
+            None => (None, None, vec![None; self.which.percentiles.len()], None),
+            Some(v) => (
+                if self.which.median { v.median() } else { None },
+                if self.which.quartiles {
+                    v.quartiles()
+                } else {
+                    None
+                },
+                self.which
+                    .percentiles
+                    .iter()
+                    .map(|&p| v.percentile(p))
+                    .collect(),
+                if self.which.mad {
+                    v.median_absolute_deviation()
+                } else {
+                    None
+                },
+            ),
+        };
+        if self.which.median {
+            values.push(median.map_or(Value::Null, float));
+        }
+        match quartiles {
             None => {
                 if self.which.quartiles {
-                    pieces.push(empty());
-                    pieces.push(empty());
-                    pieces.push(empty());
-                    pieces.push(empty());
-                    pieces.push(empty());
-                    pieces.push(empty());
-                    pieces.push(empty());
+                    values.extend(repeat(Value::Null).take(7));
                 }
             }
             Some((q1, q2, q3)) => {
                 let iqr = q3 - q1;
-                pieces.push((q1 - (1.5 * iqr)).to_string());
-                pieces.push(q1.to_string());
-                pieces.push(q2.to_string());
-                pieces.push(q3.to_string());
-                pieces.push(iqr.to_string());
-                pieces.push((q3 + (1.5 * iqr)).to_string());
+                values.push(float(q1 - (1.5 * iqr)));
+                values.push(float(q1));
+                values.push(float(q2));
+                values.push(float(q3));
+                values.push(float(iqr));
+                values.push(float(q3 + (1.5 * iqr)));
                 // calculate skewnewss using Pearson's median skewness
                 // https://en.wikipedia.org/wiki/Skewness#Pearson's_second_skewness_coefficient_(median_skewness)
                 let _mean = self.online.unwrap().mean();
                 let _stddev = self.online.unwrap().stddev();
-                pieces.push(((3.0 * (_mean - q2)) / _stddev).to_string());
+                values.push(float((3.0 * (_mean - q2)) / _stddev));
             }
         }
-        match self.modes.as_mut() {
-            None => {
-                if self.which.mode {
-                    pieces.push(empty());
-                }
-                if self.which.cardinality {
-                    pieces.push(empty());
-                }
-            }
-            Some(ref mut v) => {
-                if self.which.mode {
-                    pieces.push(
+        for p in percentiles {
+            values.push(p.map_or(Value::Null, float));
+        }
+        if self.which.mode {
+            match self.modes.as_mut() {
+                None => values.push(Value::Null),
+                Some(ref mut v) => {
+                    values.push(string(
                         v.modes()
                             .iter()
                             .map(|c| unsafe { String::from_utf8_unchecked(c.to_vec()) })
                             .join(","),
-                    );
-                }
-                if self.which.cardinality {
-                    pieces.push(v.cardinality().to_string());
+                    ));
                 }
             }
         }
+        if self.which.cardinality {
+            match (self.cardinality.as_ref(), self.modes.as_mut()) {
+                (Some(v), _) => values.push(v.cardinality().into()),
+                (None, Some(v)) => values.push(v.cardinality().into()),
+                (None, None) => values.push(Value::Null),
+            }
+        }
         if self.which.nullcount {
-            pieces.push(self.nullcount.to_string());
+            values.push(self.nullcount.into());
+        }
+        let number = |v: Option<f64>| match v {
+            Some(v) if typ.is_number() => float(v),
+            _ => Value::Null,
+        };
+        if let Some(ref v) = self.moments {
+            values.push(number(v.skewness()));
+            values.push(number(v.kurtosis()));
+        }
+        if self.which.mad {
+            values.push(number(mad));
         }
-        csv::StringRecord::from(pieces)
+        if self.which.cv {
+            values.push(number(self.online.map(|v| v.stddev() / v.mean())));
+        }
+        if let Some(ref v) = self.means {
+            values.push(number(v.geometric()));
+            values.push(number(v.harmonic()));
+        }
+        if self.which.signs {
+            if typ.is_number() {
+                values.push(self.zeros.into());
+                values.push(self.negatives.into());
+            } else {
+                values.push(Value::Null);
+                values.push(Value::Null);
+            }
+        }
+        if let Some(ref v) = self.sort_order {
+            values.push(v.show(typ).map_or(Value::Null, Value::from));
+        }
+        if let Some(ref v) = self.date_formats {
+            match (typ, self.minmax.as_ref().and_then(TypedMinMax::date_span)) {
+                (TDate | TDateTime, Some(span)) => {
+                    values.push(float(span));
+                    values.push(string(v.show_formats()));
+                    values.push(string(v.show_timezones().to_owned()));
+                }
+                _ => {
+                    values.push(Value::Null);
+                    values.push(Value::Null);
+                    values.push(Value::Null);
+                }
+            }
+        }
+        values
     }
 }
 
+/// Add the selected fields of `row` to their statistics.
+#[inline]
+fn add_row(stats: &mut [Stats], sel: &Selection, row: &csv::ByteRecord) {
+    for (i, field) in sel.select(row).enumerate() {
+        unsafe {
+            // we use unsafe here so we skip unnecessary
+            // bounds checking for this hot loop
+            stats.get_unchecked_mut(i).add(field);
+        }
+    }
+}
+
+/// A float statistic, or null if it isn't finite, as JSON has no NaN.
+fn float(v: f64) -> Value {
+    Number::from_f64(v).map_or(Value::Null, Value::Number)
+}
+
+/// A string statistic, or null if it's empty.
+fn string(s: String) -> Value {
+    if s.is_empty() {
+        Value::Null
+    } else {
+        Value::String(s)
+    }
+}
+
+/// The statistics of a field as a CSV record, as output by `qsv stats`.
+pub fn stats_record(values: &[Value]) -> csv::StringRecord {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.as_i64().map_or_else(
+                || n.as_f64().unwrap_or_default().to_string(),
+                |n| n.to_string(),
+            ),
+            _ => String::new(),
+        })
+        .collect()
+}
+
 impl Commute for Stats {
     #[inline]
     fn merge(&mut self, other: Stats) {
         self.typ.merge(other.typ);
+        self.booleans.merge(other.booleans);
         self.sum.merge(other.sum);
         self.minmax.merge(other.minmax);
         self.online.merge(other.online);
         self.modes.merge(other.modes);
-        self.median.merge(other.median);
+        self.cardinality.merge(other.cardinality);
+        self.quantiles.merge(other.quantiles);
+        self.rows += other.rows;
         self.nullcount += other.nullcount;
-        self.quartiles.merge(other.quartiles);
+        self.moments.merge(other.moments);
+        self.means.merge(other.means);
+        self.zeros += other.zeros;
+        self.negatives += other.negatives;
+        self.sort_order.merge(other.sort_order);
+        self.date_formats.merge(other.date_formats);
         self.which.merge(other.which);
     }
 }
 
+/// Quantiles keeps the numbers of a column to compute its median, quartiles
+/// and percentiles: either all of them, or a t-digest sketch with --approx.
+/// All the numbers are sorted in place once, when the first of these is
+/// computed, so that no other copy of them is needed.
+#[derive(Clone)]
+enum Quantiles {
+    Exact { values: Vec<f64>, sorted: bool },
+    Approx(TDigest),
+}
+
+impl Quantiles {
+    #[inline]
+    fn add(&mut self, n: f64) {
+        match self {
+            Quantiles::Exact { values, sorted } => {
+                values.push(n);
+                *sorted = false;
+            }
+            Quantiles::Approx(v) => v.add(n),
+        }
+    }
+
+    /// The numbers in ascending order, for exact quantiles.
+    fn sorted(&mut self) -> Option<&[f64]> {
+        match self {
+            Quantiles::Exact { values, sorted } => {
+                if !*sorted {
+                    values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                    *sorted = true;
+                }
+                Some(values)
+            }
+            Quantiles::Approx(_) => None,
+        }
+    }
+
+    fn median(&mut self) -> Option<f64> {
+        match self.sorted() {
+            Some(v) => median_on_sorted(v),
+            None => self.approx()?.quantile(0.5),
+        }
+    }
+
+    fn quartiles(&mut self) -> Option<(f64, f64, f64)> {
+        match self.sorted() {
+            Some(v) => quartiles_on_sorted(v),
+            None => {
+                let v = self.approx()?;
+                Some((v.quantile(0.25)?, v.quantile(0.5)?, v.quantile(0.75)?))
+            }
+        }
+    }
+
+    /// The `p` percentile, interpolated linearly between the closest values,
+    /// the same way as the centroids of the t-digest.
+    fn percentile(&mut self, p: f64) -> Option<f64> {
+        let v = match self.sorted() {
+            Some(v) => v,
+            None => return self.approx()?.quantile(p / 100.0),
+        };
+        if v.is_empty() {
+            return None;
+        }
+        let rank = p / 100.0 * v.len() as f64 - 0.5;
+        if rank <= 0.0 {
+            return Some(v[0]);
+        }
+        let i = rank as usize;
+        if i + 1 >= v.len() {
+            return Some(v[v.len() - 1]);
+        }
+        Some(v[i] + (v[i + 1] - v[i]) * (rank - i as f64))
+    }
+
+    /// The median of the absolute deviations from the median.
+    fn median_absolute_deviation(&mut self) -> Option<f64> {
+        match self.sorted() {
+            Some(v) => mad_on_sorted(v),
+            None => self.approx()?.median_absolute_deviation(),
+        }
+    }
+
+    fn approx(&mut self) -> Option<&mut TDigest> {
+        match self {
+            Quantiles::Approx(v) => Some(v),
+            Quantiles::Exact { .. } => None,
+        }
+    }
+}
+
+impl Commute for Quantiles {
+    #[inline]
+    fn merge(&mut self, other: Quantiles) {
+        match (self, other) {
+            (
+                Quantiles::Exact { values, sorted },
+                Quantiles::Exact {
+                    values: other_values,
+                    ..
+                },
+            ) => {
+                values.extend(other_values);
+                *sorted = false;
+            }
+            (Quantiles::Approx(v1), Quantiles::Approx(v2)) => v1.merge(v2),
+            _ => unreachable!("exact and approximate quantiles can't be merged"),
+        }
+    }
+}
+
+/// The median of sorted numbers, as computed by `stats::median`.
+fn median_on_sorted(v: &[f64]) -> Option<f64> {
+    let len = v.len();
+    match len {
+        0 => None,
+        _ if len % 2 == 0 => Some((v[len / 2 - 1] + v[len / 2]) / 2.0),
+        _ => Some(v[len / 2]),
+    }
+}
+
+/// The quartiles of sorted numbers, as computed by `stats::quartiles`, i.e.
+/// the medians of the numbers below and above the median.
+fn quartiles_on_sorted(v: &[f64]) -> Option<(f64, f64, f64)> {
+    let len = v.len();
+    if len < 3 {
+        return None;
+    }
+    if len == 3 {
+        return Some((v[0], v[1], v[2]));
+    }
+    let k = len / 4;
+    let mid = |i: usize| (v[i - 1] + v[i]) / 2.0;
+    Some(match len % 4 {
+        0 => (mid(k), mid(2 * k), mid(3 * k)),
+        1 => (mid(k), v[2 * k], mid(3 * k + 1)),
+        2 => (v[k], mid(2 * k + 1), v[3 * k + 1]),
+        _ => (v[k], v[2 * k + 1], v[3 * k + 2]),
+    })
+}
+
+/// The median absolute deviation of sorted numbers. The deviations below
+/// and above the median are each sorted already, so their median is found
+/// by merging them, without sorting a copy of them.
+fn mad_on_sorted(v: &[f64]) -> Option<f64> {
+    let median = median_on_sorted(v)?;
+    let split = v.partition_point(|&n| n < median);
+    let (below, above) = v.split_at(split);
+    // the deviations in ascending order
+    let mut below = below.iter().rev().map(|n| median - n).peekable();
+    let mut above = above.iter().map(|n| n - median).peekable();
+    let mut deviations = iter::from_fn(|| match (below.peek(), above.peek()) {
+        (Some(b), Some(a)) if b <= a => below.next(),
+        (Some(_), None) => below.next(),
+        _ => above.next(),
+    });
+    let len = v.len();
+    let lower = deviations.nth((len - 1) / 2)?;
+    if len % 2 == 0 {
+        Some((lower + deviations.next()?) / 2.0)
+    } else {
+        Some(lower)
+    }
+}
+
+/// Moments keeps the central moments of the numbers seen, up to the fourth,
+/// to compute their skewness and kurtosis in a single pass.
+///
+/// See Philippe Pébay, "Formulas for Robust, One-Pass Parallel Computation
+/// of Covariances and Arbitrary-Order Statistical Moments".
+#[derive(Clone, Copy, Default)]
+struct Moments {
+    n: f64,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+
+impl Moments {
+    #[inline]
+    fn add(&mut self, x: f64) {
+        let n1 = self.n;
+        self.n += 1.0;
+        let n = self.n;
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term1 = delta * delta_n * n1;
+        self.mean += delta_n;
+        self.m4 += term1 * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * self.m2
+            - 4.0 * delta_n * self.m3;
+        self.m3 += term1 * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term1;
+    }
+
+    /// The (population) skewness, i.e. the third standardized moment.
+    fn skewness(&self) -> Option<f64> {
+        if self.m2 == 0.0 {
+            return None;
+        }
+        Some(self.n.sqrt() * self.m3 / self.m2.powf(1.5))
+    }
+
+    /// The excess kurtosis, i.e. the fourth standardized moment minus 3, so
+    /// that it is 0 for a normal distribution.
+    fn kurtosis(&self) -> Option<f64> {
+        if self.m2 == 0.0 {
+            return None;
+        }
+        Some(self.n * self.m4 / (self.m2 * self.m2) - 3.0)
+    }
+}
+
+impl Commute for Moments {
+    #[inline]
+    fn merge(&mut self, other: Moments) {
+        let (na, nb) = (self.n, other.n);
+        let n = na + nb;
+        if n == 0.0 {
+            return;
+        }
+        let delta = other.mean - self.mean;
+        let delta2 = delta * delta;
+        let m2 = self.m2 + other.m2 + delta2 * na * nb / n;
+        let m3 = self.m3
+            + other.m3
+            + delta2 * delta * na * nb * (na - nb) / (n * n)
+            + 3.0 * delta * (na * other.m2 - nb * self.m2) / n;
+        let m4 = self.m4
+            + other.m4
+            + delta2 * delta2 * na * nb * (na * na - na * nb + nb * nb) / (n * n * n)
+            + 6.0 * delta2 * (na * na * other.m2 + nb * nb * self.m2) / (n * n)
+            + 4.0 * delta * (na * other.m3 - nb * self.m3) / n;
+        self.mean += delta * nb / n;
+        self.n = n;
+        self.m2 = m2;
+        self.m3 = m3;
+        self.m4 = m4;
+    }
+}
+
+/// Means keeps the sums needed for the geometric and harmonic means, which
+/// are only defined when all the numbers are positive.
+#[derive(Clone, Copy, Default)]
+struct Means {
+    n: u64,
+    log_sum: f64,
+    inverse_sum: f64,
+    non_positive: bool,
+}
+
+impl Means {
+    #[inline]
+    fn add(&mut self, x: f64) {
+        if x <= 0.0 {
+            self.non_positive = true;
+            return;
+        }
+        self.n += 1;
+        self.log_sum += x.ln();
+        self.inverse_sum += 1.0 / x;
+    }
+
+    fn geometric(&self) -> Option<f64> {
+        if self.non_positive || self.n == 0 {
+            return None;
+        }
+        Some((self.log_sum / self.n as f64).exp())
+    }
+
+    fn harmonic(&self) -> Option<f64> {
+        if self.non_positive || self.n == 0 {
+            return None;
+        }
+        Some(self.n as f64 / self.inverse_sum)
+    }
+}
+
+impl Commute for Means {
+    #[inline]
+    fn merge(&mut self, other: Means) {
+        self.n += other.n;
+        self.log_sum += other.log_sum;
+        self.inverse_sum += other.inverse_sum;
+        self.non_positive |= other.non_positive;
+    }
+}
+
+/// SortOrder keeps track of whether the values seen are in ascending and/or
+/// descending order, both as numbers and as strings, as the type of the
+/// column is only known at the end.
+///
+/// Unlike the other statistics, it depends on the order of the values, so
+/// the stats of chunks of the data must be merged in order.
+#[derive(Clone, Default)]
+struct SortOrder {
+    first: Option<(Option<f64>, Vec<u8>)>,
+    last: Option<(Option<f64>, Vec<u8>)>,
+    numbers: Sorted,
+    strings: Sorted,
+}
+
+/// Whether values are in ascending and/or descending order.
+#[derive(Clone, Copy)]
+struct Sorted {
+    ascending: bool,
+    descending: bool,
+}
+
+impl Default for Sorted {
+    fn default() -> Sorted {
+        Sorted {
+            ascending: true,
+            descending: true,
+        }
+    }
+}
+
+impl Sorted {
+    #[inline]
+    fn add(&mut self, ordering: Option<Ordering>) {
+        match ordering {
+            Some(Ordering::Less) => self.descending = false,
+            Some(Ordering::Greater) => self.ascending = false,
+            Some(Ordering::Equal) => {}
+            None => {
+                self.ascending = false;
+                self.descending = false;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: Sorted) {
+        self.ascending &= other.ascending;
+        self.descending &= other.descending;
+    }
+}
+
+impl SortOrder {
+    #[inline]
+    fn add(&mut self, typ: FieldType, sample: &[u8]) {
+        let n = if typ.is_number() {
+            float_from_bytes(sample)
+        } else {
+            None
+        };
+        let value = (n, sample.to_vec());
+        if self.first.is_none() {
+            self.first = Some(value.clone());
+        }
+        if let Some(last) = self.last.take() {
+            self.compare(&last, &value);
+        }
+        self.last = Some(value);
+    }
+
+    fn compare(&mut self, a: &(Option<f64>, Vec<u8>), b: &(Option<f64>, Vec<u8>)) {
+        // only columns that are all numbers are compared numerically
+        let numbers = match (a.0, b.0) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        };
+        self.numbers.add(numbers);
+        self.strings.add(Some(a.1.cmp(&b.1)));
+    }
+
+    fn show(&self, typ: FieldType) -> Option<&'static str> {
+        self.first.as_ref()?;
+        let sorted = if typ.is_number() {
+            self.numbers
+        } else {
+            self.strings
+        };
+        Some(if sorted.ascending {
+            "Ascending"
+        } else if sorted.descending {
+            "Descending"
+        } else {
+            "Unsorted"
+        })
+    }
+}
+
+impl Commute for SortOrder {
+    /// Merge the sort order of the values that come after those of `self`.
+    fn merge(&mut self, other: SortOrder) {
+        if let (Some(last), Some(first)) = (self.last.clone(), other.first.as_ref()) {
+            self.compare(&last, first);
+        }
+        self.numbers.merge(other.numbers);
+        self.strings.merge(other.strings);
+        if self.first.is_none() {
+            self.first = other.first;
+        }
+        if other.last.is_some() {
+            self.last = other.last;
+        }
+    }
+}
+
+/// The maximum number of distinct source formats kept for a date column.
+const MAX_DATE_FORMATS: usize = 10;
+
+/// DateFormats keeps the distinct source formats of the dates of a column,
+/// in the order they're first seen, and how many of the dates have a
+/// timezone.
+#[derive(Clone, Default)]
+struct DateFormats {
+    formats: Vec<String>,
+    dates: u64,
+    with_timezone: u64,
+}
+
+impl DateFormats {
+    #[inline]
+    fn add(&mut self, sample: &[u8]) {
+        let (format, timezone) = date_format(&String::from_utf8_lossy(sample));
+        self.dates += 1;
+        if timezone {
+            self.with_timezone += 1;
+        }
+        if self.formats.len() < MAX_DATE_FORMATS && !self.formats.contains(&format) {
+            self.formats.push(format);
+        }
+    }
+
+    fn show_formats(&self) -> String {
+        self.formats.join("; ")
+    }
+
+    /// Whether `All`, `Some` or `None` of the dates have a timezone.
+    fn show_timezones(&self) -> &'static str {
+        match self.with_timezone {
+            0 => "None",
+            n if n == self.dates => "All",
+            _ => "Some",
+        }
+    }
+}
+
+impl Commute for DateFormats {
+    fn merge(&mut self, other: DateFormats) {
+        self.dates += other.dates;
+        self.with_timezone += other.with_timezone;
+        for format in other.formats {
+            if self.formats.len() < MAX_DATE_FORMATS && !self.formats.contains(&format) {
+                self.formats.push(format);
+            }
+        }
+    }
+}
+
+const MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const WEEKDAYS: &[&str] = &[
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// Whether `word` (in lowercase) is one of `names`, or its abbreviation.
+fn is_name(word: &str, names: &[&str]) -> bool {
+    word.len() >= 3 && names.iter().any(|name| name.starts_with(word))
+}
+
+/// The format of the date `s` in strftime syntax, e.g. `%Y-%m-%d %H:%M:%S`
+/// or `%B %d, %Y`, and whether it has a timezone.
+///
+/// The numbers of the date are read in the same order as `dateparser` does:
+/// year first when it has 4 digits, day first next to a month name, and
+/// month first otherwise, e.g. `06/27/1968`.
+fn date_format(s: &str) -> (String, bool) {
+    let chars: Vec<char> = s.trim().chars().collect();
+    let mut format = String::with_capacity(chars.len() + 8);
+    let mut timezone = false;
+    let mut date_fields: Option<std::slice::Iter<&str>> = None;
+    let mut time_fields = ["%H", "%M", "%S"].iter();
+    let mut in_time = false;
+    let mut month_name = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        if c.is_ascii_digit() {
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let len = i - start;
+            if in_time && format.ends_with('.') {
+                // fractional seconds
+                format.pop();
+                format.push_str("%.f");
+            } else if in_time || chars.get(i) == Some(&':') {
+                in_time = true;
+                match time_fields.next() {
+                    Some(field) => format.push_str(field),
+                    None => format.extend(&chars[start..i]),
+                }
+            } else {
+                let fields = date_fields.get_or_insert_with(|| {
+                    let month_name_after = chars[i..]
+                        .split(|c| !c.is_alphabetic())
+                        .any(|w| is_name(&w.iter().collect::<String>().to_lowercase(), MONTHS));
+                    if month_name || month_name_after {
+                        ["%d", "%Y"].iter()
+                    } else if len == 4 {
+                        ["%Y", "%m", "%d"].iter()
+                    } else {
+                        ["%m", "%d", "%Y"].iter()
+                    }
+                });
+                match fields.next() {
+                    Some(&"%Y") if len == 2 => format.push_str("%y"),
+                    Some(field) => format.push_str(field),
+                    None => format.extend(&chars[start..i]),
+                }
+            }
+        } else if c.is_alphabetic() {
+            while i < chars.len() && chars[i].is_alphabetic() {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let lower = word.to_lowercase();
+            if is_name(&lower, MONTHS) {
+                month_name = true;
+                format.push_str(if lower.len() == 3 { "%b" } else { "%B" });
+            } else if is_name(&lower, WEEKDAYS) {
+                format.push_str(if lower.len() == 3 { "%a" } else { "%A" });
+            } else if in_time && (lower == "am" || lower == "pm") {
+                format.push_str("%p");
+            } else if in_time && word == "Z" {
+                timezone = true;
+                format.push('Z');
+            } else if in_time && word.len() >= 2 && word.chars().all(|c| c.is_ascii_uppercase()) {
+                timezone = true;
+                format.push_str("%Z");
+            } else {
+                format.push_str(&word);
+            }
+        } else if in_time
+            && (c == '+' || c == '-')
+            && chars.get(i + 1).map_or(false, char::is_ascii_digit)
+        {
+            // a timezone offset, e.g. +0200 or -05:00
+            i += 1;
+            let digits = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == ':') {
+                i += 1;
+            }
+            timezone = true;
+            let colon = chars[digits..i].contains(&':');
+            format.push_str(if colon { "%:z" } else { "%z" });
+        } else {
+            i += 1;
+            if c == '%' {
+                format.push_str("%%");
+            } else {
+                format.push(c);
+            }
+        }
+    }
+    (format, timezone)
+}
+
+/// The values of Boolean columns by default, which can be changed with the
+/// QSV_TRUTHY_VALUES and QSV_FALSY_VALUES environment variables.
+const DEFAULT_TRUTHY_VALUES: &str = "true,t,yes,y,1";
+const DEFAULT_FALSY_VALUES: &str = "false,f,no,n,0";
+
+static BOOLEAN_VALUES: OnceCell<(Vec<String>, Vec<String>)> = OnceCell::new();
+
+/// The thousands and decimal separators of numbers, if they're
+/// locale-formatted.
+fn numeric_locale() -> Option<String> {
+    numeric::number_format().map(|f| f.to_string())
+}
+
+/// The truthy and falsy values, in lowercase.
+fn boolean_values() -> &'static (Vec<String>, Vec<String>) {
+    BOOLEAN_VALUES.get_or_init(|| {
+        let values = |var: &str, default: &str| {
+            let values = env::var(var).unwrap_or_else(|_| default.to_owned());
+            values
+                .split(',')
+                .map(|v| v.trim().to_ascii_lowercase())
+                .filter(|v| !v.is_empty())
+                .collect()
+        };
+        (
+            values("QSV_TRUTHY_VALUES", DEFAULT_TRUTHY_VALUES),
+            values("QSV_FALSY_VALUES", DEFAULT_FALSY_VALUES),
+        )
+    })
+}
+
+/// The boolean that `s` stands for, if it's a truthy or a falsy value,
+/// compared case-insensitively.
+pub fn parse_boolean(s: &str) -> Option<bool> {
+    let (truthy, falsy) = boolean_values();
+    if truthy.iter().any(|v| v.eq_ignore_ascii_case(s)) {
+        Some(true)
+    } else if falsy.iter().any(|v| v.eq_ignore_ascii_case(s)) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Booleans tracks whether all the (non-NULL) values of a column are truthy
+/// or falsy values. Only `true` and `false` are Booleans by themselves, while
+/// values like `no` are Strings and values like `1` Integers, so a column of
+/// them is only Boolean if both truthy and falsy values are found.
+#[derive(Clone, Copy)]
+struct Booleans {
+    all: bool,
+    truthy: bool,
+    falsy: bool,
+}
+
+impl Booleans {
+    #[inline]
+    fn add(&mut self, sample: &[u8]) {
+        if !self.all {
+            return;
+        }
+        match str::from_utf8(sample).ok().and_then(parse_boolean) {
+            Some(true) => self.truthy = true,
+            Some(false) => self.falsy = true,
+            None => self.all = false,
+        }
+    }
+
+    /// The type of the column, given the type `typ` inferred from its values.
+    fn field_type(self, typ: FieldType) -> FieldType {
+        if self.all && self.truthy && self.falsy {
+            TBoolean
+        } else {
+            typ
+        }
+    }
+}
+
+impl Default for Booleans {
+    fn default() -> Booleans {
+        Booleans {
+            all: true,
+            truthy: false,
+            falsy: false,
+        }
+    }
+}
+
+impl Commute for Booleans {
+    #[inline]
+    fn merge(&mut self, other: Booleans) {
+        self.all &= other.all;
+        self.truthy |= other.truthy;
+        self.falsy |= other.falsy;
+    }
+}
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FieldType {
@@ -562,6 +1960,7 @@ pub enum FieldType {
     TString,
     TFloat,
     TInteger,
+    TBoolean,
     TDate,
     TDateTime,
 }
@@ -576,22 +1975,22 @@ impl FieldType {
             Err(_) => return TUnknown,
             Ok(s) => s,
         };
-        if string.parse::<i64>().is_ok() {
+        if numeric::parse_int(string).is_some() {
             return TInteger;
         }
-        if string.parse::<f64>().is_ok() {
+        if numeric::parse_float(string).is_some() {
             return TFloat;
         }
-        if dates {
-            if let Ok(parsed_date) = string.parse::<DateTimeUtc>() {
-                let rfc3339_date_str = parsed_date.0.to_string();
-                let datelen = rfc3339_date_str.len();
-
-                if datelen >= 17 {
-                    return TDateTime;
-                } else {
-                    return TDate;
-                }
+        let literal = string.eq_ignore_ascii_case("true") || string.eq_ignore_ascii_case("false");
+        if literal && parse_boolean(string).is_some() {
+            return TBoolean;
+        }
+        if dates && string.parse::<DateTimeUtc>().is_ok() {
+            // all the date formats with a time have a colon in it
+            if string.contains(':') {
+                return TDateTime;
+            } else {
+                return TDate;
             }
         }
 
@@ -616,12 +2015,16 @@ impl Commute for FieldType {
             (TString, TString) => TString,
             (TFloat, TFloat) => TFloat,
             (TInteger, TInteger) => TInteger,
+            (TBoolean, TBoolean) => TBoolean,
             (TDate, TDate) => TDate,
             (TDateTime, TDateTime) => TDateTime,
             // Null does not impact the type.
             (TNull, any) | (any, TNull) => any,
             // There's no way to get around an unknown.
             (TUnknown, _) | (_, TUnknown) => TUnknown,
+            // Booleans like `yes` can only degrade to Strings (but see
+            // `Booleans` for booleans like `1`).
+            (TBoolean, _) | (_, TBoolean) => TString,
             // date data types
             (TDate, TDateTime) | (TDateTime, TDate) => TDateTime,
             // Integers can degrade to floats.
@@ -657,6 +2060,7 @@ impl fmt::Display for FieldType {
             TString => write!(f, "String"),
             TFloat => write!(f, "Float"),
             TInteger => write!(f, "Integer"),
+            TBoolean => write!(f, "Boolean"),
             TDate => write!(f, "Date"),
             TDateTime => write!(f, "DateTime"),
         }
@@ -671,6 +2075,7 @@ impl fmt::Debug for FieldType {
             TString => write!(f, "String"),
             TFloat => write!(f, "Float"),
             TInteger => write!(f, "Integer"),
+            TBoolean => write!(f, "Boolean"),
             TDate => write!(f, "Date"),
             TDateTime => write!(f, "DateTime"),
         }
@@ -694,7 +2099,7 @@ impl TypedSum {
         }
         match typ {
             TFloat => {
-                let float: f64 = from_bytes::<f64>(sample).unwrap();
+                let float: f64 = float_from_bytes(sample).unwrap();
                 match self.float {
                     None => {
                         self.float = Some((self.integer as f64) + float);
@@ -706,9 +2111,9 @@ impl TypedSum {
             }
             TInteger => {
                 if let Some(ref mut float) = self.float {
-                    *float += from_bytes::<f64>(sample).unwrap();
+                    *float += float_from_bytes(sample).unwrap();
                 } else {
-                    self.integer += from_bytes::<i64>(sample).unwrap();
+                    self.integer += int_from_bytes(sample).unwrap();
                 }
             }
             _ => {}
@@ -716,11 +2121,11 @@ impl TypedSum {
     }
 
     #[inline]
-    fn show(&self, typ: FieldType) -> Option<String> {
+    fn value(&self, typ: FieldType) -> Value {
         match typ {
-            TNull | TString | TUnknown | TDate | TDateTime => None,
-            TInteger => Some(self.integer.to_string()),
-            TFloat => Some(self.float.unwrap_or(0.0).to_string()),
+            TNull | TString | TUnknown | TBoolean | TDate | TDateTime => Value::Null,
+            TInteger => self.integer.into(),
+            TFloat => float(self.float.unwrap_or(0.0)),
         }
     }
 }
@@ -745,7 +2150,7 @@ struct TypedMinMax {
     str_len: MinMax<usize>,
     integers: MinMax<i64>,
     floats: MinMax<f64>,
-    dates: MinMax<String>,
+    dates: MinMax<DateTime<Utc>>,
 }
 
 impl TypedMinMax {
@@ -757,63 +2162,78 @@ impl TypedMinMax {
         }
         self.strings.add(sample.to_vec());
         match typ {
-            TString | TUnknown | TNull => {}
-            TFloat => unsafe {
-                let n = str::from_utf8_unchecked(&*sample)
-                    .parse::<f64>()
-                    .ok()
-                    .unwrap();
-
+            TString | TUnknown | TNull | TBoolean => {}
+            TFloat => {
+                let n = float_from_bytes(sample).unwrap();
                 self.floats.add(n);
                 self.integers.add(n as i64);
-            },
-            TInteger => unsafe {
-                let n = str::from_utf8_unchecked(&*sample)
-                    .parse::<i64>()
-                    .ok()
-                    .unwrap();
+            }
+            TInteger => {
+                let n = int_from_bytes(sample).unwrap();
                 self.integers.add(n);
                 self.floats.add(n as f64);
-            },
-            TDate | TDateTime => unsafe {
-                let tempstr = str::from_utf8_unchecked(&*sample);
-                let n = dateparser::parse(tempstr).ok().unwrap();
+            }
+            TDate | TDateTime => {
+                // dates without a timezone are taken as UTC, and at midnight
+                // when there's no time
+                let date = str::from_utf8(sample).ok().and_then(|s| {
+                    dateparser::parse_with(s, &Utc, NaiveTime::from_hms(0, 0, 0)).ok()
+                });
+                if let Some(date) = date {
+                    self.dates.add(date);
+                }
+            }
+        }
+    }
 
-                self.dates.add(n.to_string());
-            },
+    /// The number of days between the earliest and the latest dates.
+    fn date_span(&self) -> Option<f64> {
+        match (self.dates.min(), self.dates.max()) {
+            (Some(min), Some(max)) => Some((*max - *min).num_milliseconds() as f64 / 86_400_000.0),
+            _ => None,
         }
     }
 
     #[inline]
-    fn len_range(&self) -> Option<(String, String)> {
+    fn len_range(&self) -> Option<(usize, usize)> {
         match (self.str_len.min(), self.str_len.max()) {
-            (Some(min), Some(max)) => Some((min.to_string(), max.to_string())),
+            (Some(min), Some(max)) => Some((*min, *max)),
             _ => None,
         }
     }
 
     #[inline]
-    fn show(&self, typ: FieldType) -> Option<(String, String)> {
+    fn values(&self, typ: FieldType) -> Option<(Value, Value)> {
         match typ {
             TNull => None,
-            TString | TUnknown => match (self.strings.min(), self.strings.max()) {
+            TString | TUnknown | TBoolean => match (self.strings.min(), self.strings.max()) {
                 (Some(min), Some(max)) => unsafe {
                     let min = String::from_utf8_unchecked((&**min).to_vec());
                     let max = String::from_utf8_unchecked((&**max).to_vec());
-                    Some((min, max))
+                    Some((min.into(), max.into()))
                 },
                 _ => None,
             },
-            TDate | TDateTime => match (self.dates.min(), self.dates.max()) {
-                (Some(min), Some(max)) => Some((min.to_string(), max.to_string())),
+            TDate => match (self.dates.min(), self.dates.max()) {
+                (Some(min), Some(max)) => Some((
+                    min.format("%Y-%m-%d").to_string().into(),
+                    max.format("%Y-%m-%d").to_string().into(),
+                )),
+                _ => None,
+            },
+            TDateTime => match (self.dates.min(), self.dates.max()) {
+                (Some(min), Some(max)) => Some((
+                    min.to_rfc3339_opts(SecondsFormat::AutoSi, true).into(),
+                    max.to_rfc3339_opts(SecondsFormat::AutoSi, true).into(),
+                )),
                 _ => None,
             },
             TInteger => match (self.integers.min(), self.integers.max()) {
-                (Some(min), Some(max)) => Some((min.to_string(), max.to_string())),
+                (Some(min), Some(max)) => Some(((*min).into(), (*max).into())),
                 _ => None,
             },
             TFloat => match (self.floats.min(), self.floats.max()) {
-                (Some(min), Some(max)) => Some((min.to_string(), max.to_string())),
+                (Some(min), Some(max)) => Some((float(*min), float(*max))),
                 _ => None,
             },
         }
@@ -832,8 +2252,13 @@ impl Commute for TypedMinMax {
 }
 
 #[inline]
-fn from_bytes<T: FromStr>(bytes: &[u8]) -> Option<T> {
+fn float_from_bytes(bytes: &[u8]) -> Option<f64> {
     // we don't need to do UTF-8 validation as we automatically
     // transcode to UTF-8 the input
-    unsafe { str::from_utf8_unchecked(bytes).parse().ok() }
+    numeric::parse_float(unsafe { str::from_utf8_unchecked(bytes) })
+}
+
+#[inline]
+fn int_from_bytes(bytes: &[u8]) -> Option<i64> {
+    numeric::parse_int(unsafe { str::from_utf8_unchecked(bytes) })
 }
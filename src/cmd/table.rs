@@ -73,7 +73,7 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
         .no_headers(true);
-    let wconfig = Config::new(&args.flag_output).delimiter(Some(Delimiter(b'\t')));
+    let wconfig = Config::new(&args.flag_output).delimiter(Some(Delimiter::from_byte(b'\t')));
 
     let tw = TabWriter::new(wconfig.io_writer()?)
         .minwidth(args.flag_width)
@@ -1,4 +1,7 @@
+use crate::cmd::schema;
+use crate::cmd::stats::parse_boolean;
 use crate::config::{Config, Delimiter, DEFAULT_WTR_BUFFER_CAPACITY};
+use crate::numeric;
 use crate::util;
 use crate::CliError;
 use crate::CliResult;
@@ -37,6 +40,12 @@ JSON Schema can be a local file or a URL.
 
 When run without JSON Schema, only a simple CSV check (RFC 4180) is performed.
 
+The values of fields of type boolean must be true or false. With schemas
+generated by 'qsv schema', which infers Boolean columns from the truthy and
+falsy values (see QSV_TRUTHY_VALUES and QSV_FALSY_VALUES), those values are
+cast to booleans too. Such schemas are marked with the top-level keyword
+'x-qsv-truthy-falsy': true, which can also be added to other schemas.
+
 
 Usage:
     qsv validate [options] [<input>] [<json-schema>]
@@ -76,6 +85,7 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let mut rconfig = Config::new(&args.arg_input)
         .delimiter(args.flag_delimiter)
         .no_headers(args.flag_no_headers);
+    numeric::init(&rconfig)?;
 
     let mut rdr = rconfig.reader()?;
 
@@ -158,6 +168,9 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 
     // debug!("compiled schema: {:?}", &schema_compiled);
 
+    // the truthy and falsy values are only booleans for schemas inferred by qsv
+    let truthy_falsy = schema_json[schema::TRUTHY_FALSY_KEYWORD] == true;
+
     // how many rows read and processed as batches
     let mut row_number: usize = 0;
     // how many invalid rows found
@@ -206,7 +219,15 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         // validation_results vector should have same row count and in same order as input CSV
         let validation_results: Vec<Option<String>> = batch
             .par_iter()
-            .map(|record| do_json_validation(&headers, record, &schema_json, &schema_compiled))
+            .map(|record| {
+                do_json_validation(
+                    &headers,
+                    record,
+                    &schema_json,
+                    &schema_compiled,
+                    truthy_falsy,
+                )
+            })
             .collect();
 
         batch.clear();
@@ -372,12 +393,13 @@ fn do_json_validation(
     record: &ByteRecord,
     schema_json: &Value,
     schema_compiled: &JSONSchema,
+    truthy_falsy: bool,
 ) -> Option<String> {
     // row number was added as last column. We use unsafe from_utf8_unchecked to
     // skip UTF8 validation since we know its safe as we added it earlier
     let row_number_string = unsafe { str::from_utf8_unchecked(record.get(headers.len()).unwrap()) };
 
-    let instance: Value = match to_json_instance(headers, record, schema_json) {
+    let instance: Value = match to_json_instance(headers, record, schema_json, truthy_falsy) {
         Ok(obj) => obj,
         Err(e) => {
             return Some(format!("{row_number_string}\t<RECORD>\t{e}"));
@@ -404,8 +426,15 @@ fn do_json_validation(
     }
 }
 
-/// convert CSV Record into JSON instance by referencing Type from Schema
-fn to_json_instance(headers: &ByteRecord, record: &ByteRecord, schema: &Value) -> Result<Value> {
+/// convert CSV Record into JSON instance by referencing Type from Schema.
+/// Booleans are only true or false, unless `truthy_falsy` is set, which also
+/// casts the truthy and falsy values.
+fn to_json_instance(
+    headers: &ByteRecord,
+    record: &ByteRecord,
+    schema: &Value,
+    truthy_falsy: bool,
+) -> Result<Value> {
     // make sure schema has expected structure
     let schema_properties = schema
         .get("properties")
@@ -470,7 +499,7 @@ fn to_json_instance(headers: &ByteRecord, record: &ByteRecord, schema: &Value) -
                 json_object_map.insert(header_string, Value::String(value_string));
             }
             "number" => {
-                if let Ok(float) = value_string.parse::<f64>() {
+                if let Some(float) = numeric::parse_float(&value_string) {
                     json_object_map.insert(
                         header_string,
                         Value::Number(Number::from_f64(float).expect("not a valid f64 float")),
@@ -482,7 +511,7 @@ fn to_json_instance(headers: &ByteRecord, record: &ByteRecord, schema: &Value) -
                 }
             }
             "integer" => {
-                if let Ok(int) = value_string.parse::<i64>() {
+                if let Some(int) = numeric::parse_int(&value_string) {
                     json_object_map.insert(header_string, Value::Number(Number::from(int)));
                 } else {
                     return Err(anyhow!(
@@ -491,7 +520,12 @@ fn to_json_instance(headers: &ByteRecord, record: &ByteRecord, schema: &Value) -
                 }
             }
             "boolean" => {
-                if let Ok(boolean) = value_string.parse::<bool>() {
+                let boolean = if truthy_falsy {
+                    parse_boolean(&value_string)
+                } else {
+                    value_string.parse::<bool>().ok()
+                };
+                if let Some(boolean) = boolean {
                     json_object_map.insert(header_string, Value::Bool(boolean));
                 } else {
                     return Err(anyhow!(
@@ -579,7 +613,8 @@ mod tests_for_csv_to_json_conversion {
             to_json_instance(
                 &headers,
                 &rdr.byte_records().next().unwrap().unwrap(),
-                &schema_json()
+                &schema_json(),
+                false
             )
             .expect("can convert csv to json instance"),
             json!({
@@ -599,6 +634,25 @@ mod tests_for_csv_to_json_conversion {
         );
     }
 
+    #[test]
+    fn test_to_json_instance_cast_boolean() {
+        let csv = "A,B,C,D,E,F,G,H,I,J,K,L
+        hello,3.1415,300000000,No,,,,,hello,3.1415,300000000,Y";
+
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let headers = rdr.byte_headers().unwrap().clone();
+
+        let record = rdr.byte_records().next().unwrap().unwrap();
+        let instance = to_json_instance(&headers, &record, &schema_json(), true)
+            .expect("can convert csv to json instance");
+        assert_eq!(instance["D"], json!(false));
+        assert_eq!(instance["L"], json!(true));
+
+        // only true and false are booleans otherwise
+        let result = to_json_instance(&headers, &record, &schema_json(), false);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_to_json_instance_cast_integer_error() {
         let csv = "A,B,C,D,E,F,G,H
@@ -611,6 +665,7 @@ mod tests_for_csv_to_json_conversion {
             &headers,
             &rdr.byte_records().next().unwrap().unwrap(),
             &schema_json(),
+            false,
         );
         assert!(&result.is_err());
         let error = result.err().unwrap();
@@ -705,7 +760,7 @@ mod tests_for_schema_validation {
 
         let record = &rdr.byte_records().next().unwrap().unwrap();
 
-        let instance = to_json_instance(&headers, &record, &schema_json()).unwrap();
+        let instance = to_json_instance(&headers, &record, &schema_json(), false).unwrap();
 
         let result = validate_json_instance(&instance, &compiled_schema());
 
@@ -722,7 +777,7 @@ mod tests_for_schema_validation {
 
         let record = &rdr.byte_records().next().unwrap().unwrap();
 
-        let instance = to_json_instance(&headers, &record, &schema_json()).unwrap();
+        let instance = to_json_instance(&headers, &record, &schema_json(), false).unwrap();
 
         let result = validate_json_instance(&instance, &compiled_schema());
 
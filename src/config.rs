@@ -1,16 +1,26 @@
 use std::borrow::ToOwned;
+use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
 
+use csv_sniffer::metadata::{Dialect, Quote};
 use csv_sniffer::{SampleSize, Sniffer};
-use encoding_rs_io::DecodeReaderBytes;
-use log::{debug, error};
-use serde::de::{Deserialize, Deserializer, Error};
+use encoding_rs::{Encoding, UTF_8};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use log::{debug, error, info, warn};
+use once_cell::sync::OnceCell;
+use serde::de::{Deserializer, Error};
+use serde::{Deserialize, Serialize};
 
-use crate::index::Indexed;
+use crate::cmd;
+use crate::index::{
+    create_index, create_index_with, read_key_index_header, IndexHeader, Indexed, KeyIndexed,
+};
 use crate::select::{SelectColumns, Selection};
 use crate::util;
 use crate::CliResult;
@@ -21,37 +31,84 @@ const DEFAULT_RDR_BUFFER_CAPACITY: usize = 16 * (1 << 10);
 pub const DEFAULT_WTR_BUFFER_CAPACITY: usize = 64 * (1 << 10);
 // number of rows for csv_sniffer to sample
 const DEFAULT_SNIFFER_SAMPLE: usize = 200;
+// number of leading bytes needed to recognize all supported compression formats
+const COMPRESSION_MAGIC_LEN: usize = 10;
+// longest multi-byte field delimiter or record terminator supported
+const MAX_DELIMITER_LEN: usize = 16;
+// the CSV parser and writer use these bytes in place of multi-byte field
+// delimiters and record terminators, which are transcoded on the way in and out
+const FIELD_DELIMITER_PLACEHOLDER: u8 = b'\x1f';
+const RECORD_TERMINATOR_PLACEHOLDER: u8 = b'\x1e';
+// number of bytes read at a time when transcoding multi-byte delimiters
+const TRANSCODE_CHUNK_SIZE: usize = 8 * (1 << 10);
+// number of leading bytes sampled to detect the character encoding
+const ENCODING_DETECTION_SAMPLE: usize = 64 * (1 << 10);
+// input that needs seeking is kept in memory up to this size, and is spooled
+// to a temporary file beyond it. Can be changed with QSV_SPOOL_SIZE.
+const DEFAULT_SPOOL_SIZE: u64 = 64 * (1 << 20);
 
-#[derive(Clone, Copy, Debug)]
-pub struct Delimiter(pub u8);
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Delimiter {
+    bytes: [u8; MAX_DELIMITER_LEN],
+    len: usize,
+}
 
 /// Delimiter represents values that can be passed from the command line that
 /// can be used as a field delimiter in CSV data.
 ///
-/// Its purpose is to ensure that the Unicode character given decodes to a
-/// valid ASCII character as required by the CSV parser.
+/// A single ASCII character is handed to the CSV parser as is. Anything
+/// longer (e.g. '¦', '§', '||' or '~|~') is transcoded to a placeholder byte
+/// the CSV parser understands, as it only supports single byte delimiters.
 impl Delimiter {
+    pub fn from_byte(b: u8) -> Delimiter {
+        let mut bytes = [0; MAX_DELIMITER_LEN];
+        bytes[0] = b;
+        Delimiter { bytes, len: 1 }
+    }
+
+    /// The byte the CSV parser uses for this delimiter - the delimiter
+    /// itself, or a placeholder byte for multi-byte delimiters.
     pub fn as_byte(self) -> u8 {
-        self.0
+        if self.is_multi_byte() {
+            FIELD_DELIMITER_PLACEHOLDER
+        } else {
+            self.bytes[0]
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    pub fn is_multi_byte(self) -> bool {
+        self.len > 1
     }
 
     fn decode_delimiter(s: String) -> Result<Delimiter, String> {
         if s == r"\t" {
-            return Ok(Delimiter(b'\t'));
+            return Ok(Delimiter::from_byte(b'\t'));
         }
 
-        if s.len() != 1 {
+        let s = s
+            .replace(r"\t", "\t")
+            .replace(r"\r", "\r")
+            .replace(r"\n", "\n");
+        if s.is_empty() {
+            return Err("Delimiter cannot be empty.".to_owned());
+        }
+        if s.len() > MAX_DELIMITER_LEN {
             return Err(format!(
-                "Could not convert '{s}' to a single ASCII character."
+                "Could not use '{s}' as a delimiter. It is longer than \
+                 {MAX_DELIMITER_LEN} bytes."
             ));
         }
 
-        let c = s.chars().next().unwrap();
-        if c.is_ascii() {
-            Ok(Delimiter(c as u8))
-        } else {
-            Err(format!("Could not convert '{c}' to ASCII delimiter."))
-        }
+        let mut bytes = [0; MAX_DELIMITER_LEN];
+        bytes[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(Delimiter {
+            bytes,
+            len: s.len(),
+        })
     }
 }
 
@@ -65,9 +122,199 @@ impl<'de> Deserialize<'de> for Delimiter {
     }
 }
 
+/// Compression represents the compression formats qsv can transparently
+/// read and write.
+///
+/// On read, the format is detected from the magic bytes of the data, falling
+/// back to the file extension. On write, the format is determined by the file
+/// extension of the output path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+    Snappy,
+}
+
+impl Compression {
+    pub fn from_path(path: &Path) -> Compression {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            Some("bz2") => Compression::Bzip2,
+            Some("sz") => Compression::Snappy,
+            _ => Compression::None,
+        }
+    }
+
+    pub fn from_magic(bytes: &[u8]) -> Compression {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if bytes.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if bytes.starts_with(b"\xff\x06\x00\x00sNaPpY") {
+            Compression::Snappy
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Detect the compression of the file at `path` by its magic bytes,
+    /// falling back to its extension when the file is too short to tell.
+    pub fn detect(path: &Path) -> io::Result<Compression> {
+        let mut magic = Vec::with_capacity(COMPRESSION_MAGIC_LEN);
+        fs::File::open(path)?
+            .take(COMPRESSION_MAGIC_LEN as u64)
+            .read_to_end(&mut magic)?;
+        Ok(match Compression::from_magic(&magic) {
+            Compression::None if magic.len() < COMPRESSION_MAGIC_LEN => {
+                Compression::from_path(path)
+            }
+            compression => compression,
+        })
+    }
+
+    pub fn is_compressed(self) -> bool {
+        self != Compression::None
+    }
+
+    fn decoder<R: io::BufRead + 'static>(self, rdr: R) -> io::Result<Box<dyn io::Read + 'static>> {
+        Ok(match self {
+            Compression::None => Box::new(rdr),
+            Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(rdr)),
+            Compression::Zstd => Box::new(zstd::Decoder::with_buffer(rdr)?),
+            Compression::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(rdr)),
+            Compression::Snappy => Box::new(snap::read::FrameDecoder::new(rdr)),
+        })
+    }
+
+    fn encoder<W: io::Write + 'static>(self, wtr: W) -> io::Result<Box<dyn io::Write + 'static>> {
+        Ok(match self {
+            Compression::None => Box::new(wtr),
+            // a snappy frame is complete once flushed, so it needs no finishing
+            Compression::Snappy => Box::new(snap::write::FrameEncoder::new(wtr)),
+            _ => Box::new(FinishingEncoder::new(self, wtr)?),
+        })
+    }
+}
+
+/// An encoder of a compression format whose stream must be finished, e.g.
+/// by writing a trailer.
+enum Encoder<W: io::Write> {
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+    Bzip2(bzip2::write::BzEncoder<W>),
+}
+
+impl<W: io::Write> Encoder<W> {
+    fn new(compression: Compression, wtr: W) -> io::Result<Encoder<W>> {
+        Ok(match compression {
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                wtr,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(wtr, 0)?),
+            Compression::Bzip2 => Encoder::Bzip2(bzip2::write::BzEncoder::new(
+                wtr,
+                bzip2::Compression::default(),
+            )),
+            Compression::None | Compression::Snappy => unreachable!(),
+        })
+    }
+
+    fn finish(self) -> io::Result<W> {
+        match self {
+            Encoder::Gzip(enc) => enc.finish(),
+            Encoder::Zstd(enc) => enc.finish(),
+            Encoder::Bzip2(enc) => enc.finish(),
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut dyn io::Write {
+        match self {
+            Encoder::Gzip(enc) => enc,
+            Encoder::Zstd(enc) => enc,
+            Encoder::Bzip2(enc) => enc,
+        }
+    }
+}
+
+/// A compressing writer that finishes the compressed stream when it's
+/// flushed, so that the errors doing so are returned to the caller instead
+/// of being lost when the encoder is dropped. Writing after a flush starts a
+/// new stream, as gzip, zstd and bzip2 all decode concatenated streams.
+struct FinishingEncoder<W: io::Write> {
+    compression: Compression,
+    encoder: Option<Encoder<W>>,
+    finished: Option<W>,
+}
+
+impl<W: io::Write> FinishingEncoder<W> {
+    fn new(compression: Compression, wtr: W) -> io::Result<FinishingEncoder<W>> {
+        Ok(FinishingEncoder {
+            compression,
+            encoder: Some(Encoder::new(compression, wtr)?),
+            finished: None,
+        })
+    }
+}
+
+impl<W: io::Write> io::Write for FinishingEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.encoder.is_none() {
+            let wtr = self.finished.take().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    "cannot write after failing to finish the compressed output",
+                )
+            })?;
+            self.encoder = Some(Encoder::new(self.compression, wtr)?);
+        }
+        self.encoder.as_mut().unwrap().get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(encoder) = self.encoder.take() {
+            self.finished = Some(encoder.finish()?);
+        }
+        match self.finished {
+            Some(ref mut wtr) => wtr.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: io::Write> Drop for FinishingEncoder<W> {
+    fn drop(&mut self) {
+        // the writer wasn't flushed, e.g. on an earlier error
+        if let Some(encoder) = self.encoder.take() {
+            let _ = encoder.finish();
+        }
+    }
+}
+
+/// The fingerprint of a CSV file for its statistics cache: the description
+/// of its data, as for an index, along with the modification time and inode
+/// of the file, as an edit in the middle of a file that keeps its size
+/// doesn't change the description.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsFingerprint {
+    #[serde(flatten)]
+    pub data: IndexHeader,
+    pub modified: Option<SystemTime>,
+    pub inode: Option<u64>,
+}
+
 #[derive(Debug)]
 pub struct Config {
-    path: Option<PathBuf>, // None implies <stdin>
+    path: Option<PathBuf>, // None implies <stdin>, unless url is set
+    url: Option<String>,
     idx_path: Option<PathBuf>,
     select_columns: Option<SelectColumns>,
     delimiter: u8,
@@ -79,6 +326,11 @@ pub struct Config {
     double_quote: bool,
     escape: Option<u8>,
     quoting: bool,
+    dialect: Option<Dialect>, // set when QSV_SNIFF_DIALECT is enabled
+    field_delimiter: Option<Delimiter>, // set for multi-byte delimiters
+    record_terminator: Option<Delimiter>,
+    input_encoding: Option<String>, // encoding label or "auto"
+    output_encoding: Option<String>,
 }
 
 // Empty trait as an alias for Seek and Read that avoids auto trait errors
@@ -88,26 +340,51 @@ impl<T: io::Seek + io::Read> SeekRead for T {}
 impl Config {
     pub fn new(path: &Option<String>) -> Config {
         let default_delim = match env::var("QSV_DEFAULT_DELIMITER") {
-            Ok(delim) => Delimiter::decode_delimiter(delim).unwrap().as_byte(),
-            _ => b',',
+            Ok(delim) => Delimiter::decode_delimiter(delim).unwrap(),
+            _ => Delimiter::from_byte(b','),
+        };
+        let delim_from_extension = |path: &Path| {
+            // look past the compression extension, e.g. "data.tsv.gz"
+            let data_path = if Compression::from_path(path).is_compressed() {
+                path.with_extension("")
+            } else {
+                path.to_path_buf()
+            };
+            if data_path
+                .extension()
+                .map_or(false, |v| v == "tsv" || v == "tab")
+            {
+                Delimiter::from_byte(b'\t')
+            } else if data_path.extension().map_or(false, |v| v == "csv") {
+                Delimiter::from_byte(b',')
+            } else {
+                default_delim
+            }
         };
-        let (path, mut delim) = match *path {
-            None => (None, default_delim),
-            Some(ref s) if s.deref() == "-" => (None, default_delim),
+        let (path, url, mut delim) = match *path {
+            None => (None, None, default_delim),
+            Some(ref s) if s.deref() == "-" => (None, None, default_delim),
+            Some(ref s) if util::is_url(s) => {
+                let delim = match reqwest::Url::parse(s) {
+                    Ok(url) => delim_from_extension(Path::new(url.path())),
+                    Err(_) => default_delim,
+                };
+                (None, Some(s.clone()), delim)
+            }
             Some(ref s) => {
                 let path = PathBuf::from(s);
-                let delim = if path.extension().map_or(false, |v| v == "tsv" || v == "tab") {
-                    b'\t'
-                } else if path.extension().map_or(false, |v| v == "csv") {
-                    b','
-                } else {
-                    default_delim
-                };
-                (Some(path), delim)
+                let delim = delim_from_extension(&path);
+                (Some(path), None, delim)
             }
         };
         let sniff_delimiter = env::var("QSV_SNIFF_DELIMITER").is_ok();
-        if sniff_delimiter && path.is_some() {
+        let sniff_dialect = env::var("QSV_SNIFF_DIALECT").is_ok();
+        let mut dialect = None;
+        if (sniff_delimiter || sniff_dialect)
+            && path.is_some()
+            && !Compression::detect(path.as_ref().unwrap())
+                .map_or(false, Compression::is_compressed)
+        {
             let sniff_path = path.as_ref().unwrap().to_str().unwrap();
 
             match Sniffer::new()
@@ -116,9 +393,11 @@ impl Config {
             {
                 Ok(metadata) => {
                     debug!("sniffed metadata: {metadata:?}");
-                    if sniff_delimiter {
-                        delim = metadata.dialect.delimiter;
-                        debug!("use sniffed delimiter");
+                    delim = Delimiter::from_byte(metadata.dialect.delimiter);
+                    debug!("use sniffed delimiter");
+                    if sniff_dialect {
+                        debug!("use sniffed dialect");
+                        dialect = Some(metadata.dialect);
                     }
                 }
                 Err(e) => {
@@ -127,29 +406,77 @@ impl Config {
             }
         }
 
+        let (quote, quoting) = match dialect {
+            Some(Dialect {
+                quote: Quote::Some(q),
+                ..
+            }) => (q, true),
+            Some(Dialect {
+                quote: Quote::None, ..
+            }) => (b'"', false),
+            None => (b'"', true),
+        };
+        let no_headers = dialect.as_ref().map_or(false, |d| !d.header.has_header_row);
+
         Config {
             path,
+            url,
             idx_path: None,
             select_columns: None,
-            delimiter: delim,
-            no_headers: false,
+            delimiter: delim.as_byte(),
+            no_headers,
             flexible: false,
             terminator: csv::Terminator::Any(b'\n'),
-            quote: b'"',
+            quote,
             quote_style: csv::QuoteStyle::Necessary,
             double_quote: true,
             escape: None,
-            quoting: true,
+            quoting,
+            dialect,
+            field_delimiter: Some(delim).filter(|d| d.is_multi_byte()),
+            record_terminator: None,
+            input_encoding: env::var("QSV_INPUT_ENCODING").ok(),
+            output_encoding: None,
         }
     }
 
     pub fn delimiter(mut self, d: Option<Delimiter>) -> Config {
         if let Some(d) = d {
             self.delimiter = d.as_byte();
+            self.field_delimiter = Some(d).filter(|d| d.is_multi_byte());
+        }
+        self
+    }
+
+    /// Set the record terminator used for both reading and writing. Unlike
+    /// `terminator`, this also accepts multi-byte terminators.
+    pub fn record_terminator(mut self, t: Option<Delimiter>) -> Config {
+        if let Some(t) = t {
+            self.terminator = csv::Terminator::Any(if t.is_multi_byte() {
+                RECORD_TERMINATOR_PLACEHOLDER
+            } else {
+                t.as_bytes()[0]
+            });
+            self.record_terminator = Some(t);
         }
         self
     }
 
+    /// Set the character encoding of the input, overriding QSV_INPUT_ENCODING.
+    /// Accepts any WHATWG encoding label, or "auto" to detect it.
+    pub fn input_encoding(mut self, label: Option<String>) -> Config {
+        if label.is_some() {
+            self.input_encoding = label;
+        }
+        self
+    }
+
+    /// Set the character encoding of the output. Defaults to UTF-8.
+    pub fn output_encoding(mut self, label: Option<String>) -> Config {
+        self.output_encoding = label;
+        self
+    }
+
     #[allow(dead_code)]
     pub fn get_delimiter(&self) -> u8 {
         self.delimiter
@@ -159,7 +486,12 @@ impl Config {
         if env::var("QSV_TOGGLE_HEADERS").unwrap_or_else(|_| "0".to_owned()) == "1" {
             yes = !yes;
         }
-        if env::var("QSV_NO_HEADERS").is_ok() {
+        if env::var("QSV_NO_HEADERS").is_ok()
+            || self
+                .dialect
+                .as_ref()
+                .map_or(false, |d| !d.header.has_header_row)
+        {
             self.no_headers = true;
         } else {
             self.no_headers = yes;
@@ -217,7 +549,35 @@ impl Config {
     }
 
     pub fn is_std(&self) -> bool {
-        self.path.is_none()
+        self.path.is_none() && self.url.is_none()
+    }
+
+    /// Whether multi-byte delimiters or record terminators are transcoded
+    /// for the CSV parser and writer. Transcoded data can't be seeked into.
+    fn is_transcoded(&self) -> bool {
+        self.field_delimiter.is_some()
+            || self
+                .record_terminator
+                .map_or(false, Delimiter::is_multi_byte)
+    }
+
+    /// Returns true if the input has to be decoded to UTF-8, in which case
+    /// byte offsets into the file don't match those of the parsed data.
+    fn is_reencoded(&self) -> bool {
+        self.input_encoding.as_deref().map_or(false, |label| {
+            Encoding::for_label(label.as_bytes()) != Some(UTF_8)
+        })
+    }
+
+    fn transcodings(&self) -> Vec<(Vec<u8>, u8)> {
+        let mut transcodings = Vec::with_capacity(2);
+        if let Some(d) = self.field_delimiter {
+            transcodings.push((d.as_bytes().to_vec(), FIELD_DELIMITER_PLACEHOLDER));
+        }
+        if let Some(t) = self.record_terminator.filter(|t| t.is_multi_byte()) {
+            transcodings.push((t.as_bytes().to_vec(), RECORD_TERMINATOR_PLACEHOLDER));
+        }
+        transcodings
     }
 
     pub fn selection(&self, first_record: &csv::ByteRecord) -> Result<Selection, String> {
@@ -225,8 +585,32 @@ impl Config {
             None => Err("Config has no 'SelectColums'. Did you call \
                          Config::select?"
                 .to_owned()),
-            Some(ref sel) => sel.selection(first_record, !self.no_headers),
+            Some(ref sel) => {
+                // type selectors (e.g. ':numeric') need the types of the columns
+                let types = if sel.needs_types() {
+                    Some(self.column_types()?)
+                } else {
+                    None
+                };
+                sel.typed_selection(first_record, !self.no_headers, types.as_deref())
+            }
+        }
+    }
+
+    /// The types of the columns, as reported by `qsv stats --dates`. They're
+    /// only inferred once per input, as selections may be made in each job.
+    fn column_types(&self) -> Result<Vec<String>, String> {
+        let key = match self.path {
+            Some(ref path) => (path.clone(), self.delimiter, self.no_headers),
+            None => return cmd::stats::column_types(self).map_err(|e| e.to_string()),
+        };
+        let mut column_types = COLUMN_TYPES.get_or_init(Default::default).lock().unwrap();
+        if let Some(types) = column_types.get(&key) {
+            return Ok(types.clone());
         }
+        let types = cmd::stats::column_types(self).map_err(|e| e.to_string())?;
+        column_types.insert(key, types.clone());
+        Ok(types)
     }
 
     pub fn write_headers<R: io::Read, W: io::Write>(
@@ -252,31 +636,136 @@ impl Config {
     }
 
     pub fn reader_file(&self) -> io::Result<csv::Reader<fs::File>> {
-        match self.path {
-            None => Err(io::Error::new(
+        let (file, compression, name) = match (&self.path, &self.url) {
+            (Some(p), _) => {
+                let compression = Compression::detect(p)?;
+                (fs::File::open(p)?, compression, p.display().to_string())
+            }
+            // URLs are downloaded to a temporary file, which is deleted
+            // when the reader is dropped
+            (None, Some(url)) => {
+                let (file, compression) = download(url)?;
+                (file, compression, url.clone())
+            }
+            (None, None) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Cannot use <stdin> here",
+                ))
+            }
+        };
+        if compression.is_compressed() {
+            return Err(io::Error::new(
                 io::ErrorKind::Other,
-                "Cannot use <stdin> here",
-            )),
-            Some(ref p) => fs::File::open(p).map(|f| self.from_reader(f)),
+                format!("Cannot use compressed file {name} here. Please decompress it first."),
+            ));
         }
+        if self.is_transcoded() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Cannot use multi-byte delimiters or record terminators here. \
+                 Please convert the data with 'qsv input' first.",
+            ));
+        }
+        if self.is_reencoded() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Cannot use non UTF-8 encoded data here. \
+                 Please convert the data with 'qsv input' first.",
+            ));
+        }
+        self.from_seek_reader(file)
+    }
+
+    /// Spool stdin, so it can be read more than once and seeked into.
+    ///
+    /// Up to QSV_SPOOL_SIZE bytes, stdin is kept in memory. Beyond that, it's
+    /// written to a temporary file and indexed as it's written, so commands
+    /// can use their indexed/parallel code paths. stdin is only spooled once
+    /// per process, and the temporary files are removed by `StdinSpoolGuard`.
+    fn spool_stdin(&self) -> io::Result<&'static StdinSpool> {
+        STDIN_SPOOL.get_or_try_init(|| {
+            let limit = spool_size();
+            let mut rdr = self.io_reader()?;
+            let mut buffer = Vec::new();
+            rdr.by_ref().take(limit + 1).read_to_end(&mut buffer)?;
+            if buffer.len() as u64 <= limit {
+                return Ok(StdinSpool::Memory(buffer));
+            }
+
+            let (csv_file, csv) = tempfile::Builder::new()
+                .prefix("qsv-stdin-")
+                .suffix(".csv")
+                .tempfile()?
+                .keep()
+                .map_err(|e| e.error)?;
+            debug!("spooling stdin to {}", csv.display());
+            let idx = util::idx_path(&csv);
+            let spooled = (|| -> io::Result<()> {
+                let tee = TeeReader {
+                    rdr: io::Cursor::new(buffer).chain(rdr),
+                    wtr: io::BufWriter::new(csv_file),
+                };
+                // the spooled data is already decoded, so there are no
+                // preamble rows to skip
+                let mut csv_rdr = self.from_reader(tee);
+                let idx_wtr = io::BufWriter::new(fs::File::create(&idx)?);
+                create_index_with(&mut csv_rdr, idx_wtr, |csv_rdr| {
+                    csv_rdr.get_mut().wtr.flush()?;
+                    IndexHeader::describe(&mut fs::File::open(&csv)?, csv_rdr, self.delimiter)
+                })
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+            })();
+            if let Err(e) = spooled {
+                let _ = fs::remove_file(&csv);
+                let _ = fs::remove_file(&idx);
+                return Err(e);
+            }
+            Ok(StdinSpool::Disk { csv, idx })
+        })
     }
 
     pub fn reader_file_stdin(&self) -> io::Result<csv::Reader<Box<dyn SeekRead + 'static>>> {
         Ok(match self.path {
-            None => {
-                // Create a buffer in memory when stdin needs to be indexed
-                let mut buffer: Vec<u8> = Vec::new();
-                let stdin = io::stdin();
-                stdin.lock().read_to_end(&mut buffer)?;
-                self.from_reader(Box::new(io::Cursor::new(buffer)))
+            Some(ref p)
+                if !self.is_transcoded()
+                    && !self.is_reencoded()
+                    && !Compression::detect(p)?.is_compressed() =>
+            {
+                self.from_seek_reader(Box::new(fs::File::open(p)?) as Box<dyn SeekRead>)?
+            }
+            None if self.url.is_none() => match self.spool_stdin()? {
+                StdinSpool::Memory(buffer) => {
+                    self.from_reader(Box::new(io::Cursor::new(&buffer[..])))
+                }
+                StdinSpool::Disk { csv, .. } => self.from_reader(Box::new(fs::File::open(csv)?)),
+            },
+            _ => {
+                // Spool a URL, compressed, transcoded or non UTF-8 data when
+                // it needs to be seeked into - in memory if it's small enough,
+                // to a temporary file otherwise
+                let mut spooled = tempfile::spooled_tempfile(spool_size() as usize);
+                io::copy(&mut self.io_reader()?, &mut spooled)?;
+                spooled.seek(SeekFrom::Start(0))?;
+                self.from_reader(Box::new(spooled))
             }
-            Some(ref p) => self.from_reader(Box::new(fs::File::open(p).unwrap())),
         })
     }
 
     pub fn index_files(&self) -> io::Result<Option<(csv::Reader<fs::File>, fs::File)>> {
-        let (csv_file, idx_file) = match (&self.path, &self.idx_path) {
-            (&None, &None) => return Ok(None),
+        let (csv_path, idx_path, idx_file) = match (&self.path, &self.idx_path) {
+            (&None, &None) if self.url.is_some() => return Ok(None),
+            (&None, &None) => {
+                // large inputs piped to stdin are spooled to disk and indexed
+                return match self.spool_stdin()? {
+                    StdinSpool::Memory(_) => Ok(None),
+                    StdinSpool::Disk { csv, idx } => {
+                        let mut idx_file = fs::File::open(idx)?;
+                        IndexHeader::read(&mut idx_file)?;
+                        Ok(Some((self.from_reader(fs::File::open(csv)?), idx_file)))
+                    }
+                };
+            }
             (&None, &Some(_)) => {
                 return Err(io::Error::new(
                     io::ErrorKind::Other,
@@ -285,33 +774,99 @@ impl Config {
                 ));
             }
             (&Some(ref p), &None) => {
+                // Compressed, transcoded or non UTF-8 files can't be seeked
+                // into, so we always stream them.
+                if self.is_transcoded()
+                    || self.is_reencoded()
+                    || Compression::detect(p)?.is_compressed()
+                {
+                    return Ok(None);
+                }
                 // We generally don't want to report an error here, since we're
                 // passively trying to find an index.
                 let idx_file = match fs::File::open(&util::idx_path(p)) {
                     Err(e) => {
                         error!("Cannot read index file - {p:?}: {e}");
-                        return Ok(None);
+                        None
                     }
-                    Ok(f) => f,
+                    Ok(f) => Some(f),
                 };
-                (fs::File::open(p)?, idx_file)
+                (p, util::idx_path(p), idx_file)
             }
-            (&Some(ref p), &Some(ref ip)) => (fs::File::open(p)?, fs::File::open(ip)?),
+            (&Some(ref p), &Some(ref ip)) => (p, ip.clone(), Some(fs::File::open(ip)?)),
         };
-        // If the CSV data was last modified after the index file was last
-        // modified, then return an error and demand the user regenerate the
-        // index.
-        let data_modified = util::last_modified(&csv_file.metadata()?);
-        let idx_modified = util::last_modified(&idx_file.metadata()?);
-        if data_modified > idx_modified {
-            return Err(io::Error::new(
+
+        // Without an index, there's nothing to check, and the data only needs
+        // to be described if it's large enough to be indexed automatically.
+        let autoindex_size = autoindex_size();
+        if idx_file.is_none() {
+            match autoindex_size {
+                Some(size) if fs::metadata(csv_path)?.len() > size => {}
+                _ => return Ok(None),
+            }
+        }
+        let mut csv_file = fs::File::open(csv_path)?;
+        let mut csv_rdr = self.from_seek_reader(fs::File::open(csv_path)?)?;
+        let current = IndexHeader::describe(&mut csv_file, &mut csv_rdr, self.delimiter)?;
+
+        // If the index doesn't match the CSV data (anymore), then return an
+        // error explaining why, and demand the user regenerate the index -
+        // unless automatic indexing is enabled, in which case it's rebuilt.
+        let reject = |reason: &str| {
+            io::Error::new(
                 io::ErrorKind::Other,
-                "The CSV file was modified after the index file. \
-                 Please re-create the index.",
-            ));
+                format!(
+                    "Cannot use the index {}: {reason}. Please re-create the index.",
+                    idx_path.display()
+                ),
+            )
+        };
+        let stale_reason = match idx_file {
+            Some(mut idx_file) => {
+                let checked = IndexHeader::read(&mut idx_file)
+                    .map_err(|e| e.to_string())
+                    .and_then(|header| header.check(&current));
+                match checked {
+                    Ok(()) => return Ok(Some((csv_rdr, idx_file))),
+                    Err(reason) if autoindex_size.is_none() => return Err(reject(&reason)),
+                    Err(reason) => Some(reason),
+                }
+            }
+            None => None,
+        };
+
+        match self.autoindex(csv_path, &idx_path, &current) {
+            Ok(idx_file) => Ok(Some((csv_rdr, idx_file))),
+            Err(e) => {
+                let _ = fs::remove_file(&idx_path);
+                match stale_reason {
+                    Some(reason) => Err(reject(&reason)),
+                    // an index is only an optimization, so stream the data
+                    None => {
+                        warn!("Cannot create index {}: {e}", idx_path.display());
+                        Ok(None)
+                    }
+                }
+            }
         }
-        let csv_rdr = self.from_reader(csv_file);
-        Ok(Some((csv_rdr, idx_file)))
+    }
+
+    /// Create the index `idx_path` of the CSV data at `csv_path`, described
+    /// by `header`, returning it positioned at the start of its offsets.
+    fn autoindex(
+        &self,
+        csv_path: &Path,
+        idx_path: &Path,
+        header: &IndexHeader,
+    ) -> io::Result<fs::File> {
+        info!("automatically indexing {}", csv_path.display());
+        let mut csv_rdr = self.from_seek_reader(fs::File::open(csv_path)?)?;
+        let idx_wtr = io::BufWriter::new(fs::File::create(idx_path)?);
+        create_index(header, &mut csv_rdr, idx_wtr)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        let mut idx_file = fs::File::open(idx_path)?;
+        IndexHeader::read(&mut idx_file)?;
+        Ok(idx_file)
     }
 
     pub fn indexed(&self) -> CliResult<Option<Indexed<fs::File, fs::File>>> {
@@ -321,20 +876,220 @@ impl Config {
         }
     }
 
+    /// Open the key index of the CSV data created by `qsv index --column`,
+    /// which is `<input>.kidx` unless `key_idx_path` is given. Returns
+    /// `None` if there's no such index, or if the data can't be seeked into.
+    pub fn key_indexed(
+        &self,
+        key_idx_path: Option<&Path>,
+    ) -> CliResult<Option<KeyIndexed<fs::File, fs::File>>> {
+        let csv_path = match self.path {
+            Some(ref p) => p,
+            None => return Ok(None),
+        };
+        if self.is_transcoded()
+            || self.is_reencoded()
+            || Compression::detect(csv_path)?.is_compressed()
+        {
+            return Ok(None);
+        }
+        let idx_path = match key_idx_path {
+            Some(p) => p.to_path_buf(),
+            None => util::key_idx_path(csv_path),
+        };
+        let mut idx_file = match fs::File::open(&idx_path) {
+            Err(_) if key_idx_path.is_none() => return Ok(None),
+            Err(e) => return fail!(format!("Cannot read key index {}: {e}", idx_path.display())),
+            Ok(f) => f,
+        };
+
+        let mut csv_file = fs::File::open(csv_path)?;
+        let mut csv_rdr = self.from_seek_reader(fs::File::open(csv_path)?)?;
+        let current = IndexHeader::describe(&mut csv_file, &mut csv_rdr, self.delimiter)?;
+        let checked = read_key_index_header(&mut idx_file)
+            .map_err(|e| e.to_string())
+            .and_then(|header| {
+                // unlike offsets, keys depend on whether there's a header row
+                if header.has_headers != current.has_headers {
+                    return Err(format!(
+                        "it was created {} a header row",
+                        if header.has_headers {
+                            "with"
+                        } else {
+                            "without"
+                        }
+                    ));
+                }
+                header.check(&current)
+            });
+        if let Err(reason) = checked {
+            return fail!(format!(
+                "Cannot use the key index {}: {reason}. Please re-create the key index.",
+                idx_path.display()
+            ));
+        }
+        Ok(Some(KeyIndexed::open(csv_rdr, idx_file)?))
+    }
+
+    /// The path of the statistics cache of the CSV data written by
+    /// `qsv stats --cache`, which is `<input>.stats.json`, and the fingerprint of
+    /// the file, to check that the cache is still valid. Returns `None` if
+    /// the data isn't a plain file, e.g. <stdin> or compressed data.
+    pub fn stats_cache(&self) -> io::Result<Option<(PathBuf, StatsFingerprint)>> {
+        let csv_path = match self.path {
+            Some(ref p) => p,
+            None => return Ok(None),
+        };
+        if self.is_transcoded()
+            || self.is_reencoded()
+            || Compression::detect(csv_path)?.is_compressed()
+        {
+            return Ok(None);
+        }
+        let mut csv_file = fs::File::open(csv_path)?;
+        let mut csv_rdr = self.from_seek_reader(fs::File::open(csv_path)?)?;
+        let metadata = csv_file.metadata()?;
+        #[cfg(unix)]
+        let inode = Some(std::os::unix::fs::MetadataExt::ino(&metadata));
+        #[cfg(not(unix))]
+        let inode = None;
+        let fingerprint = StatsFingerprint {
+            data: IndexHeader::describe(&mut csv_file, &mut csv_rdr, self.delimiter)?,
+            modified: metadata.modified().ok(),
+            inode,
+        };
+        Ok(Some((util::stats_cache_path(csv_path), fingerprint)))
+    }
+
     pub fn io_reader(&self) -> io::Result<Box<dyn io::Read + 'static>> {
-        Ok(match self.path {
-            None => Box::new(io::stdin()),
+        if self.path.is_none() && self.url.is_none() {
+            // stdin can only be read once, so read it from its spool if it
+            // was spooled before. It is already decoded and transcoded.
+            match STDIN_SPOOL.get() {
+                Some(StdinSpool::Memory(buffer)) => return Ok(Box::new(&buffer[..])),
+                Some(StdinSpool::Disk { csv, .. }) => return Ok(Box::new(fs::File::open(csv)?)),
+                None => {}
+            }
+        }
+        let rdr: Box<dyn io::Read + 'static> = match self.path {
+            None if self.url.is_some() => {
+                let url = self.url.as_deref().unwrap();
+                let mut rdr = io::BufReader::new(http_get(url)?);
+                let compression = Compression::from_magic(rdr.fill_buf()?);
+                self.decode(compression.decoder(rdr)?)?
+            }
+            None => {
+                let mut stdin = io::BufReader::new(io::stdin());
+                let compression = Compression::from_magic(stdin.fill_buf()?);
+                if compression.is_compressed() {
+                    self.decode(compression.decoder(stdin)?)?
+                } else if self.input_encoding.is_some() {
+                    self.decode(Box::new(stdin))?
+                } else {
+                    Box::new(stdin)
+                }
+            }
             Some(ref p) => match fs::File::open(p) {
                 Ok(x) => {
-                    let transcoded = DecodeReaderBytes::new(x);
-                    Box::new(transcoded)
+                    let compression = Compression::detect(p)?;
+                    self.decode(compression.decoder(io::BufReader::new(x))?)?
                 }
                 Err(err) => {
                     let msg = format!("failed to open {}: {}", p.display(), err);
                     return Err(io::Error::new(io::ErrorKind::NotFound, msg));
                 }
             },
-        })
+        };
+        let preamble_rows = self.preamble_rows();
+        let rdr: Box<dyn io::Read + 'static> = if preamble_rows == 0 {
+            rdr
+        } else {
+            let mut rdr = io::BufReader::new(rdr);
+            let mut line = Vec::new();
+            for _ in 0..preamble_rows {
+                rdr.read_until(b'\n', &mut line)?;
+            }
+            Box::new(rdr)
+        };
+        if self.is_transcoded() {
+            Ok(Box::new(TranscodeReader::new(
+                rdr,
+                self.transcodings(),
+                Some(self.quote).filter(|_| self.quoting),
+            )))
+        } else {
+            Ok(rdr)
+        }
+    }
+
+    /// The number of lines before the header row (or the first data row)
+    /// that are skipped when reading, as sniffed with `QSV_SNIFF_DIALECT`.
+    pub fn preamble_rows(&self) -> usize {
+        self.dialect
+            .as_ref()
+            .map_or(0, |d| d.header.num_preamble_rows)
+    }
+
+    /// Decodes the input to UTF-8, using the configured input encoding if
+    /// any. Otherwise, only a BOM can change the encoding from UTF-8.
+    fn decode(&self, rdr: Box<dyn io::Read + 'static>) -> io::Result<Box<dyn io::Read + 'static>> {
+        let (encoding, rdr) = match self.input_encoding.as_deref() {
+            None => (None, rdr),
+            Some(label) if label.eq_ignore_ascii_case("auto") => {
+                let mut rdr = rdr;
+                let mut sample = Vec::with_capacity(ENCODING_DETECTION_SAMPLE);
+                rdr.by_ref()
+                    .take(ENCODING_DETECTION_SAMPLE as u64)
+                    .read_to_end(&mut sample)?;
+                let mut detector = chardetng::EncodingDetector::new();
+                detector.feed(&sample, sample.len() < ENCODING_DETECTION_SAMPLE);
+                let encoding = detector.guess(None, true);
+                debug!("detected encoding: {}", encoding.name());
+                let rdr: Box<dyn io::Read + 'static> = Box::new(io::Cursor::new(sample).chain(rdr));
+                (Some(encoding), rdr)
+            }
+            Some(label) => (Some(lookup_encoding(label)?), rdr),
+        };
+        Ok(Box::new(
+            DecodeReaderBytesBuilder::new()
+                .encoding(encoding)
+                .build(rdr),
+        ))
+    }
+
+    /// Like `from_reader`, but skips the preamble rows of a seekable reader
+    /// while keeping the record positions relative to the start of the file,
+    /// so that indexes remain valid.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_seek_reader<R: io::Read + io::Seek>(&self, mut rdr: R) -> io::Result<csv::Reader<R>> {
+        let preamble_rows = self.preamble_rows();
+        if preamble_rows == 0 {
+            return Ok(self.from_reader(rdr));
+        }
+        let mut offset = 0;
+        {
+            let mut buf_rdr = io::BufReader::new(&mut rdr);
+            let mut line = Vec::new();
+            for _ in 0..preamble_rows {
+                offset += buf_rdr.read_until(b'\n', &mut line)? as u64;
+            }
+        }
+        rdr.seek(SeekFrom::Start(offset))?;
+
+        // read the header row first, as seeking the csv reader afterwards
+        // would otherwise read it from the start of the file
+        let mut csv_rdr = self.from_reader(rdr);
+        let (byte, line) = if csv_rdr.has_headers() {
+            csv_rdr.byte_headers()?;
+            (csv_rdr.position().byte(), csv_rdr.position().line())
+        } else {
+            (0, 1)
+        };
+        let mut pos = csv::Position::new();
+        pos.set_byte(offset + byte)
+            .set_line(preamble_rows as u64 + line);
+        csv_rdr.seek_raw(SeekFrom::Start(pos.byte()), pos)?;
+        Ok(csv_rdr)
     }
 
     #[allow(clippy::wrong_self_convention)]
@@ -347,9 +1102,17 @@ impl Config {
             .ok()
             .map(|s| s.as_bytes().first().unwrap().to_owned());
 
+        let flexible = self.flexible || self.dialect.as_ref().map_or(false, |d| d.flexible);
+
+        let terminator = match self.record_terminator {
+            None => csv::Terminator::CRLF,
+            Some(_) => self.terminator,
+        };
+
         csv::ReaderBuilder::new()
-            .flexible(self.flexible)
+            .flexible(flexible)
             .delimiter(self.delimiter)
+            .terminator(terminator)
             .has_headers(!self.no_headers)
             .quote(self.quote)
             .quoting(self.quoting)
@@ -360,10 +1123,58 @@ impl Config {
     }
 
     pub fn io_writer(&self) -> io::Result<Box<dyn io::Write + 'static>> {
-        Ok(match self.path {
+        if let Some(ref url) = self.url {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("Cannot write to URL {url}. Please specify a file path."),
+            ));
+        }
+        let wtr: Box<dyn io::Write + 'static> = match self.path {
             None => Box::new(io::stdout()),
-            Some(ref p) => Box::new(fs::File::create(p)?),
-        })
+            Some(ref p) => Compression::from_path(p).encoder(fs::File::create(p)?)?,
+        };
+        let wtr: Box<dyn io::Write + 'static> = match self.output_encoding.as_deref() {
+            Some(label) => {
+                let encoding = lookup_encoding(label)?;
+                if encoding == UTF_8 {
+                    wtr
+                } else {
+                    Box::new(EncodeWriter::new(wtr, encoding))
+                }
+            }
+            None => wtr,
+        };
+        if self.is_transcoded() {
+            let transcodings = self
+                .transcodings()
+                .into_iter()
+                .map(|(seq, placeholder)| (placeholder, seq))
+                .collect();
+            let mut separators = match self.terminator {
+                csv::Terminator::Any(b) => vec![b],
+                _ => vec![b'\r', b'\n'],
+            };
+            separators.push(self.delimiter);
+            let quote = if matches!(self.quote_style, csv::QuoteStyle::Never) {
+                None
+            } else {
+                Some(self.quote)
+            };
+            let escape = if self.double_quote {
+                None
+            } else {
+                Some(self.escape.unwrap_or(b'\\'))
+            };
+            Ok(Box::new(TranscodeWriter::new(
+                wtr,
+                transcodings,
+                separators,
+                quote,
+                escape,
+            )))
+        } else {
+            Ok(wtr)
+        }
     }
 
     #[allow(clippy::wrong_self_convention)]
@@ -384,3 +1195,388 @@ impl Config {
             .from_writer(wtr)
     }
 }
+
+/// TranscodeReader replaces multi-byte sequences (i.e. delimiters and record
+/// terminators) with the single placeholder bytes the CSV parser is set up with.
+/// Sequences inside quoted fields are left as is. Placeholder bytes outside of
+/// quoted fields would be parsed as delimiters or terminators, so they're
+/// rejected.
+struct TranscodeReader<R> {
+    rdr: R,
+    transcodings: Vec<(Vec<u8>, u8)>,
+    quote: Option<u8>,
+    in_quotes: bool,
+    // raw bytes read, but not yet transcoded
+    buf: Vec<u8>,
+    // transcoded bytes, not yet returned to the caller
+    out: Vec<u8>,
+    out_pos: usize,
+    eof: bool,
+}
+
+impl<R: io::Read> TranscodeReader<R> {
+    fn new(rdr: R, transcodings: Vec<(Vec<u8>, u8)>, quote: Option<u8>) -> TranscodeReader<R> {
+        TranscodeReader {
+            rdr,
+            transcodings,
+            quote,
+            in_quotes: false,
+            buf: Vec::with_capacity(TRANSCODE_CHUNK_SIZE),
+            out: Vec::with_capacity(TRANSCODE_CHUNK_SIZE),
+            out_pos: 0,
+            eof: false,
+        }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        if !self.eof {
+            let start = self.buf.len();
+            self.buf.resize(start + TRANSCODE_CHUNK_SIZE, 0);
+            let n = self.rdr.read(&mut self.buf[start..])?;
+            self.buf.truncate(start + n);
+            self.eof = n == 0;
+        }
+
+        self.out.clear();
+        self.out_pos = 0;
+        let mut i = 0;
+        'bytes: while i < self.buf.len() {
+            let rest = &self.buf[i..];
+            if !self.in_quotes {
+                for (seq, placeholder) in &self.transcodings {
+                    if rest.starts_with(seq) {
+                        self.out.push(*placeholder);
+                        i += seq.len();
+                        continue 'bytes;
+                    }
+                    // a sequence may be split across reads, so wait for more data
+                    if !self.eof && seq.starts_with(rest) {
+                        break 'bytes;
+                    }
+                }
+                if let Some((_, placeholder)) =
+                    self.transcodings.iter().find(|(_, p)| *p == rest[0])
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "The data contains the byte {placeholder:#04x} outside of quotes, \
+                             which can't be read with multi-byte delimiters or record terminators."
+                        ),
+                    ));
+                }
+            }
+            // the quote is only toggled once the byte is consumed, as a
+            // sequence starting with it may be deferred to the next read
+            if Some(rest[0]) == self.quote {
+                self.in_quotes = !self.in_quotes;
+            }
+            self.out.push(rest[0]);
+            i += 1;
+        }
+        self.buf.drain(..i);
+        Ok(())
+    }
+}
+
+impl<R: io::Read> io::Read for TranscodeReader<R> {
+    fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
+        while self.out_pos == self.out.len() {
+            if self.eof && self.buf.is_empty() {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+        let n = dst.len().min(self.out.len() - self.out_pos);
+        dst[..n].copy_from_slice(&self.out[self.out_pos..self.out_pos + n]);
+        self.out_pos += n;
+        Ok(n)
+    }
+}
+
+/// TranscodeWriter replaces the placeholder bytes the CSV writer is set up
+/// with by their multi-byte sequences (i.e. delimiters and record terminators).
+///
+/// The CSV writer only quotes fields containing the placeholder bytes, so the
+/// unquoted fields that contain (part of) a multi-byte sequence are quoted
+/// here, and the placeholder bytes found in quoted fields are left as is.
+struct TranscodeWriter<W> {
+    wtr: W,
+    transcodings: Vec<(u8, Vec<u8>)>,
+    // the single byte delimiter and record terminators, which aren't transcoded
+    separators: Vec<u8>,
+    // None if fields are never quoted
+    quote: Option<u8>,
+    // the escape byte, if quotes are escaped instead of doubled
+    escape: Option<u8>,
+    in_quotes: bool,
+    escaped: bool,
+    // the unquoted field being written, until its end shows if it needs quotes
+    field: Vec<u8>,
+    buf: Vec<u8>,
+}
+
+impl<W: io::Write> TranscodeWriter<W> {
+    fn new(
+        wtr: W,
+        transcodings: Vec<(u8, Vec<u8>)>,
+        separators: Vec<u8>,
+        quote: Option<u8>,
+        escape: Option<u8>,
+    ) -> TranscodeWriter<W> {
+        TranscodeWriter {
+            wtr,
+            transcodings,
+            separators,
+            quote,
+            escape,
+            in_quotes: false,
+            escaped: false,
+            field: Vec::new(),
+            buf: Vec::with_capacity(TRANSCODE_CHUNK_SIZE),
+        }
+    }
+
+    /// Whether the field, followed by any delimiter or terminator, would
+    /// not be read back as is, i.e. if a multi-byte sequence starts in it.
+    fn needs_quotes(&self, field: &[u8]) -> bool {
+        (0..field.len()).any(|i| {
+            self.transcodings
+                .iter()
+                .any(|(_, seq)| field[i..].starts_with(seq) || seq.starts_with(&field[i..]))
+        })
+    }
+
+    fn end_field(&mut self) {
+        match self.quote {
+            Some(quote) if self.needs_quotes(&self.field) => {
+                self.buf.push(quote);
+                self.buf.append(&mut self.field);
+                self.buf.push(quote);
+            }
+            _ => self.buf.append(&mut self.field),
+        }
+    }
+}
+
+impl<W: io::Write> io::Write for TranscodeWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.clear();
+        for &b in data {
+            if self.in_quotes {
+                if self.escaped {
+                    self.escaped = false;
+                } else if Some(b) == self.escape {
+                    self.escaped = true;
+                } else if Some(b) == self.quote {
+                    self.in_quotes = false;
+                }
+                self.buf.push(b);
+            } else if Some(b) == self.quote && self.field.is_empty() {
+                self.in_quotes = true;
+                self.buf.push(b);
+            } else if let Some(i) = self
+                .transcodings
+                .iter()
+                .position(|(placeholder, _)| *placeholder == b)
+            {
+                self.end_field();
+                self.buf.extend_from_slice(&self.transcodings[i].1);
+            } else if self.separators.contains(&b) {
+                self.end_field();
+                self.buf.push(b);
+            } else {
+                self.field.push(b);
+            }
+        }
+        self.wtr.write_all(&self.buf)?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.buf.clear();
+        self.end_field();
+        self.wtr.write_all(&self.buf)?;
+        self.wtr.flush()
+    }
+}
+
+/// stdin, spooled by `Config::spool_stdin`.
+enum StdinSpool {
+    Memory(Vec<u8>),
+    Disk { csv: PathBuf, idx: PathBuf },
+}
+
+static STDIN_SPOOL: OnceCell<StdinSpool> = OnceCell::new();
+
+/// The types of the columns of the inputs for type selectors, by path,
+/// delimiter and header presence.
+type ColumnTypes = HashMap<(PathBuf, u8, bool), Vec<String>>;
+
+static COLUMN_TYPES: OnceCell<Mutex<ColumnTypes>> = OnceCell::new();
+
+/// Files larger than this size (in bytes) are indexed automatically by
+/// commands that can use an index, and stale indexes are rebuilt. Disabled
+/// unless QSV_AUTOINDEX_SIZE is set.
+fn autoindex_size() -> Option<u64> {
+    env::var("QSV_AUTOINDEX_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+}
+
+fn spool_size() -> u64 {
+    env::var("QSV_SPOOL_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_SPOOL_SIZE)
+}
+
+/// Removes the temporary files stdin was spooled to, if any, when dropped.
+/// As statics are never dropped, this guard has to be held while a command
+/// runs, so that the files are also removed when it fails or panics.
+pub struct StdinSpoolGuard;
+
+impl Drop for StdinSpoolGuard {
+    fn drop(&mut self) {
+        if let Some(StdinSpool::Disk { csv, idx }) = STDIN_SPOOL.get() {
+            let _ = fs::remove_file(csv);
+            let _ = fs::remove_file(idx);
+        }
+    }
+}
+
+/// A reader that also writes all the data it reads to `wtr`.
+struct TeeReader<R, W> {
+    rdr: R,
+    wtr: W,
+}
+
+impl<R: io::Read, W: io::Write> io::Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.rdr.read(buf)?;
+        self.wtr.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+/// Send a GET request for `url`, using the same client settings as `fetch`.
+/// Proxies are configured with the HTTP_PROXY, HTTPS_PROXY and NO_PROXY
+/// environment variables.
+fn http_get(url: &str) -> io::Result<reqwest::blocking::Response> {
+    let to_io_error = |e: reqwest::Error| {
+        io::Error::new(io::ErrorKind::Other, format!("failed to open {url}: {e}"))
+    };
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(util::DEFAULT_USER_AGENT)
+        .brotli(true)
+        .gzip(true)
+        // large files take a while to stream, so only the connection times out
+        .timeout(None)
+        .connect_timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(to_io_error)?;
+    client
+        .get(url)
+        .send()
+        .and_then(reqwest::blocking::Response::error_for_status)
+        .map_err(to_io_error)
+}
+
+/// Download `url` to an anonymous temporary file, returning the file
+/// rewound to its start along with the compression of its contents.
+fn download(url: &str) -> io::Result<(fs::File, Compression)> {
+    let mut file = tempfile::tempfile()?;
+    io::copy(&mut http_get(url)?, &mut file)?;
+    file.seek(SeekFrom::Start(0))?;
+    let mut magic = Vec::with_capacity(COMPRESSION_MAGIC_LEN);
+    (&mut file)
+        .take(COMPRESSION_MAGIC_LEN as u64)
+        .read_to_end(&mut magic)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok((file, Compression::from_magic(&magic)))
+}
+
+fn lookup_encoding(label: &str) -> io::Result<&'static Encoding> {
+    Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unknown character encoding '{label}'."),
+        )
+    })
+}
+
+/// EncodeWriter converts the UTF-8 output of the CSV writer into another
+/// character encoding. Characters that can't be represented in the target
+/// encoding are reported as errors.
+struct EncodeWriter<W: io::Write> {
+    wtr: W,
+    encoder: encoding_rs::Encoder,
+    // trailing bytes of an incomplete UTF-8 sequence
+    pending: Vec<u8>,
+    buf: Vec<u8>,
+}
+
+impl<W: io::Write> EncodeWriter<W> {
+    fn new(wtr: W, encoding: &'static Encoding) -> EncodeWriter<W> {
+        EncodeWriter {
+            wtr,
+            encoder: encoding.new_encoder(),
+            pending: Vec::new(),
+            buf: Vec::with_capacity(TRANSCODE_CHUNK_SIZE),
+        }
+    }
+
+    fn encode(&mut self, src: &str, last: bool) -> io::Result<()> {
+        self.buf.clear();
+        if let Some(needed) = self
+            .encoder
+            .max_buffer_length_from_utf8_without_replacement(src.len())
+        {
+            self.buf.reserve(needed);
+        }
+        let (result, _) =
+            self.encoder
+                .encode_from_utf8_to_vec_without_replacement(src, &mut self.buf, last);
+        if let encoding_rs::EncoderResult::Unmappable(c) = result {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Cannot encode '{c}' as {}.", self.encoder.encoding().name()),
+            ));
+        }
+        self.wtr.write_all(&self.buf)
+    }
+}
+
+impl<W: io::Write> io::Write for EncodeWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(data);
+        let valid_up_to = match std::str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            // an incomplete sequence at the end is completed by the next write
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Cannot change the encoding of data that is not valid UTF-8.",
+                ));
+            }
+        };
+        let pending = std::mem::take(&mut self.pending);
+        // safe to unwrap, validated above
+        self.encode(std::str::from_utf8(&pending[..valid_up_to]).unwrap(), false)?;
+        self.pending = pending[valid_up_to..].to_vec();
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.wtr.flush()
+    }
+}
+
+impl<W: io::Write> Drop for EncodeWriter<W> {
+    fn drop(&mut self) {
+        // let stateful encodings (e.g. ISO-2022-JP) return to their initial state
+        let _ = self.encode("", true);
+        let _ = self.wtr.flush();
+    }
+}
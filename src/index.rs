@@ -1,14 +1,289 @@
-use std::io;
+use std::borrow::Cow;
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::ops;
+use std::str;
 
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use csv_index::RandomAccessSimple;
+use serde::{Deserialize, Serialize};
 
+use crate::select::Selection;
 use crate::CliResult;
 
+// index files start with these magic bytes, followed by the format version
+const INDEX_MAGIC: &[u8; 6] = b"QSVIDX";
+const INDEX_VERSION: u16 = 1;
+// key indexes start with these magic bytes, followed by an index header
+const KEY_INDEX_MAGIC: &[u8; 6] = b"QSVKEY";
+// number of bytes hashed at the start and at the end of the CSV data
+const FINGERPRINT_SAMPLE: u64 = 64 * (1 << 10);
+
+/// IndexHeader describes the CSV data an index was created for, so that
+/// a stale or mismatched index can be detected - and the reason explained -
+/// without relying on file modification times.
+///
+/// It's written in front of the offsets of the index, as a big-endian:
+/// magic bytes, version (u16), file size (u64), head and tail hashes (u64),
+/// delimiter (u8), header presence (u8) and column count (u64).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexHeader {
+    pub file_size: u64,
+    pub head_hash: u64,
+    pub tail_hash: u64,
+    pub delimiter: u8,
+    pub has_headers: bool,
+    pub column_count: u64,
+}
+
+impl IndexHeader {
+    /// Describe the raw CSV data in `csv_data`, as parsed by `csv_rdr`.
+    pub fn describe<D: Read + Seek, R: Read>(
+        csv_data: &mut D,
+        csv_rdr: &mut csv::Reader<R>,
+        delimiter: u8,
+    ) -> io::Result<IndexHeader> {
+        let file_size = csv_data.seek(SeekFrom::End(0))?;
+        let head_hash = fnv1a_hash(csv_data, 0, FINGERPRINT_SAMPLE)?;
+        let tail_start = file_size.saturating_sub(FINGERPRINT_SAMPLE);
+        let tail_hash = fnv1a_hash(csv_data, tail_start, FINGERPRINT_SAMPLE)?;
+        Ok(IndexHeader {
+            file_size,
+            head_hash,
+            tail_hash,
+            delimiter,
+            has_headers: csv_rdr.has_headers(),
+            column_count: csv_rdr.byte_headers()?.len() as u64,
+        })
+    }
+
+    /// Read the header of an index, leaving `idx` positioned at the start of
+    /// the offsets.
+    pub fn read<I: Read>(idx: &mut I) -> io::Result<IndexHeader> {
+        let mut magic = [0; INDEX_MAGIC.len()];
+        if idx.read_exact(&mut magic).is_err() || &magic != INDEX_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "it has no header, so it was likely created by an older version of qsv",
+            ));
+        }
+        let version = idx.read_u16::<BigEndian>()?;
+        if version != INDEX_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "its format version {version} is not supported (expected version \
+                     {INDEX_VERSION})"
+                ),
+            ));
+        }
+        Ok(IndexHeader {
+            file_size: idx.read_u64::<BigEndian>()?,
+            head_hash: idx.read_u64::<BigEndian>()?,
+            tail_hash: idx.read_u64::<BigEndian>()?,
+            delimiter: idx.read_u8()?,
+            has_headers: idx.read_u8()? != 0,
+            column_count: idx.read_u64::<BigEndian>()?,
+        })
+    }
+
+    pub fn write<W: Write>(&self, wtr: &mut W) -> io::Result<()> {
+        wtr.write_all(INDEX_MAGIC)?;
+        wtr.write_u16::<BigEndian>(INDEX_VERSION)?;
+        wtr.write_u64::<BigEndian>(self.file_size)?;
+        wtr.write_u64::<BigEndian>(self.head_hash)?;
+        wtr.write_u64::<BigEndian>(self.tail_hash)?;
+        wtr.write_u8(self.delimiter)?;
+        wtr.write_u8(self.has_headers as u8)?;
+        wtr.write_u64::<BigEndian>(self.column_count)
+    }
+
+    /// Check that an index created for the data described by `self` can be
+    /// used for the data described by `current`, explaining why not otherwise.
+    ///
+    /// Header presence is not checked, as it doesn't change the offsets.
+    pub fn check(&self, current: &IndexHeader) -> Result<(), String> {
+        if self.file_size != current.file_size {
+            return Err(format!(
+                "the CSV file size changed from {} to {} bytes",
+                self.file_size, current.file_size
+            ));
+        }
+        if self.head_hash != current.head_hash {
+            return Err("the start of the CSV file changed".to_owned());
+        }
+        if self.tail_hash != current.tail_hash {
+            return Err("the end of the CSV file changed".to_owned());
+        }
+        if self.delimiter != current.delimiter {
+            return Err(format!(
+                "it was created with the delimiter {:?}, not {:?}",
+                self.delimiter as char, current.delimiter as char
+            ));
+        }
+        if self.column_count != current.column_count {
+            return Err(format!(
+                "it was created for {} columns, but the CSV data has {}",
+                self.column_count, current.column_count
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the CSV data described by `self` is a prefix of `csv_data`,
+    /// described by `current`, i.e. whether it was only appended to since.
+    pub fn is_prefix_of<D: Read + Seek>(
+        &self,
+        csv_data: &mut D,
+        current: &IndexHeader,
+    ) -> io::Result<bool> {
+        if self.file_size > current.file_size
+            || self.delimiter != current.delimiter
+            || self.column_count != current.column_count
+        {
+            return Ok(false);
+        }
+        let tail_start = self.file_size.saturating_sub(FINGERPRINT_SAMPLE);
+        Ok(
+            fnv1a_hash(csv_data, 0, self.file_size.min(FINGERPRINT_SAMPLE))? == self.head_hash
+                && fnv1a_hash(csv_data, tail_start, self.file_size - tail_start)? == self.tail_hash,
+        )
+    }
+}
+
+/// Hash up to `len` bytes of `data` starting at `start`, using the FNV-1a
+/// hash, which is fast and stable across versions and platforms.
+fn fnv1a_hash<D: Read + Seek>(data: &mut D, start: u64, len: u64) -> io::Result<u64> {
+    data.seek(SeekFrom::Start(start))?;
+    let mut sample = Vec::with_capacity(len as usize);
+    data.take(len).read_to_end(&mut sample)?;
+    Ok(fnv1a(FNV_OFFSET_BASIS, &sample))
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Write an index of the CSV data read by `csv_rdr`, starting with `header`.
+pub fn create_index<R: Read, W: Write>(
+    header: &IndexHeader,
+    csv_rdr: &mut csv::Reader<R>,
+    mut wtr: W,
+) -> CliResult<()> {
+    header.write(&mut wtr)?;
+    RandomAccessSimple::create(csv_rdr, &mut wtr)?;
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Index the CSV data read by `csv_rdr` into `idx`, and only then write the
+/// header returned by `describe`, once all the data has been read. This
+/// indexes data while it's being written, e.g. while stdin is spooled.
+pub fn create_index_with<R, I, F>(
+    csv_rdr: &mut csv::Reader<R>,
+    mut idx: I,
+    describe: F,
+) -> CliResult<()>
+where
+    R: Read,
+    I: Write + Seek,
+    F: FnOnce(&mut csv::Reader<R>) -> io::Result<IndexHeader>,
+{
+    // the header has a fixed size, so its space is reserved until then
+    let start = idx.stream_position()?;
+    IndexHeader::default().write(&mut idx)?;
+    RandomAccessSimple::create(csv_rdr, &mut idx)?;
+    let header = describe(csv_rdr)?;
+    idx.seek(SeekFrom::Start(start))?;
+    header.write(&mut idx)?;
+    idx.flush()?;
+    Ok(())
+}
+
+/// Update the index `idx` of CSV data that was only appended to since, by
+/// re-indexing from its last record onwards (as it may have been incomplete),
+/// and replacing its header with `header`. Returns `false` without changing
+/// the index if it has no records to start from.
+///
+/// The header is written last, so that an interrupted update leaves an index
+/// that's rejected as stale rather than one that's silently wrong.
+pub fn append_index<R: Read + Seek, I: Read + Write + Seek>(
+    header: &IndexHeader,
+    csv_rdr: &mut csv::Reader<R>,
+    idx: &mut I,
+) -> CliResult<bool> {
+    idx.seek(SeekFrom::Start(0))?;
+    IndexHeader::read(idx)?;
+    let start = idx.stream_position()?;
+    let end = idx.seek(SeekFrom::End(-8))?;
+    let count = idx.read_u64::<BigEndian>()?;
+    if end < start || (end - start) != count * 8 {
+        return fail!("The index is corrupt. Please re-create the index.");
+    }
+    if count == 0 {
+        return Ok(false);
+    }
+
+    // resume from the start of the last indexed record
+    let last = start + (count - 1) * 8;
+    idx.seek(SeekFrom::Start(last))?;
+    let mut pos = csv::Position::new();
+    pos.set_byte(idx.read_u64::<BigEndian>()?);
+    csv_rdr.seek(pos)?;
+
+    let mut wtr = io::BufWriter::new(idx);
+    wtr.seek(SeekFrom::Start(last))?;
+    let mut count = count - 1;
+    let mut record = csv::ByteRecord::new();
+    while csv_rdr.read_byte_record(&mut record)? {
+        wtr.write_u64::<BigEndian>(record.position().expect("position on row").byte())?;
+        count += 1;
+    }
+    wtr.write_u64::<BigEndian>(count)?;
+    wtr.flush()?;
+
+    let idx = wtr.into_inner().map_err(io::IntoInnerError::into_error)?;
+    idx.seek(SeekFrom::Start(0))?;
+    header.write(idx)?;
+    idx.flush()?;
+    Ok(true)
+}
+
+/// IndexBody hides the header of an index from `RandomAccessSimple`, which
+/// expects the offsets to start at the beginning of its reader.
+struct IndexBody<I> {
+    idx: I,
+    start: u64,
+}
+
+impl<I: Read> Read for IndexBody<I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.idx.read(buf)
+    }
+}
+
+impl<I: Seek> Seek for IndexBody<I> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(n) => SeekFrom::Start(self.start + n),
+            pos => pos,
+        };
+        self.idx.seek(pos)?.checked_sub(self.start).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot seek before the start of the index",
+            )
+        })
+    }
+}
+
 /// Indexed composes a CSV reader with a simple random access index.
 pub struct Indexed<R, I> {
     csv_rdr: csv::Reader<R>,
-    idx: RandomAccessSimple<I>,
+    idx: RandomAccessSimple<IndexBody<I>>,
 }
 
 impl<R, I> ops::Deref for Indexed<R, I> {
@@ -25,11 +300,16 @@ impl<R, I> ops::DerefMut for Indexed<R, I> {
 }
 
 impl<R: io::Read + io::Seek, I: io::Read + io::Seek> Indexed<R, I> {
-    /// Opens an index.
-    pub fn open(csv_rdr: csv::Reader<R>, idx_rdr: I) -> CliResult<Indexed<R, I>> {
+    /// Opens an index. The offsets of the index start at the current
+    /// position of `idx_rdr`, i.e. after its header, if any.
+    pub fn open(csv_rdr: csv::Reader<R>, mut idx_rdr: I) -> CliResult<Indexed<R, I>> {
+        let start = idx_rdr.stream_position()?;
         Ok(Indexed {
             csv_rdr,
-            idx: RandomAccessSimple::open(idx_rdr)?,
+            idx: RandomAccessSimple::open(IndexBody {
+                idx: idx_rdr,
+                start,
+            })?,
         })
     }
 
@@ -61,3 +341,207 @@ impl<R: io::Read + io::Seek, I: io::Read + io::Seek> Indexed<R, I> {
         Ok(())
     }
 }
+
+/// Write a key index of the CSV data read by `csv_rdr`, starting with
+/// `header`, which maps the values of the `key` columns of every record to
+/// the offset of that record.
+///
+/// After the header and the key columns, it's a table of (key hash, offset)
+/// pairs sorted by key hash, so that lookups are a binary search away. As
+/// hashes can collide, the records found must still be compared with the key.
+pub fn create_key_index<R: Read, W: Write>(
+    header: &IndexHeader,
+    key: &Selection,
+    csv_rdr: &mut csv::Reader<R>,
+    mut wtr: W,
+) -> CliResult<()> {
+    let mut entries = Vec::new();
+    let mut record = csv::ByteRecord::new();
+    while csv_rdr.read_byte_record(&mut record)? {
+        let offset = record.position().expect("position on row").byte();
+        entries.push((key_hash(key.select(&record)), offset));
+    }
+    entries.sort_unstable();
+
+    wtr.write_all(KEY_INDEX_MAGIC)?;
+    header.write(&mut wtr)?;
+    wtr.write_u64::<BigEndian>(key.len() as u64)?;
+    for &i in key.iter() {
+        wtr.write_u64::<BigEndian>(i as u64)?;
+    }
+    wtr.write_u64::<BigEndian>(entries.len() as u64)?;
+    for (hash, offset) in entries {
+        wtr.write_u64::<BigEndian>(hash)?;
+        wtr.write_u64::<BigEndian>(offset)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Read the header of a key index, leaving `idx` positioned at its key
+/// columns.
+pub fn read_key_index_header<I: Read>(idx: &mut I) -> io::Result<IndexHeader> {
+    let mut magic = [0; KEY_INDEX_MAGIC.len()];
+    if idx.read_exact(&mut magic).is_err() || &magic != KEY_INDEX_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "it is not a key index (create it with 'qsv index --column')",
+        ));
+    }
+    IndexHeader::read(idx)
+}
+
+/// Normalize a key field: leading and trailing whitespace is ignored, as is
+/// case, so that the same key index serves case sensitive and insensitive
+/// lookups.
+fn normalize_key_field(field: &[u8]) -> Cow<'_, [u8]> {
+    match str::from_utf8(field) {
+        Err(_) => Cow::Borrowed(field),
+        Ok(s) => Cow::Owned(s.trim().to_lowercase().into_bytes()),
+    }
+}
+
+fn key_hash<'a, I: Iterator<Item = &'a [u8]>>(key: I) -> u64 {
+    key.fold(FNV_OFFSET_BASIS, |hash, field| {
+        // separate the fields, so that e.g. ("ab", "c") and ("a", "bc") differ
+        fnv1a(fnv1a(hash, &normalize_key_field(field)), &[0xff])
+    })
+}
+
+fn key_field_eq(a: &[u8], b: &[u8], casei: bool) -> bool {
+    if casei {
+        return normalize_key_field(a) == normalize_key_field(b);
+    }
+    match (str::from_utf8(a), str::from_utf8(b)) {
+        (Ok(a), Ok(b)) => a.trim() == b.trim(),
+        _ => a == b,
+    }
+}
+
+// size of a (key hash, offset) entry of a key index
+const KEY_ENTRY_SIZE: u64 = 16;
+
+/// KeyIndexed composes a CSV reader with a key index, to look up records
+/// by the values of their key columns.
+pub struct KeyIndexed<R, I> {
+    csv_rdr: csv::Reader<R>,
+    idx: I,
+    key: Vec<usize>,
+    // position of the first entry in `idx`
+    start: u64,
+    len: u64,
+}
+
+impl<R, I> ops::Deref for KeyIndexed<R, I> {
+    type Target = csv::Reader<R>;
+    fn deref(&self) -> &csv::Reader<R> {
+        &self.csv_rdr
+    }
+}
+
+impl<R, I> ops::DerefMut for KeyIndexed<R, I> {
+    fn deref_mut(&mut self) -> &mut csv::Reader<R> {
+        &mut self.csv_rdr
+    }
+}
+
+impl<R: io::Read + io::Seek, I: io::Read + io::Seek> KeyIndexed<R, I> {
+    /// Opens a key index, which must be positioned after its header.
+    pub fn open(csv_rdr: csv::Reader<R>, mut idx_rdr: I) -> CliResult<KeyIndexed<R, I>> {
+        let ncols = idx_rdr.read_u64::<BigEndian>()?;
+        let key = (0..ncols)
+            .map(|_| Ok(idx_rdr.read_u64::<BigEndian>()? as usize))
+            .collect::<io::Result<Vec<_>>>()?;
+        let len = idx_rdr.read_u64::<BigEndian>()?;
+        let start = idx_rdr.stream_position()?;
+        Ok(KeyIndexed {
+            csv_rdr,
+            idx: idx_rdr,
+            key,
+            start,
+            len,
+        })
+    }
+
+    /// The indices of the key columns.
+    pub fn key(&self) -> &[usize] {
+        &self.key
+    }
+
+    /// Split a key given on the command line into its values. A key of
+    /// several columns is parsed as a CSV record, e.g. `"US,New York"`.
+    pub fn split_key(&self, key: &str, delimiter: u8) -> CliResult<Vec<Vec<u8>>> {
+        if self.key.len() == 1 {
+            return Ok(vec![key.as_bytes().to_vec()]);
+        }
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .delimiter(delimiter)
+            .from_reader(key.as_bytes());
+        let mut record = csv::ByteRecord::new();
+        rdr.read_byte_record(&mut record)?;
+        Ok(record.iter().map(<[u8]>::to_vec).collect())
+    }
+
+    /// Return the records whose key columns match `key`, in the order they
+    /// appear in the CSV data. Leading and trailing whitespace is ignored,
+    /// as is case if `casei` is set.
+    pub fn lookup(&mut self, key: &[&[u8]], casei: bool) -> CliResult<Vec<csv::ByteRecord>> {
+        if key.len() != self.key.len() {
+            return fail!(format!(
+                "The key index has {} key column(s), but the key has {} value(s).",
+                self.key.len(),
+                key.len()
+            ));
+        }
+        let hash = key_hash(key.iter().copied());
+
+        // binary search the first entry with this hash
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.entry(mid)?.0 < hash {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let mut offsets = vec![];
+        for i in lo..self.len {
+            let (entry_hash, offset) = self.entry(i)?;
+            if entry_hash != hash {
+                break;
+            }
+            offsets.push(offset);
+        }
+
+        let mut records = vec![];
+        for offset in offsets {
+            let mut pos = csv::Position::new();
+            pos.set_byte(offset);
+            self.csv_rdr.seek(pos)?;
+            let mut record = csv::ByteRecord::new();
+            if !self.csv_rdr.read_byte_record(&mut record)? {
+                continue;
+            }
+            let matched = self.key.iter().zip(key).all(|(&i, &field)| {
+                record
+                    .get(i)
+                    .map_or(false, |f| key_field_eq(f, field, casei))
+            });
+            if matched {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    fn entry(&mut self, i: u64) -> io::Result<(u64, u64)> {
+        self.idx
+            .seek(SeekFrom::Start(self.start + i * KEY_ENTRY_SIZE))?;
+        Ok((
+            self.idx.read_u64::<BigEndian>()?,
+            self.idx.read_u64::<BigEndian>()?,
+        ))
+    }
+}
@@ -44,6 +44,7 @@ macro_rules! command_list {
     apply*      Apply series of transformations to a column
     behead      Drop header from CSV file
     cat         Concatenate by row or column
+    corr        Compute the correlation matrix of numeric columns
     count       Count records
     dedup       Remove redundant rows
     enum        Add a new column enumerating CSV lines
@@ -59,10 +60,12 @@ macro_rules! command_list {
     generate*   Generate test data by profiling a CSV
     headers     Show header names
     help        Show this usage message
+    histogram   Show histograms of numeric columns
     index       Create CSV index for faster access
     input       Read CSV data with special quoting rules
     join        Join CSV files
     jsonl       Convert newline-delimited JSON files to CSV
+    lookup      Look up rows by key using a key index
     lua*        Execute Lua script on CSV data
     partition   Partition CSV data based on a column value
     pseudo      Pseudonymise the values of a column
@@ -93,7 +96,9 @@ macro_rules! command_list {
 mod cmd;
 mod config;
 mod index;
+mod numeric;
 mod select;
+mod sketch;
 mod util;
 
 static USAGE: &str = concat!(
@@ -104,7 +109,8 @@ Usage:
 
 Options:
     --list               List all commands available.
-    --envlist            List all qsv-relevant environment variables.
+    --envlist            List all qsv-relevant environment variables,
+                         including those set in qsv.toml files.
     -u, --update         Update qsv to the latest release from GitHub.
     -h, --help           Display this message
     <command> -h         Display the command help message
@@ -129,6 +135,11 @@ fn check_python() -> bool {
 }
 
 fn main() {
+    // load qsv.toml first, as it can set QSV_LOG_LEVEL and QSV_LOG_DIR
+    if let Err(e) = util::load_qsv_toml() {
+        werr!("{e}");
+        ::std::process::exit(1);
+    }
     util::init_logger();
 
     #[cfg(feature = "python")]
@@ -223,6 +234,7 @@ enum Command {
     Apply,
     Behead,
     Cat,
+    Corr,
     Count,
     Dedup,
     Enum,
@@ -241,10 +253,12 @@ enum Command {
     Generate,
     Headers,
     Help,
+    Histogram,
     Index,
     Input,
     Join,
     Jsonl,
+    Lookup,
     #[cfg(all(feature = "lua", not(feature = "lite")))]
     Lua,
     Partition,
@@ -281,11 +295,14 @@ impl Command {
                 argv[1].to_lowercase()
             )));
         }
+        // removes the temporary files stdin may be spooled to when done
+        let _stdin_spool = config::StdinSpoolGuard;
         match self {
             Command::Behead => cmd::behead::run(argv),
             #[cfg(all(feature = "apply", not(feature = "lite")))]
             Command::Apply => cmd::apply::run(argv),
             Command::Cat => cmd::cat::run(argv),
+            Command::Corr => cmd::corr::run(argv),
             Command::Count => cmd::count::run(argv),
             Command::Dedup => cmd::dedup::run(argv),
             Command::Enum => cmd::enumerate::run(argv),
@@ -301,6 +318,7 @@ impl Command {
             #[cfg(all(feature = "generate", not(feature = "lite")))]
             Command::Generate => cmd::generate::run(argv),
             Command::Headers => cmd::headers::run(argv),
+            Command::Histogram => cmd::histogram::run(argv),
             Command::Help => {
                 wout!("{USAGE}");
                 util::qsv_check_for_update("qsv");
@@ -310,6 +328,7 @@ impl Command {
             Command::Input => cmd::input::run(argv),
             Command::Join => cmd::join::run(argv),
             Command::Jsonl => cmd::jsonl::run(argv),
+            Command::Lookup => cmd::lookup::run(argv),
             #[cfg(all(feature = "lua", not(feature = "lite")))]
             Command::Lua => cmd::lua::run(argv),
             Command::Partition => cmd::partition::run(argv),
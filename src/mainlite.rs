@@ -41,6 +41,7 @@ macro_rules! command_list {
         "
     behead      Drop header from CSV file
     cat         Concatenate by row or column
+    corr        Compute the correlation matrix of numeric columns
     count       Count records
     dedup       Remove redundant rows
     enum        Add a new column enumerating CSV lines
@@ -53,10 +54,12 @@ macro_rules! command_list {
     frequency   Show frequency tables
     headers     Show header names
     help        Show this usage message
+    histogram   Show histograms of numeric columns
     index       Create CSV index for faster access
     input       Read CSV data with special quoting rules
     join        Join CSV files
     jsonl       Convert newline-delimited JSON files to CSV
+    lookup      Look up rows by key using a key index
     partition   Partition CSV data based on a column value
     pseudo      Pseudonymise the values of a column
     rename      Rename the columns of CSV data efficiently
@@ -83,7 +86,9 @@ macro_rules! command_list {
 mod cmd;
 mod config;
 mod index;
+mod numeric;
 mod select;
+mod sketch;
 mod util;
 
 static USAGE: &str = concat!(
@@ -94,7 +99,8 @@ Usage:
 
 Options:
     --list               List all commands available.
-    --envlist            List all qsv-relevant environment variables.
+    --envlist            List all qsv-relevant environment variables,
+                         including those set in qsv.toml files.
     -u, --update         Update qsv to the latest release from GitHub.
     -h, --help           Display this message
     <command> -h         Display the command help message
@@ -114,6 +120,11 @@ struct Args {
 }
 
 fn main() {
+    // load qsv.toml first, as it can set QSV_LOG_LEVEL and QSV_LOG_DIR
+    if let Err(e) = util::load_qsv_toml() {
+        werr!("{e}");
+        ::std::process::exit(1);
+    }
     util::init_logger();
 
     let now = Instant::now();
@@ -196,6 +207,7 @@ Please choose one of the following commands:",
 enum Command {
     Behead,
     Cat,
+    Corr,
     Count,
     Dedup,
     Enum,
@@ -208,10 +220,12 @@ enum Command {
     Frequency,
     Headers,
     Help,
+    Histogram,
     Index,
     Input,
     Join,
     Jsonl,
+    Lookup,
     Partition,
     Pseudo,
     Rename,
@@ -244,9 +258,12 @@ impl Command {
                 argv[1].to_lowercase()
             )));
         }
+        // removes the temporary files stdin may be spooled to when done
+        let _stdin_spool = config::StdinSpoolGuard;
         match self {
             Command::Behead => cmd::behead::run(argv),
             Command::Cat => cmd::cat::run(argv),
+            Command::Corr => cmd::corr::run(argv),
             Command::Count => cmd::count::run(argv),
             Command::Dedup => cmd::dedup::run(argv),
             Command::Enum => cmd::enumerate::run(argv),
@@ -258,6 +275,7 @@ impl Command {
             Command::Fmt => cmd::fmt::run(argv),
             Command::Frequency => cmd::frequency::run(argv),
             Command::Headers => cmd::headers::run(argv),
+            Command::Histogram => cmd::histogram::run(argv),
             Command::Help => {
                 wout!("{USAGE}");
                 util::qsv_check_for_update("qsvlite");
@@ -267,6 +285,7 @@ impl Command {
             Command::Input => cmd::input::run(argv),
             Command::Join => cmd::join::run(argv),
             Command::Jsonl => cmd::jsonl::run(argv),
+            Command::Lookup => cmd::lookup::run(argv),
             Command::Partition => cmd::partition::run(argv),
             Command::Pseudo => cmd::pseudo::run(argv),
             Command::Rename => cmd::rename::run(argv),
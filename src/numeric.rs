@@ -0,0 +1,355 @@
+//! Locale-aware parsing of numbers, e.g. `1,234.56`, `1.234,56`, `12 345`,
+//! `(500)` or `45%`, with the thousands and decimal separators set by the
+//! `QSV_NUMERIC_LOCALE` environment variable. Without it, numbers must be in
+//! the plain syntax of Rust, e.g. `-1234.56`.
+
+use std::env;
+use std::fmt;
+use std::str;
+
+use log::{debug, warn};
+use once_cell::sync::OnceCell;
+
+use crate::config::Config;
+use crate::CliResult;
+
+/// The number of records whose values are sampled to detect the number
+/// format, with `QSV_NUMERIC_LOCALE=auto`.
+const SAMPLE_SIZE: usize = 1000;
+
+/// The separators of a locale-formatted number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub thousands: Option<char>,
+    pub decimal: char,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Setting {
+    Plain,
+    Auto,
+    Format(NumberFormat),
+}
+
+static SETTING: OnceCell<Setting> = OnceCell::new();
+static NUMBER_FORMAT: OnceCell<Option<NumberFormat>> = OnceCell::new();
+
+fn setting() -> Setting {
+    *SETTING.get_or_init(|| {
+        let value = env::var("QSV_NUMERIC_LOCALE").unwrap_or_default();
+        Setting::parse(&value).unwrap_or_else(|e| {
+            warn!("Ignoring QSV_NUMERIC_LOCALE: {e}");
+            Setting::Plain
+        })
+    })
+}
+
+impl Setting {
+    /// Parses `auto`, or the thousands separator followed by the decimal
+    /// separator, e.g. `,.` or `.,`, or only the decimal separator.
+    fn parse(value: &str) -> Result<Setting, String> {
+        if value.is_empty() {
+            return Ok(Setting::Plain);
+        }
+        if value.eq_ignore_ascii_case("auto") {
+            return Ok(Setting::Auto);
+        }
+        let chars: Vec<char> = value.chars().collect();
+        let (thousands, decimal) = match chars[..] {
+            [decimal] => (None, decimal),
+            [thousands, decimal] => (Some(thousands), decimal),
+            _ => return Err(format!("{value:?} isn't 'auto' or 1 or 2 separators.")),
+        };
+        let separators = thousands.into_iter().chain(Some(decimal));
+        if thousands == Some(decimal) || separators.clone().any(|c| c.is_ascii_digit()) {
+            return Err(format!("{value:?} doesn't have 2 different non-digits."));
+        }
+        Ok(Setting::Format(NumberFormat { thousands, decimal }))
+    }
+}
+
+/// The number format set by `QSV_NUMERIC_LOCALE`, if any. With `auto`, it's
+/// only known after `init`, and numbers are parsed in the plain syntax until
+/// then.
+pub fn number_format() -> Option<NumberFormat> {
+    *NUMBER_FORMAT.get_or_init(|| match setting() {
+        Setting::Format(format) => Some(format),
+        Setting::Plain | Setting::Auto => None,
+    })
+}
+
+/// Detects the number format from a sample of the input of `conf`, with
+/// `QSV_NUMERIC_LOCALE=auto`. This must be called before parsing numbers.
+pub fn init(conf: &Config) -> CliResult<()> {
+    if setting() != Setting::Auto || NUMBER_FORMAT.get().is_some() {
+        return Ok(());
+    }
+    if conf.is_std() {
+        warn!("The number format can't be detected from <stdin>, as the data is read twice.");
+        let _ = NUMBER_FORMAT.set(None);
+        return Ok(());
+    }
+    let mut rdr = conf.reader()?;
+    let mut records = Vec::with_capacity(SAMPLE_SIZE);
+    for record in rdr.byte_records().take(SAMPLE_SIZE) {
+        records.push(record?);
+    }
+    let values = records
+        .iter()
+        .flat_map(|r| r.iter())
+        .filter_map(|v| str::from_utf8(v).ok());
+    let format = detect(values);
+    debug!("detected the number format {format:?}");
+    let _ = NUMBER_FORMAT.set(Some(format));
+    Ok(())
+}
+
+/// The integer in `s`, in the configured number format.
+#[inline]
+pub fn parse_int(s: &str) -> Option<i64> {
+    match number_format() {
+        None => s.parse().ok(),
+        Some(format) => format.parse_int(s),
+    }
+}
+
+/// The float in `s`, in the configured number format.
+#[inline]
+pub fn parse_float(s: &str) -> Option<f64> {
+    match number_format() {
+        None => s.parse().ok(),
+        Some(format) => format.parse_float(s),
+    }
+}
+
+/// Whether `c` is a space that separates the thousands, when the thousands
+/// separator is a space. Non-breaking spaces are commonly used too.
+#[inline]
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\u{a0}' || c == '\u{202f}'
+}
+
+#[inline]
+fn all_digits(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_digit())
+}
+
+impl NumberFormat {
+    /// The integer in `s`. Percentages aren't integers.
+    pub fn parse_int(&self, s: &str) -> Option<i64> {
+        match self.normalize(s)? {
+            (_, true) => None,
+            (plain, false) => plain.parse().ok(),
+        }
+    }
+
+    /// The float in `s`, where percentages are divided by 100.
+    pub fn parse_float(&self, s: &str) -> Option<f64> {
+        let (plain, percent) = self.normalize(s)?;
+        let n: f64 = plain.parse().ok()?;
+        Some(if percent { n / 100.0 } else { n })
+    }
+
+    /// The number in `s` in the plain syntax, and whether it's a percentage.
+    /// Negative numbers can be in parentheses, the thousands must be grouped
+    /// by 3 digits, and an exponent can follow, e.g. `(1.234,5e3)`.
+    fn normalize(&self, s: &str) -> Option<(String, bool)> {
+        let (s, parenthesized) = match s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+        let (s, percent) = match s.strip_suffix('%') {
+            Some(s) => (s.trim_end(), true),
+            None => (s, false),
+        };
+        let (s, minus) = match s.strip_prefix('-') {
+            Some(s) => (s, true),
+            None => (s.strip_prefix('+').unwrap_or(s), false),
+        };
+        if parenthesized && minus {
+            return None;
+        }
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(i) => (&s[..i], Some(&s[i + 1..])),
+            None => (s, None),
+        };
+        let (int, frac) = match mantissa.split_once(self.decimal) {
+            Some((int, frac)) => (int, Some(frac)),
+            None => (mantissa, None),
+        };
+
+        let mut plain = String::with_capacity(s.len() + 1);
+        if parenthesized || minus {
+            plain.push('-');
+        }
+        let is_separator = |c| match self.thousands {
+            Some(' ') => is_space(c),
+            thousands => thousands == Some(c),
+        };
+        if int.contains(is_separator) {
+            for (i, group) in int.split(is_separator).enumerate() {
+                let size_ok = if i == 0 {
+                    (1..=3).contains(&group.len())
+                } else {
+                    group.len() == 3
+                };
+                if !size_ok || !all_digits(group) {
+                    return None;
+                }
+                plain.push_str(group);
+            }
+        } else if all_digits(int) {
+            plain.push_str(int);
+        } else {
+            return None;
+        }
+        if let Some(frac) = frac {
+            if !all_digits(frac) {
+                return None;
+            }
+            plain.push('.');
+            plain.push_str(frac);
+        }
+        if int.is_empty() && frac.map_or(true, str::is_empty) {
+            return None;
+        }
+        if let Some(exponent) = exponent {
+            let digits = exponent
+                .strip_prefix(|c| c == '-' || c == '+')
+                .unwrap_or(exponent);
+            if digits.is_empty() || !all_digits(digits) {
+                return None;
+            }
+            plain.push('e');
+            plain.push_str(exponent);
+        }
+        Some((plain, percent))
+    }
+}
+
+impl fmt::Display for NumberFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(thousands) = self.thousands {
+            write!(f, "{thousands}")?;
+        }
+        write!(f, "{}", self.decimal)
+    }
+}
+
+/// Detects the number format of `values`, from those that look like numbers
+/// with separators. In numbers with both a dot and a comma, the last one is
+/// the decimal separator and the other one the thousands separator. Otherwise
+/// a single separator that isn't followed by 3 digits is a decimal separator,
+/// and a repeated one a thousands separator. The decimal separator defaults
+/// to a dot, and the thousands separator to the other one, unless digits are
+/// more often grouped by spaces.
+pub fn detect<'a, I>(values: I) -> NumberFormat
+where
+    I: Iterator<Item = &'a str>,
+{
+    // the votes for the dot and the comma as decimal separators, and for each
+    // thousands separator
+    let (mut dot, mut comma) = (0, 0);
+    let (mut dots, mut commas, mut spaces) = (0, 0, 0);
+    for value in values {
+        let number = value.trim_matches(|c: char| "()%+-".contains(c) || c.is_whitespace());
+        let looks_like_number = number.starts_with(|c: char| c.is_ascii_digit())
+            && number.ends_with(|c: char| c.is_ascii_digit())
+            && number
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == '.' || c == ',' || is_space(c));
+        if !looks_like_number {
+            continue;
+        }
+        if number.contains(is_space) {
+            spaces += 1;
+        }
+        let digits_after = |sep| number.len() - number.rfind(sep).unwrap_or_default() - 1;
+        match (number.matches('.').count(), number.matches(',').count()) {
+            (0, 0) => {}
+            (1, 0) if digits_after('.') != 3 => dot += 1,
+            (0, 1) if digits_after(',') != 3 => comma += 1,
+            (1, 0) | (0, 1) => {}
+            (_, 0) => dots += 1,
+            (0, _) => commas += 1,
+            _ if number.rfind('.') > number.rfind(',') => {
+                dot += 1;
+                commas += 1;
+            }
+            _ => {
+                comma += 1;
+                dots += 1;
+            }
+        }
+    }
+    let (decimal, thousands, votes) = if comma + dots > dot + commas {
+        (',', '.', dots)
+    } else {
+        ('.', ',', commas)
+    };
+    NumberFormat {
+        thousands: Some(if spaces > votes { ' ' } else { thousands }),
+        decimal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: NumberFormat = NumberFormat {
+        thousands: Some(','),
+        decimal: '.',
+    };
+    const DE: NumberFormat = NumberFormat {
+        thousands: Some('.'),
+        decimal: ',',
+    };
+    const FR: NumberFormat = NumberFormat {
+        thousands: Some(' '),
+        decimal: ',',
+    };
+
+    #[test]
+    fn parse_formats() {
+        assert_eq!(EN.parse_float("1,234.56"), Some(1234.56));
+        assert_eq!(EN.parse_int("-1,234,567"), Some(-1_234_567));
+        assert_eq!(EN.parse_int("(500)"), Some(-500));
+        assert_eq!(EN.parse_int("45%"), None);
+        assert_eq!(EN.parse_float("45%"), Some(0.45));
+        assert_eq!(EN.parse_float("1.5e3"), Some(1500.0));
+        assert_eq!(DE.parse_float("1.234,56"), Some(1234.56));
+        assert_eq!(DE.parse_int("1.234"), Some(1234));
+        assert_eq!(FR.parse_int("12 345"), Some(12345));
+        assert_eq!(FR.parse_float("12\u{202f}345,5 %"), Some(123.455));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for s in [
+            "", "-", "1,23", "1234,567", ",123", "1.2.3", "(-5)", "1e", "abc",
+        ] {
+            assert_eq!(EN.parse_float(s), None, "{s}");
+        }
+        assert_eq!(DE.parse_float("1.5"), None);
+    }
+
+    #[test]
+    fn parse_setting() {
+        assert_eq!(Setting::parse(""), Ok(Setting::Plain));
+        assert_eq!(Setting::parse("AUTO"), Ok(Setting::Auto));
+        assert_eq!(Setting::parse(".,"), Ok(Setting::Format(DE)));
+        assert!(Setting::parse("..").is_err());
+        assert!(Setting::parse("1.").is_err());
+        assert!(Setting::parse(",.'").is_err());
+    }
+
+    #[test]
+    fn detect_formats() {
+        assert_eq!(detect(["1,234", "12.5", "x"].into_iter()), EN);
+        assert_eq!(detect(["1.234.567", "12"].into_iter()), DE);
+        assert_eq!(detect(["(1.234,5)", "2.345"].into_iter()), DE);
+        assert_eq!(detect(["12 345,6", "7"].into_iter()), FR);
+        assert_eq!(detect(["1.234", "5,678"].into_iter()), EN);
+    }
+}
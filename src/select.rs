@@ -25,19 +25,52 @@ impl SelectColumns {
         } else {
             false
         };
-        Ok(SelectColumns {
-            selectors: SelectorParser::new(s).parse()?,
-            invert,
-        })
+        let mut selectors = SelectorParser::new(s).parse()?;
+        let excludes = selectors
+            .iter()
+            .any(|sel| matches!(*sel, Selector::Exclude(_)));
+        if invert && excludes && !selectors.is_empty() {
+            // e.g. `!a,!b`: the leading `!` excludes the first column like
+            // the others, rather than inverting the whole selection.
+            let first = selectors.remove(0);
+            selectors.insert(0, Selector::Exclude(Box::new(first)));
+            return Ok(SelectColumns {
+                selectors,
+                invert: false,
+            });
+        }
+        Ok(SelectColumns { selectors, invert })
+    }
+
+    /// Whether no columns were given, which selects all columns.
+    pub fn is_empty(&self) -> bool {
+        self.selectors.is_empty() && !self.invert
+    }
+
+    /// Whether there are type selectors (e.g. `:numeric`), which need the
+    /// types of the columns.
+    pub fn needs_types(&self) -> bool {
+        self.selectors.iter().any(Selector::needs_types)
     }
 
     pub fn selection(
         &self,
         first_record: &csv::ByteRecord,
         use_names: bool,
+    ) -> Result<Selection, String> {
+        self.typed_selection(first_record, use_names, None)
+    }
+
+    /// Like `selection`, with the `types` of the columns (as reported by
+    /// `qsv stats`) to resolve type selectors.
+    pub fn typed_selection(
+        &self,
+        first_record: &csv::ByteRecord,
+        use_names: bool,
+        types: Option<&[String]>,
     ) -> Result<Selection, String> {
         if self.selectors.is_empty() {
-            return Ok(Selection(if self.invert {
+            return Ok(Selection::new(if self.invert {
                 // Inverting everything means we get nothing.
                 vec![]
             } else {
@@ -46,11 +79,54 @@ impl SelectColumns {
         }
 
         let mut map = vec![];
+        let mut aliases = vec![];
+        let mut excluded = HashSet::new();
         for sel in &self.selectors {
-            let idxs = sel.indices(first_record, use_names);
-            map.extend(idxs?.into_iter());
+            let idxs = sel.indices(first_record, use_names, types)?;
+            match *sel {
+                Selector::Exclude(ref sel) => {
+                    if matches!(**sel, Selector::Alias(..))
+                        && sel.exact_name(first_record, use_names).is_none()
+                    {
+                        return Err("Excluded columns can't have an alias.".to_owned());
+                    }
+                    excluded.extend(idxs);
+                }
+                Selector::Alias(_, ref alias, _)
+                    if sel.exact_name(first_record, use_names).is_none() =>
+                {
+                    if idxs.len() != 1 {
+                        return Err(format!(
+                            "The alias '{alias}' must be given to a single column, but \
+                             {} columns were selected.",
+                            idxs.len()
+                        ));
+                    }
+                    map.extend(idxs);
+                    aliases.push(Some(alias.clone()));
+                }
+                _ => {
+                    aliases.extend(repeat(None).take(idxs.len()));
+                    map.extend(idxs);
+                }
+            }
+        }
+        if !excluded.is_empty() {
+            // Only excluding columns means excluding them from all columns.
+            if map.is_empty() {
+                map = (0..first_record.len()).collect();
+                aliases = vec![None; map.len()];
+            }
+            (map, aliases) = map
+                .into_iter()
+                .zip(aliases)
+                .filter(|(i, _)| !excluded.contains(i))
+                .unzip();
         }
         if self.invert {
+            if aliases.iter().any(Option::is_some) {
+                return Err("Aliases can't be used in an inverted selection.".to_owned());
+            }
             let set: HashSet<_> = map.into_iter().collect();
             let mut map = vec![];
             for i in 0..first_record.len() {
@@ -58,9 +134,9 @@ impl SelectColumns {
                     map.push(i);
                 }
             }
-            return Ok(Selection(map));
+            return Ok(Selection::new(map));
         }
-        Ok(Selection(map))
+        Ok(Selection { map, aliases })
     }
 }
 
@@ -86,6 +162,11 @@ impl<'de> Deserialize<'de> for SelectColumns {
     }
 }
 
+// The column types that can be selected with `:<type>`.
+const TYPE_SELECTORS: &[&str] = &[
+    "numeric", "integer", "float", "string", "boolean", "date", "null",
+];
+
 struct SelectorParser {
     chars: Vec<char>,
     pos: usize,
@@ -100,63 +181,126 @@ impl SelectorParser {
     }
 
     fn parse(&mut self) -> Result<Vec<Selector>, String> {
-        if let (Some('/'), Some('/')) = (self.chars.first(), self.chars.last()) {
-            if self.chars.len() == 2 {
-                return Err(format!(
-                    "Empty regex: {}",
-                    self.chars.iter().collect::<String>()
-                ));
-            }
-            let re: String = self.chars[1..(self.chars.len() - 1)].iter().collect();
-            let regex = match Regex::new(&re) {
-                Ok(r) => r,
-                Err(_) => return Err(format!("Invalid regex: {re}")),
-            };
-            return Ok(vec![Selector::Regex(regex)]);
-        }
-
         let mut sels = vec![];
         loop {
             if self.cur().is_none() {
                 break;
             }
-            let f1: OneSelector = if self.cur() == Some('-') {
-                OneSelector::Start
-            } else {
-                self.parse_one()?
-            };
-            let f2: Option<OneSelector> = if self.cur() == Some('-') {
+            let exclude = self.cur() == Some('!');
+            if exclude {
                 self.bump();
-                Some(if self.is_end_of_selector() {
-                    OneSelector::End
-                } else {
-                    self.parse_one()?
-                })
-            } else {
-                None
-            };
+            }
+            let start = self.pos;
+            let mut sel = self.parse_selector()?;
+            if self.is_alias_start() {
+                self.pos += 4;
+                let alias = self.parse_alias()?;
+                let raw = self.chars[start..self.pos].iter().collect();
+                sel = Selector::Alias(Box::new(sel), alias, raw);
+            }
             if !self.is_end_of_selector() {
                 return Err(format!(
                     "Expected end of field but got '{}' instead.",
                     self.cur().unwrap()
                 ));
             }
-            sels.push(match f2 {
-                Some(end) => Selector::Range(f1, end),
-                None => Selector::One(f1),
+            sels.push(if exclude {
+                Selector::Exclude(Box::new(sel))
+            } else {
+                sel
             });
             self.bump();
         }
         Ok(sels)
     }
 
+    fn parse_selector(&mut self) -> Result<Selector, String> {
+        let term: String = self.chars[self.pos..self.term_end()].iter().collect();
+        if term.starts_with('/') {
+            return self.parse_regex();
+        }
+        if let Some(ty) = term.strip_prefix(':') {
+            if TYPE_SELECTORS.contains(&ty) {
+                self.pos += term.chars().count();
+                return Ok(Selector::Type(ty.to_owned()));
+            }
+        }
+        if !term.starts_with('"') && !term.contains('[') && term.contains(['*', '?']) {
+            self.pos += term.chars().count();
+            return Ok(Selector::Glob(glob_to_regex(&term)?, term));
+        }
+
+        let f1: OneSelector = if self.cur() == Some('-') {
+            OneSelector::Start
+        } else {
+            self.parse_one()?
+        };
+        let f2: Option<OneSelector> = if self.cur() == Some('-') {
+            self.bump();
+            Some(if self.is_end_of_selector() || self.is_alias_start() {
+                OneSelector::End
+            } else {
+                self.parse_one()?
+            })
+        } else {
+            None
+        };
+        Ok(match f2 {
+            Some(end) => Selector::Range(f1, end),
+            None => Selector::One(f1),
+        })
+    }
+
+    fn parse_regex(&mut self) -> Result<Selector, String> {
+        assert_eq!(self.cur().unwrap(), '/');
+        self.bump();
+        let mut re = String::new();
+        loop {
+            match self.cur() {
+                None => return Err(format!("Unclosed regex, missing closing /: /{re}")),
+                Some('/') => {
+                    self.bump();
+                    if self.is_end_of_selector() || self.is_alias_start() {
+                        break;
+                    }
+                    re.push('/');
+                }
+                Some(c) => {
+                    re.push(c);
+                    self.bump();
+                }
+            }
+        }
+        if re.is_empty() {
+            return Err("Empty regex: //".to_owned());
+        }
+        Ok(Selector::Regex(new_regex(&re)?))
+    }
+
     fn parse_one(&mut self) -> Result<OneSelector, String> {
-        let name = if self.cur() == Some('"') {
+        let quoted = self.cur() == Some('"');
+        let name = if quoted {
             self.bump();
             self.parse_quoted_name()?
         } else {
             self.parse_name()?
         };
+        if !quoted && name == "_" {
+            // `_` is the last column, `_-N` the Nth column before it
+            let mut n = 0;
+            if self.cur() == Some('-') && self.peek().map_or(false, |c| c.is_ascii_digit()) {
+                self.bump();
+                let mut digits = String::new();
+                while let Some(c) = self.cur().filter(char::is_ascii_digit) {
+                    digits.push(c);
+                    self.bump();
+                }
+                n = digits
+                    .parse()
+                    .map_err(|err| format!("Could not convert '{digits}' to an integer: {err}"))?;
+            }
+            return Ok(OneSelector::FromEnd(n));
+        }
         Ok(if self.cur() == Some('[') {
             let idx = self.parse_index()?;
             OneSelector::IndexedName(name, idx)
@@ -171,7 +315,7 @@ impl SelectorParser {
     fn parse_name(&mut self) -> Result<String, String> {
         let mut name = String::new();
         loop {
-            if self.is_end_of_field() || self.cur() == Some('[') {
+            if self.is_end_of_field() || self.cur() == Some('[') || self.is_alias_start() {
                 break;
             }
             name.push(self.cur().unwrap());
@@ -180,6 +324,24 @@ impl SelectorParser {
         Ok(name)
     }
 
+    fn parse_alias(&mut self) -> Result<String, String> {
+        let alias = if self.cur() == Some('"') {
+            self.bump();
+            self.parse_quoted_name()?.replace("\"\"", "\"")
+        } else {
+            let mut alias = String::new();
+            while !self.is_end_of_selector() {
+                alias.push(self.cur().unwrap());
+                self.bump();
+            }
+            alias
+        };
+        if alias.is_empty() {
+            return Err("Empty alias after 'as'.".to_owned());
+        }
+        Ok(alias)
+    }
+
     fn parse_quoted_name(&mut self) -> Result<String, String> {
         let mut name = String::new();
         loop {
@@ -234,6 +396,28 @@ impl SelectorParser {
         self.chars.get(self.pos).copied()
     }
 
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos + 1).copied()
+    }
+
+    /// The position of the end of the current selector, i.e. of the next
+    /// comma or alias.
+    fn term_end(&self) -> usize {
+        let mut end = self.pos;
+        while end < self.chars.len() && self.chars[end] != ',' && !self.is_alias_at(end) {
+            end += 1;
+        }
+        end
+    }
+
+    fn is_alias_at(&self, pos: usize) -> bool {
+        self.chars.get(pos..pos + 4) == Some(&[' ', 'a', 's', ' '])
+    }
+
+    fn is_alias_start(&self) -> bool {
+        self.is_alias_at(self.pos)
+    }
+
     fn is_end_of_field(&self) -> bool {
         self.cur().map_or(true, |c| c == ',' || c == '-')
     }
@@ -249,27 +433,79 @@ impl SelectorParser {
     }
 }
 
+fn new_regex(re: &str) -> Result<Regex, String> {
+    Regex::new(re).map_err(|_| format!("Invalid regex: {re}"))
+}
+
+/// Convert a glob pattern, where `*` matches any characters and `?` any
+/// single character, to a regex matching whole column names.
+fn glob_to_regex(glob: &str) -> Result<Regex, String> {
+    let mut re = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    new_regex(&re)
+}
+
 #[derive(Clone)]
 enum Selector {
     One(OneSelector),
     Range(OneSelector, OneSelector),
     Regex(Regex),
+    Glob(Regex, String),
+    Type(String),
+    // the selector, its alias and the selector as written
+    Alias(Box<Selector>, String, String),
+    Exclude(Box<Selector>),
 }
 
 #[derive(Clone)]
 enum OneSelector {
     Start,
     End,
+    FromEnd(usize),
     Index(usize),
     IndexedName(String, usize),
 }
 
 impl Selector {
+    fn needs_types(&self) -> bool {
+        match *self {
+            Selector::Type(_) => true,
+            Selector::Alias(ref sel, ..) | Selector::Exclude(ref sel) => sel.needs_types(),
+            _ => false,
+        }
+    }
+
+    /// The index of the column whose name is the selector as written, if
+    /// any. Such a column (e.g. 'Price as Reported' or 'Total*') is selected
+    /// by name, rather than with the alias, glob or type syntax.
+    fn exact_name(&self, first_record: &csv::ByteRecord, use_names: bool) -> Option<usize> {
+        let name = match *self {
+            Selector::Glob(_, ref raw) | Selector::Alias(_, _, ref raw) => raw.clone(),
+            Selector::Type(ref ty) => format!(":{ty}"),
+            _ => return None,
+        };
+        if !use_names {
+            return None;
+        }
+        first_record.iter().position(|h| h == name.as_bytes())
+    }
+
     fn indices(
         &self,
         first_record: &csv::ByteRecord,
         use_names: bool,
+        types: Option<&[String]>,
     ) -> Result<Vec<usize>, String> {
+        if let Some(i) = self.exact_name(first_record, use_names) {
+            return Ok(vec![i]);
+        }
         match *self {
             Selector::One(ref sel) => sel.index(first_record, use_names).map(|i| vec![i]),
             Selector::Range(ref sel1, ref sel2) => {
@@ -304,10 +540,63 @@ impl Selector {
                 }
                 Ok(inds)
             }
+            Selector::Glob(ref re, ref glob) => {
+                let inds: Vec<usize> = first_record
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, h)| re.is_match(h))
+                    .map(|(i, _)| i)
+                    .collect();
+                if inds.is_empty() {
+                    return Err(format!(
+                        "Selector pattern '{glob}' does not match any columns in the CSV header."
+                    ));
+                }
+                Ok(inds)
+            }
+            Selector::Type(ref ty) => {
+                let types = match types {
+                    Some(types) => types,
+                    None => {
+                        return Err(format!(
+                            "Selector ':{ty}' needs the types of the columns, which \
+                             aren't known here."
+                        ))
+                    }
+                };
+                let inds: Vec<usize> = types
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, t)| type_matches(ty, t))
+                    .map(|(i, _)| i)
+                    .collect();
+                if inds.is_empty() {
+                    return Err(format!("Selector ':{ty}' does not match any columns."));
+                }
+                Ok(inds)
+            }
+            Selector::Alias(ref sel, ..) | Selector::Exclude(ref sel) => {
+                sel.indices(first_record, use_names, types)
+            }
         }
     }
 }
 
+/// Whether a column of type `ty` (as reported by `qsv stats`) is selected by
+/// the type selector `sel`.
+fn type_matches(sel: &str, ty: &str) -> bool {
+    match sel {
+        "numeric" => ty == "Integer" || ty == "Float",
+        "integer" => ty == "Integer",
+        "float" => ty == "Float",
+        "string" => ty == "String",
+        "boolean" => ty == "Boolean",
+        "date" => ty == "Date" || ty == "DateTime",
+        "null" => ty == "NULL",
+        _ => false,
+    }
+}
+
 impl OneSelector {
     fn index(&self, first_record: &csv::ByteRecord, use_names: bool) -> Result<usize, String> {
         match *self {
@@ -317,6 +606,16 @@ impl OneSelector {
             } else {
                 first_record.len() - 1
             }),
+            OneSelector::FromEnd(n) => {
+                if n >= first_record.len() {
+                    Err(format!(
+                        "Selector '_-{n}' is out of bounds. There are only {} columns.",
+                        first_record.len()
+                    ))
+                } else {
+                    Ok(first_record.len() - 1 - n)
+                }
+            }
             OneSelector::Index(i) => {
                 if first_record.is_empty() {
                     return Err("Input is empty.".to_string());
@@ -373,6 +672,10 @@ impl fmt::Debug for Selector {
             Selector::One(ref sel) => sel.fmt(f),
             Selector::Range(ref s, ref e) => write!(f, "Range({s:?}, {e:?})"),
             Selector::Regex(ref re) => re.fmt(f),
+            Selector::Glob(_, ref glob) => write!(f, "Glob({glob})"),
+            Selector::Type(ref ty) => write!(f, "Type({ty})"),
+            Selector::Alias(ref sel, ref alias, _) => write!(f, "Alias({sel:?}, {alias})"),
+            Selector::Exclude(ref sel) => write!(f, "Exclude({sel:?})"),
         }
     }
 }
@@ -382,6 +685,7 @@ impl fmt::Debug for OneSelector {
         match *self {
             OneSelector::Start => write!(f, "Start"),
             OneSelector::End => write!(f, "End"),
+            OneSelector::FromEnd(n) => write!(f, "FromEnd({n})"),
             OneSelector::Index(idx) => write!(f, "Index({idx})"),
             OneSelector::IndexedName(ref s, idx) => write!(f, "IndexedName({s}[{idx}])"),
         }
@@ -389,11 +693,25 @@ impl fmt::Debug for OneSelector {
 }
 
 #[derive(Clone, Debug)]
-pub struct Selection(Vec<usize>);
+pub struct Selection {
+    map: Vec<usize>,
+    // the aliases of the selected columns, if any
+    aliases: Vec<Option<String>>,
+}
 
 pub type _GetField = for<'c> fn(&mut &'c csv::ByteRecord, &usize) -> Option<&'c [u8]>;
 
 impl Selection {
+    fn new(map: Vec<usize>) -> Selection {
+        let aliases = vec![None; map.len()];
+        Selection { map, aliases }
+    }
+
+    /// The alias given to the `i`th selected column with `<column> as <alias>`.
+    pub fn alias(&self, i: usize) -> Option<&str> {
+        self.aliases.get(i)?.as_deref()
+    }
+
     pub fn select<'a, 'b>(
         &'a self,
         row: &'b csv::ByteRecord,
@@ -407,7 +725,7 @@ impl Selection {
     }
 
     pub fn normal(&self) -> NormalSelection {
-        let &Selection(ref inds) = self;
+        let inds = &self.map;
         if inds.is_empty() {
             return NormalSelection(vec![]);
         }
@@ -423,7 +741,7 @@ impl Selection {
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.map.len()
     }
 }
 
@@ -431,7 +749,7 @@ impl ops::Deref for Selection {
     type Target = [usize];
 
     fn deref(&self) -> &[usize] {
-        &self.0
+        &self.map
     }
 }
 
@@ -0,0 +1,379 @@
+//! Sketches that summarize a stream of data in bounded memory, and that can
+//! be merged, so they can be computed on chunks of the data in parallel.
+
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::f64::consts::PI;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use ahash::{AHashMap, RandomState};
+use stats::Commute;
+
+/// The compression of a `TDigest`, i.e. roughly the number of centroids it
+/// keeps. The rank of its quantiles is typically within 0.1% of the exact one.
+const TDIGEST_COMPRESSION: f64 = 200.0;
+
+/// The number of bits of the hash of a value that select a register of a
+/// `HyperLogLog`, i.e. it has 2^14 registers and a standard error of 0.8%.
+const HLL_PRECISION: u32 = 14;
+
+/// The hasher of the values of a `HyperLogLog`, with fixed seeds, so that
+/// the sketches of different chunks of the data can be merged.
+const HLL_HASHER: RandomState = RandomState::with_seeds(
+    0x243f_6a88_85a3_08d3,
+    0x1319_8a2e_0370_7344,
+    0xa409_3822_299f_31d0,
+    0x082e_fa98_ec4e_6c89,
+);
+
+#[derive(Clone, Copy, Debug)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// A merging t-digest, to compute approximate quantiles of a stream of
+/// numbers.
+///
+/// The numbers are buffered and merged into centroids, which are small near
+/// the minimum and the maximum and larger around the median, so the
+/// quantiles are more accurate at the tails. Small streams are kept exactly.
+///
+/// See Ted Dunning and Otmar Ertl, "Computing Extremely Accurate Quantiles
+/// Using t-Digests".
+#[derive(Clone, Debug)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    buffer: Vec<f64>,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    pub fn new() -> TDigest {
+        TDigest {
+            compression: TDIGEST_COMPRESSION,
+            centroids: vec![],
+            buffer: vec![],
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Add a number. NaNs are ignored.
+    #[inline]
+    pub fn add(&mut self, x: f64) {
+        if x.is_nan() {
+            return;
+        }
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+        self.buffer.push(x);
+        if self.buffer.len() as f64 >= self.compression * 5.0 {
+            self.flush();
+        }
+    }
+
+    /// The approximate `q` quantile, with `q` between 0 and 1, interpolated
+    /// linearly between the centroids.
+    pub fn quantile(&mut self, q: f64) -> Option<f64> {
+        self.flush();
+        let centroids = &self.centroids;
+        match centroids.len() {
+            0 => return None,
+            1 => return Some(centroids[0].mean),
+            _ => {}
+        }
+        let total: f64 = centroids.iter().map(|c| c.weight).sum();
+        let target = q.clamp(0.0, 1.0) * total;
+
+        // each centroid is centered on the middle of its weight
+        let first = centroids[0];
+        if target < first.weight / 2.0 {
+            let frac = target / (first.weight / 2.0);
+            return Some(lerp(self.min, first.mean, frac));
+        }
+        let mut center = first.weight / 2.0;
+        for pair in centroids.windows(2) {
+            let next = center + (pair[0].weight + pair[1].weight) / 2.0;
+            if target < next {
+                let frac = (target - center) / (next - center);
+                return Some(lerp(pair[0].mean, pair[1].mean, frac));
+            }
+            center = next;
+        }
+        let last = centroids[centroids.len() - 1];
+        let frac = (target - center) / (last.weight / 2.0);
+        Some(lerp(last.mean, self.max, frac.min(1.0)))
+    }
+
+    /// The approximate median absolute deviation, i.e. the median of the
+    /// distances of the numbers to their median, with the centroids standing
+    /// in for the numbers they were merged from.
+    pub fn median_absolute_deviation(&mut self) -> Option<f64> {
+        let median = self.quantile(0.5)?;
+        let items: Vec<Centroid> = self
+            .centroids
+            .iter()
+            .map(|c| Centroid {
+                mean: (c.mean - median).abs(),
+                weight: c.weight,
+            })
+            .collect();
+        let mut deviations = TDigest::new();
+        deviations.min = items.iter().map(|c| c.mean).fold(f64::INFINITY, f64::min);
+        deviations.max = (self.max - median).max(median - self.min);
+        deviations.compress(items);
+        deviations.quantile(0.5)
+    }
+
+    /// Merge the buffered numbers into the centroids.
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut items = std::mem::take(&mut self.centroids);
+        items.extend(
+            self.buffer
+                .drain(..)
+                .map(|mean| Centroid { mean, weight: 1.0 }),
+        );
+        self.compress(items);
+    }
+
+    /// Replace the centroids with `items`, merging neighbours as long as
+    /// they stay within the size limit of the k1 scale function.
+    fn compress(&mut self, mut items: Vec<Centroid>) {
+        items.sort_unstable_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap_or(Ordering::Equal));
+        let total: f64 = items.iter().map(|c| c.weight).sum();
+
+        let mut centroids = Vec::with_capacity(self.compression as usize);
+        let mut items = items.into_iter();
+        let mut cur = match items.next() {
+            None => return,
+            Some(c) => c,
+        };
+        let mut weight_so_far = 0.0;
+        let mut limit = total * self.k_to_q(self.q_to_k(0.0) + 1.0);
+        for c in items {
+            if weight_so_far + cur.weight + c.weight <= limit {
+                cur.weight += c.weight;
+                cur.mean += (c.mean - cur.mean) * c.weight / cur.weight;
+            } else {
+                weight_so_far += cur.weight;
+                centroids.push(cur);
+                limit = total * self.k_to_q(self.q_to_k(weight_so_far / total) + 1.0);
+                cur = c;
+            }
+        }
+        centroids.push(cur);
+        self.centroids = centroids;
+    }
+
+    fn q_to_k(&self, q: f64) -> f64 {
+        self.compression / (2.0 * PI) * (2.0 * q - 1.0).asin()
+    }
+
+    fn k_to_q(&self, k: f64) -> f64 {
+        let k = k.min(self.compression / 4.0);
+        ((k * 2.0 * PI / self.compression).sin() + 1.0) / 2.0
+    }
+}
+
+impl Default for TDigest {
+    fn default() -> TDigest {
+        TDigest::new()
+    }
+}
+
+impl Commute for TDigest {
+    fn merge(&mut self, other: TDigest) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        let mut items = std::mem::take(&mut self.centroids);
+        items.extend(other.centroids);
+        items.extend(
+            self.buffer
+                .drain(..)
+                .chain(other.buffer)
+                .map(|mean| Centroid { mean, weight: 1.0 }),
+        );
+        self.compress(items);
+    }
+}
+
+#[inline]
+fn lerp(a: f64, b: f64, frac: f64) -> f64 {
+    a + (b - a) * frac
+}
+
+/// A HyperLogLog sketch, to estimate the number of distinct values of a
+/// stream in a fixed 16KB of memory.
+///
+/// Each register keeps the longest run of leading zeros seen in the hashes
+/// of the values that map to it. Small cardinalities are estimated with
+/// linear counting of the empty registers, so they are nearly exact.
+///
+/// See Flajolet et al., "HyperLogLog: the analysis of a near-optimal
+/// cardinality estimation algorithm".
+#[derive(Clone, Debug)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub fn new() -> HyperLogLog {
+        HyperLogLog {
+            registers: vec![0; 1 << HLL_PRECISION],
+        }
+    }
+
+    #[inline]
+    pub fn add(&mut self, value: &[u8]) {
+        let mut hasher = HLL_HASHER.build_hasher();
+        hasher.write(value);
+        let hash = hasher.finish();
+
+        let i = (hash >> (64 - HLL_PRECISION)) as usize;
+        let rest = hash << HLL_PRECISION;
+        let rank = (rest.leading_zeros().min(64 - HLL_PRECISION) + 1) as u8;
+        if rank > self.registers[i] {
+            self.registers[i] = rank;
+        }
+    }
+
+    /// The estimated number of distinct values added.
+    pub fn cardinality(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&r| 1.0 / (1u64 << r) as f64)
+            .sum();
+        let estimate = alpha * m * m / sum;
+
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+impl Default for HyperLogLog {
+    fn default() -> HyperLogLog {
+        HyperLogLog::new()
+    }
+}
+
+impl Commute for HyperLogLog {
+    fn merge(&mut self, other: HyperLogLog) {
+        for (r, o) in self.registers.iter_mut().zip(other.registers) {
+            *r = (*r).max(o);
+        }
+    }
+}
+
+/// A Space-Saving sketch, to find the most frequent values of a stream, and
+/// estimate their counts, with a bounded number of counters.
+///
+/// A value that isn't counted gets a counter whose count starts at `floor`,
+/// the largest count of the counters evicted so far, which is an upper bound
+/// of the actual count of any value that isn't counted. So the counts
+/// overestimate the actual counts, by at most their `error`, which is the
+/// floor when their counter was created. The counters are evicted in batches,
+/// when there are twice as many as the capacity, by keeping the largest ones.
+///
+/// See Metwally et al., "Efficient computation of frequent and top-k
+/// elements in data streams", and Agarwal et al., "Mergeable summaries".
+#[derive(Clone, Debug)]
+pub struct SpaceSaving<T: Eq + Hash> {
+    capacity: usize,
+    floor: u64,
+    total: u64,
+    /// The count and the error of each counted value.
+    counters: AHashMap<T, (u64, u64)>,
+}
+
+impl<T: Eq + Hash> SpaceSaving<T> {
+    pub fn new(capacity: usize) -> SpaceSaving<T> {
+        SpaceSaving {
+            capacity: capacity.max(1),
+            floor: 0,
+            total: 0,
+            counters: AHashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn add(&mut self, value: T) {
+        self.total += 1;
+        match self.counters.entry(value) {
+            Entry::Occupied(mut e) => e.get_mut().0 += 1,
+            Entry::Vacant(e) => {
+                e.insert((self.floor + 1, self.floor));
+                if self.counters.len() >= 2 * self.capacity {
+                    self.prune();
+                }
+            }
+        }
+    }
+
+    /// Evicts all the counters but the `capacity` largest ones, or fewer
+    /// when the smallest of those are tied with evicted ones.
+    fn prune(&mut self) {
+        if self.counters.len() <= self.capacity {
+            return;
+        }
+        let mut counts: Vec<u64> = self.counters.values().map(|c| c.0).collect();
+        let (_, &mut threshold, _) = counts.select_nth_unstable_by(self.capacity, |a, b| b.cmp(a));
+        self.counters.retain(|_, c| c.0 > threshold);
+        self.floor = self.floor.max(threshold);
+    }
+
+    /// The number of values added.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// The counted values with their estimated counts and errors, sorted by
+    /// descending count, then by value.
+    pub fn most_frequent(&self) -> Vec<(&T, u64, u64)>
+    where
+        T: Ord,
+    {
+        let mut counts: Vec<_> = self
+            .counters
+            .iter()
+            .map(|(value, &(count, error))| (value, count, error))
+            .collect();
+        counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        counts
+    }
+}
+
+impl<T: Eq + Hash> Commute for SpaceSaving<T> {
+    /// The counts of the values counted by a single sketch are summed with
+    /// the floor of the other one, as an upper bound of their count there.
+    fn merge(&mut self, mut other: SpaceSaving<T>) {
+        let (floor, other_floor) = (self.floor, other.floor);
+        let mut counters = AHashMap::with_capacity(self.counters.len() + other.counters.len());
+        for (value, (count, error)) in self.counters.drain() {
+            let (other_count, other_error) = other
+                .counters
+                .remove(&value)
+                .unwrap_or((other_floor, other_floor));
+            counters.insert(value, (count + other_count, error + other_error));
+        }
+        for (value, (count, error)) in other.counters {
+            counters.insert(value, (count + floor, error + floor));
+        }
+        self.counters = counters;
+        self.floor = floor + other_floor;
+        self.total += other.total;
+        self.prune();
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::{cmp, env, fs, io, str, thread, time};
 
@@ -8,6 +9,7 @@ use crate::CliResult;
 use docopt::Docopt;
 use indicatif::{ProgressBar, ProgressStyle};
 use log::{debug, info, log_enabled, Level};
+use once_cell::sync::OnceCell;
 use regex::Regex;
 use serde::de::{Deserialize, DeserializeOwned, Deserializer, Error};
 use thousands::Separable;
@@ -88,16 +90,29 @@ pub fn version() -> String {
 
 const OTHER_ENV_VARS: &[&str] = &["no_proxy", "http_proxy", "https_proxy"];
 
+fn is_qsv_env_var(env_var: &str) -> bool {
+    env_var.starts_with("QSV_")
+        || env_var.starts_with("MIMALLOC_")
+        || OTHER_ENV_VARS.contains(&env_var.to_lowercase().as_str())
+}
+
 pub fn show_env_vars() {
+    let defaults = QSV_TOML.get();
     let mut env_var_set = false;
     for (n, v) in env::vars_os() {
         let env_var = n.into_string().unwrap();
-        if env_var.starts_with("QSV_")
-            || env_var.starts_with("MIMALLOC_")
-            || OTHER_ENV_VARS.contains(&env_var.to_lowercase().as_str())
-        {
+        if is_qsv_env_var(&env_var) {
+            env_var_set = true;
+            match defaults.and_then(|d| d.env.get(&env_var)) {
+                Some(source) => println!("{env_var}: {v:?} ({})", source.display()),
+                None => println!("{env_var}: {v:?}"),
+            }
+        }
+    }
+    for (command, flags) in defaults.map(|d| &d.commands).into_iter().flatten() {
+        for (flag, (value, source)) in flags {
             env_var_set = true;
-            println!("{env_var}: {v:?}");
+            println!("{command} {flag}: {value} ({})", source.display());
         }
     }
     if !env_var_set {
@@ -105,6 +120,172 @@ pub fn show_env_vars() {
     }
 }
 
+/// The name of the qsv configuration file, which is looked up in the
+/// working directory (project file) and in the user config directory
+/// (user file).
+pub const QSV_TOML_FILENAME: &str = "qsv.toml";
+
+/// The contents of a qsv.toml file, e.g.
+///
+/// ```toml
+/// [env]
+/// QSV_DEFAULT_DELIMITER = ";"
+///
+/// [commands.stats]
+/// everything = true
+/// delimiter = ";"
+/// ```
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QsvToml {
+    #[serde(default)]
+    env: BTreeMap<String, toml::Value>,
+    #[serde(default)]
+    commands: BTreeMap<String, BTreeMap<String, toml::Value>>,
+}
+
+/// The defaults loaded from the qsv.toml files, along with the file they
+/// were taken from.
+#[derive(Default)]
+struct QsvDefaults {
+    env: BTreeMap<String, PathBuf>,
+    commands: BTreeMap<String, BTreeMap<String, (toml::Value, PathBuf)>>,
+}
+
+static QSV_TOML: OnceCell<QsvDefaults> = OnceCell::new();
+
+/// Load the project and user qsv.toml files, if they exist.
+///
+/// Environment variables set in the files are only applied when they aren't
+/// already set, and the project file takes precedence over the user file.
+/// Command defaults are applied in `get_args`, unless overridden by a flag.
+pub fn load_qsv_toml() -> Result<(), String> {
+    let mut defaults = QsvDefaults::default();
+    let user_file = dirs::config_dir().map(|d| d.join("qsv").join(QSV_TOML_FILENAME));
+    let project_file = env::current_dir().ok().map(|d| d.join(QSV_TOML_FILENAME));
+    let paths = [project_file, user_file];
+    for path in paths.into_iter().flatten() {
+        if !path.is_file() {
+            continue;
+        }
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+        let qsv_toml: QsvToml =
+            toml::from_str(&contents).map_err(|e| format!("Invalid {}: {e}", path.display()))?;
+        debug!("loading defaults from {}", path.display());
+
+        for (env_var, value) in qsv_toml.env {
+            if !is_qsv_env_var(&env_var) {
+                return Err(format!(
+                    "Invalid {}: {env_var} is not a qsv environment variable.",
+                    path.display()
+                ));
+            }
+            let value = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                    value.to_string()
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid {}: {env_var} must be a string, number or boolean.",
+                        path.display()
+                    ))
+                }
+            };
+            if env::var_os(&env_var).is_none() {
+                env::set_var(&env_var, value);
+                defaults.env.insert(env_var, path.clone());
+            }
+        }
+        for (command, flags) in qsv_toml.commands {
+            let command_defaults = defaults.commands.entry(command).or_default();
+            for (flag, value) in flags {
+                let flag = if flag.starts_with('-') {
+                    flag
+                } else if flag.len() == 1 {
+                    format!("-{flag}")
+                } else {
+                    format!("--{flag}")
+                };
+                command_defaults
+                    .entry(flag)
+                    .or_insert_with(|| (value, path.clone()));
+            }
+        }
+    }
+    // only the first call sets the defaults
+    let _ = QSV_TOML.set(defaults);
+    Ok(())
+}
+
+/// Prepend the qsv.toml defaults of the command in `argv` to its arguments,
+/// skipping flags the user already passed (by their long or short name).
+fn with_command_defaults(usage: &str, argv: &[&str]) -> Result<Vec<String>, String> {
+    let mut args: Vec<String> = argv.iter().map(|&s| s.to_owned()).collect();
+    let flags = match QSV_TOML
+        .get()
+        .and_then(|d| argv.get(1).and_then(|command| d.commands.get(*command)))
+    {
+        Some(flags) if !flags.is_empty() => flags,
+        _ => return Ok(args),
+    };
+
+    static SYNONYMS_RE: OnceCell<Regex> = OnceCell::new();
+    let synonyms_re = SYNONYMS_RE
+        .get_or_init(|| Regex::new(r"(?m)^\s*(-[[:alnum:]]),\s*(--[[:alnum:]-]+)").unwrap());
+    let mut synonyms = BTreeMap::new();
+    for caps in synonyms_re.captures_iter(usage) {
+        synonyms.insert(caps[1].to_owned(), caps[2].to_owned());
+        synonyms.insert(caps[2].to_owned(), caps[1].to_owned());
+    }
+    let user_args: Vec<&str> = argv[2..]
+        .iter()
+        .copied()
+        .take_while(|&arg| arg != "--")
+        .collect();
+    let is_passed = |flag: &str| {
+        user_args.iter().any(|arg| {
+            if flag.starts_with("--") {
+                *arg == flag || arg.starts_with(&format!("{flag}="))
+            } else {
+                !arg.starts_with("--") && arg.starts_with(flag)
+            }
+        })
+    };
+
+    let mut defaults = Vec::new();
+    for (flag, (value, source)) in flags {
+        if is_passed(flag) || synonyms.get(flag).map_or(false, |s| is_passed(s)) {
+            continue;
+        }
+        let values = match value {
+            toml::Value::Array(values) => values.clone(),
+            value => vec![value.clone()],
+        };
+        for value in values {
+            match value {
+                toml::Value::Boolean(true) => defaults.push(flag.clone()),
+                toml::Value::Boolean(false) => {}
+                toml::Value::String(s) => defaults.extend([flag.clone(), s]),
+                toml::Value::Integer(_) | toml::Value::Float(_) => {
+                    defaults.extend([flag.clone(), value.to_string()]);
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid {}: {} {flag} must be a string, number or boolean.",
+                        source.display(),
+                        argv[1]
+                    ))
+                }
+            }
+        }
+    }
+    debug!("qsv.toml defaults: {defaults:?}");
+    args.splice(2..2, defaults);
+    Ok(args)
+}
+
 pub fn count_rows(conf: &Config) -> u64 {
     match conf.indexed().unwrap() {
         Some(idx) => idx.count(),
@@ -201,9 +382,10 @@ pub fn get_args<T>(usage: &str, argv: &[&str]) -> CliResult<T>
 where
     T: DeserializeOwned,
 {
+    let argv = with_command_defaults(usage, argv)?;
     Docopt::new(usage)
         .and_then(|d| {
-            d.argv(argv.iter().copied())
+            d.argv(argv.iter().map(String::as_str))
                 .version(Some(version()))
                 .deserialize()
         })
@@ -231,6 +413,12 @@ pub fn many_configs(
     Ok(confs)
 }
 
+/// Whether `path` is an http(s) URL rather than a local file path.
+pub fn is_url(path: &str) -> bool {
+    let path = path.to_ascii_lowercase();
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
 pub fn errif_greater_one_stdin(inps: &[Config]) -> Result<(), String> {
     let nstd = inps.iter().filter(|inp| inp.is_std()).count();
     if nstd > 1 {
@@ -258,11 +446,6 @@ pub fn num_of_chunks(nitems: usize, chunk_size: usize) -> usize {
     n
 }
 
-pub fn last_modified(md: &fs::Metadata) -> u64 {
-    use filetime::FileTime;
-    FileTime::from_last_modification_time(md).unix_seconds() as u64
-}
-
 pub fn condense(val: Cow<[u8]>, n: Option<usize>) -> Cow<[u8]> {
     match n {
         None => val,
@@ -300,6 +483,26 @@ pub fn idx_path(csv_path: &Path) -> PathBuf {
     PathBuf::from(&p)
 }
 
+pub fn key_idx_path(csv_path: &Path) -> PathBuf {
+    let mut p = csv_path
+        .to_path_buf()
+        .into_os_string()
+        .into_string()
+        .unwrap();
+    p.push_str(".kidx");
+    PathBuf::from(&p)
+}
+
+pub fn stats_cache_path(csv_path: &Path) -> PathBuf {
+    let mut p = csv_path
+        .to_path_buf()
+        .into_os_string()
+        .into_string()
+        .unwrap();
+    p.push_str(".stats.json");
+    PathBuf::from(&p)
+}
+
 pub type Idx = Option<usize>;
 
 pub fn range(start: Idx, end: Idx, len: Idx, index: Idx) -> Result<(usize, usize), String> {
@@ -0,0 +1,150 @@
+use std::fs;
+use std::io::{Read, Write};
+
+use crate::workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["h1", "h2"],
+        svec!["a", "1"],
+        svec!["b", "2"],
+        svec!["c", "3"],
+    ]
+}
+
+fn gzip(wrk: &Workdir, name: &str, contents: &str) {
+    let file = fs::File::create(wrk.path(name)).unwrap();
+    let mut enc = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    enc.write_all(contents.as_bytes()).unwrap();
+    enc.finish().unwrap();
+}
+
+#[test]
+fn compress_read_gzip() {
+    let wrk = Workdir::new("compress_read_gzip");
+    gzip(&wrk, "in.csv.gz", "h1,h2\na,1\nb,2\nc,3\n");
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("h2,h1").arg("in.csv.gz");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h2", "h1"],
+        svec!["1", "a"],
+        svec!["2", "b"],
+        svec!["3", "c"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn compress_read_gzip_magic_bytes() {
+    let wrk = Workdir::new("compress_read_gzip_magic_bytes");
+    gzip(&wrk, "in.csv", "h1,h2\na,1\nb,2\nc,3\n");
+
+    let mut cmd = wrk.command("count");
+    cmd.arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "3");
+}
+
+#[test]
+fn compress_read_tsv_gzip_delimiter() {
+    let wrk = Workdir::new("compress_read_tsv_gzip_delimiter");
+    gzip(&wrk, "in.tsv.gz", "h1\th2\na\t1\nb\t2\nc\t3\n");
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("h2").arg("in.tsv.gz");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h2"], svec!["1"], svec!["2"], svec!["3"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn compress_write_gzip() {
+    let wrk = Workdir::new("compress_write_gzip");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("fmt");
+    cmd.arg("in.csv").args(["--output", "out.csv.gz"]);
+    wrk.run(&mut cmd);
+
+    let mut got = String::new();
+    flate2::read::GzDecoder::new(fs::File::open(wrk.path("out.csv.gz")).unwrap())
+        .read_to_string(&mut got)
+        .unwrap();
+    assert_eq!(got, "h1,h2\na,1\nb,2\nc,3\n");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn compress_write_error() {
+    let wrk = Workdir::new("compress_write_error");
+    wrk.create("in.csv", data());
+    // failing to write the end of the compressed stream is an error
+    let _ = std::fs::remove_file(wrk.path("out.csv.zst"));
+    std::os::unix::fs::symlink("/dev/full", wrk.path("out.csv.zst")).unwrap();
+
+    let mut cmd = wrk.command("fmt");
+    cmd.arg("in.csv").args(["--output", "out.csv.zst"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn compress_roundtrip() {
+    for ext in ["gz", "zst", "bz2", "sz"] {
+        let wrk = Workdir::new("compress_roundtrip");
+        wrk.create("in.csv", data());
+        let compressed = format!("out.csv.{ext}");
+
+        let mut cmd = wrk.command("select");
+        cmd.arg("1-2").arg("in.csv").args(["--output", &compressed]);
+        wrk.run(&mut cmd);
+
+        let mut cmd = wrk.command("cat");
+        cmd.arg("rows").arg(&compressed);
+
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        assert_eq!(got, data(), "roundtrip failed for .{ext}");
+    }
+}
+
+#[test]
+fn compress_read_stdin() {
+    let wrk = Workdir::new("compress_read_stdin");
+    gzip(&wrk, "in.csv.gz", "h1,h2\na,1\nb,2\nc,3\n");
+
+    let mut cmd = wrk.command("count");
+    cmd.stdin(fs::File::open(wrk.path("in.csv.gz")).unwrap());
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "3");
+}
+
+#[test]
+fn compress_index_error() {
+    let wrk = Workdir::new("compress_index_error");
+    gzip(&wrk, "in.csv.gz", "h1,h2\na,1\nb,2\nc,3\n");
+
+    let mut cmd = wrk.command("index");
+    cmd.arg("in.csv.gz");
+
+    let got = wrk.output_stderr(&mut cmd);
+    assert!(got.contains("Please decompress it first."));
+}
+
+#[test]
+fn compress_stats_streams() {
+    let wrk = Workdir::new("compress_stats_streams");
+    gzip(&wrk, "in.csv.gz", "h1,h2\na,1\nb,2\nc,3\n");
+
+    let mut cmd = wrk.command("stats");
+    cmd.arg("in.csv.gz").args(["--select", "h2"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][0], "h2");
+    assert_eq!(got[1][1], "Integer");
+    assert_eq!(got[1][2], "6");
+}
@@ -0,0 +1,80 @@
+use crate::workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["x", "y", "z", "name"],
+        svec!["1", "2", "9", "a"],
+        svec!["2", "4", "", "b"],
+        svec!["3", "7", "4", "c"],
+        svec!["4", "8", "1", "d"],
+    ]
+}
+
+fn setup(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create("in.csv", data());
+    wrk
+}
+
+#[test]
+fn corr_pearson() {
+    let wrk = setup("corr_pearson");
+    let mut cmd = wrk.command("corr");
+    cmd.arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "x", "y", "z"],
+        svec!["x", "1", "0.9844951849708403", "-0.9989061072386719"],
+        svec!["y", "0.9844951849708403", "1", "-0.9750002110024923"],
+        svec!["z", "-0.9989061072386719", "-0.9750002110024923", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn corr_pearson_index() {
+    let wrk = Workdir::new("corr_pearson_index");
+    wrk.create_indexed("in.csv", data());
+
+    let mut cmd = wrk.command("corr");
+    cmd.args(["--jobs", "2", "--select", "x,y"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "x", "y"],
+        svec!["x", "1", "0.9844951849708403"],
+        svec!["y", "0.9844951849708403", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn corr_spearman() {
+    let wrk = setup("corr_spearman");
+    let mut cmd = wrk.command("corr");
+    cmd.arg("--spearman").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "x", "y", "z"],
+        svec!["x", "1", "1", "-1"],
+        svec!["y", "1", "1", "-1"],
+        svec!["z", "-1", "-1", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn corr_covariance() {
+    let wrk = setup("corr_covariance");
+    let mut cmd = wrk.command("corr");
+    cmd.args(["--covariance", "--select", "x,y"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "x", "y"],
+        svec!["x", "1.6666666666666667", "3.5"],
+        svec!["y", "3.5", "7.583333333333333"],
+    ];
+    assert_eq!(got, expected);
+}
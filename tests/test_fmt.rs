@@ -88,3 +88,102 @@ mnopqr,stuvwx
 ab\"cd\"ef,gh,ij,kl";
     assert_eq!(got, expected.to_string());
 }
+
+#[test]
+fn fmt_multi_byte_delimiter() {
+    let (wrk, mut cmd) = setup("fmt_multi_byte_delimiter");
+    cmd.args(["--out-delimiter", "~|~"]);
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+h1~|~h2
+abcdef~|~ghijkl
+mnopqr~|~stuvwx
+\"ab\"\"cd\"\"ef\"~|~gh,ij,kl";
+    assert_eq!(got, expected.to_string());
+}
+
+#[test]
+fn fmt_non_ascii_delimiter_terminator() {
+    let (wrk, mut cmd) = setup("fmt_non_ascii_delimiter_terminator");
+    cmd.args(["--out-delimiter", "¦"])
+        .args(["--out-terminator", "§\\n"]);
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+h1¦h2§
+abcdef¦ghijkl§
+mnopqr¦stuvwx§
+\"ab\"\"cd\"\"ef\"¦gh,ij,kl§";
+    assert_eq!(got, expected.to_string());
+}
+
+#[test]
+fn fmt_read_multi_byte_delimiter() {
+    let wrk = Workdir::new("fmt_read_multi_byte_delimiter");
+    wrk.create_from_string("in.txt", "h1¦h2\nabc¦\"d¦ef\"\n");
+
+    let mut cmd = wrk.command("fmt");
+    cmd.args(["--delimiter", "¦"]).arg("in.txt");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+h1,h2
+abc,d¦ef";
+    assert_eq!(got, expected.to_string());
+}
+
+#[test]
+fn fmt_out_encoding() {
+    let wrk = Workdir::new("fmt_out_encoding");
+    wrk.create_from_string("in.csv", "name,n\ncafé,1\nnaïve,2\n");
+
+    let mut cmd = wrk.command("fmt");
+    cmd.args(["--out-encoding", "windows-1252"])
+        .arg("in.csv")
+        .args(["--output", "out.csv"]);
+    wrk.run(&mut cmd);
+
+    let got = std::fs::read(wrk.path("out.csv")).unwrap();
+    assert_eq!(got, b"name,n\ncaf\xe9,1\nna\xefve,2\n");
+}
+
+#[test]
+fn fmt_out_encoding_unmappable() {
+    let wrk = Workdir::new("fmt_out_encoding_unmappable");
+    wrk.create_from_string("in.csv", "name\n日本\n");
+
+    let mut cmd = wrk.command("fmt");
+    cmd.args(["--out-encoding", "windows-1252"]).arg("in.csv");
+
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn fmt_multi_byte_delimiter_in_field() {
+    let wrk = Workdir::new("fmt_multi_byte_delimiter_in_field");
+    wrk.create_from_string("in.csv", "h1,h2,h3\nx||y,a|,z\n\"p\x1fq\",b,c\n");
+
+    let mut cmd = wrk.command("fmt");
+    cmd.args(["--out-delimiter", "||"]).arg("in.csv");
+
+    // fields that contain (the start of) the delimiter are quoted, and the
+    // placeholder byte in data is left as is
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "\
+h1||h2||h3
+\"x||y\"||\"a|\"||z
+\"p\x1fq\"||b||c";
+    assert_eq!(got, expected.to_string());
+
+    wrk.create_from_string("out.txt", &got);
+    let mut cmd = wrk.command("input");
+    cmd.args(["--delimiter", "||"]).arg("out.txt");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["h1", "h2", "h3"],
+        svec!["x||y", "a|", "z"],
+        svec!["p\x1fq", "b", "c"],
+    ];
+    assert_eq!(got, expected);
+}
@@ -40,11 +40,11 @@ fn frequency_no_headers() {
     got = got.into_iter().skip(1).collect();
     got.sort();
     let expected = vec![
-        svec!["1", "(NULL)", "1"],
-        svec!["1", "(NULL)", "1"],
-        svec!["1", "a", "3"],
-        svec!["1", "b", "1"],
-        svec!["1", "h1", "1"],
+        svec!["1", "(NULL)", "1", "14.29", "57.14"],
+        svec!["1", "(NULL)", "1", "14.29", "71.43"],
+        svec!["1", "a", "3", "42.86", "42.86"],
+        svec!["1", "b", "1", "14.29", "85.71"],
+        svec!["1", "h1", "1", "14.29", "100.00"],
     ];
     assert_eq!(got, expected);
 }
@@ -59,10 +59,16 @@ fn frequency_no_nulls() {
     let mut got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
     got.sort();
     let expected = vec![
-        svec!["field", "value", "count"],
-        svec!["h1", "(NULL)", "1"],
-        svec!["h1", "a", "3"],
-        svec!["h1", "b", "1"],
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage"
+        ],
+        svec!["h1", "(NULL)", "1", "20.00", "80.00"],
+        svec!["h1", "a", "3", "60.00", "60.00"],
+        svec!["h1", "b", "1", "20.00", "100.00"],
     ];
     assert_eq!(got, expected);
 }
@@ -75,11 +81,17 @@ fn frequency_nulls() {
     let mut got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
     got.sort();
     let expected = vec![
-        svec!["field", "value", "count"],
-        svec!["h1", "(NULL)", "1"],
-        svec!["h1", "(NULL)", "1"],
-        svec!["h1", "a", "3"],
-        svec!["h1", "b", "1"],
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage"
+        ],
+        svec!["h1", "(NULL)", "1", "16.67", "66.67"],
+        svec!["h1", "(NULL)", "1", "16.67", "83.33"],
+        svec!["h1", "a", "3", "50.00", "50.00"],
+        svec!["h1", "b", "1", "16.67", "100.00"],
     ];
     assert_eq!(got, expected);
 }
@@ -92,9 +104,17 @@ fn frequency_limit() {
     let mut got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
     got.sort();
     let expected = vec![
-        svec!["field", "value", "count"],
-        svec!["h1", "a", "3"],
-        svec!["h2", "z", "3"],
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage"
+        ],
+        svec!["h1", "Other (3)", "3", "50.00", "100.00"],
+        svec!["h1", "a", "3", "50.00", "50.00"],
+        svec!["h2", "Other (2)", "3", "50.00", "100.00"],
+        svec!["h2", "z", "3", "50.00", "50.00"],
     ];
     assert_eq!(got, expected);
 }
@@ -108,7 +128,17 @@ fn frequency_asc() {
 
     let mut got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
     got.sort();
-    let expected = vec![svec!["field", "value", "count"], svec!["h2", "x", "1"]];
+    let expected = vec![
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage"
+        ],
+        svec!["h2", "Other (2)", "5", "83.33", "100.00"],
+        svec!["h2", "x", "1", "16.67", "16.67"],
+    ];
     assert_eq!(got, expected);
 }
 
@@ -120,10 +150,39 @@ fn frequency_select() {
     let mut got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
     got.sort();
     let expected = vec![
-        svec!["field", "value", "count"],
-        svec!["h2", "x", "1"],
-        svec!["h2", "y", "2"],
-        svec!["h2", "z", "3"],
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage"
+        ],
+        svec!["h2", "x", "1", "16.67", "100.00"],
+        svec!["h2", "y", "2", "33.33", "83.33"],
+        svec!["h2", "z", "3", "50.00", "50.00"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_pct_limit() {
+    let (wrk, mut cmd) = setup("frequency_pct_limit");
+    cmd.args(["--pct-limit", "30"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage"
+        ],
+        svec!["h1", "a", "3", "50.00", "50.00"],
+        svec!["h1", "Other (3)", "3", "50.00", "100.00"],
+        svec!["h2", "z", "3", "50.00", "50.00"],
+        svec!["h2", "y", "2", "33.33", "83.33"],
+        svec!["h2", "Other (1)", "1", "16.67", "100.00"],
     ];
     assert_eq!(got, expected);
 }
@@ -265,3 +324,158 @@ fn assert_eq_ftables(got: &FTables, expected: &FTables) -> bool {
     }
     true
 }
+
+#[test]
+fn frequency_all_unique() {
+    let wrk = Workdir::new("frequency_all_unique");
+    let rows = vec![
+        svec!["id", "h"],
+        svec!["1", "a"],
+        svec!["2", "a"],
+        svec!["3", "b"],
+    ];
+    wrk.create("in.csv", rows);
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--cardinality", "--cache"]).arg("in.csv");
+    wrk.run(&mut cmd);
+
+    // the stats cache doesn't change the output by default
+    let mut cmd = wrk.command("frequency");
+    cmd.args(["--select", "id"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage"
+        ],
+        svec!["id", "1", "1", "33.33", "33.33"],
+        svec!["id", "2", "1", "33.33", "66.67"],
+        svec!["id", "3", "1", "33.33", "100.00"],
+    ];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("frequency");
+    cmd.arg("--all-unique").arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage"
+        ],
+        svec!["id", "<ALL_UNIQUE>", "3", "100.00", "100.00"],
+        svec!["h", "a", "2", "66.67", "66.67"],
+        svec!["h", "b", "1", "33.33", "100.00"],
+    ];
+    assert_eq!(got, expected);
+
+    // all the values are listed without a limit
+    let mut cmd = wrk.command("frequency");
+    cmd.args(["--all-unique", "--limit", "0", "--select", "id"])
+        .arg("in.csv");
+    let mut got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    got.sort();
+    let expected = vec![
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage"
+        ],
+        svec!["id", "1", "1", "33.33", "33.33"],
+        svec!["id", "2", "1", "33.33", "66.67"],
+        svec!["id", "3", "1", "33.33", "100.00"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_approx() {
+    let (wrk, mut cmd) = setup("frequency_approx");
+    cmd.arg("--approx").args(["--limit", "2"]);
+
+    // with enough counters, the counts are exact
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec![
+            "field",
+            "value",
+            "count",
+            "percentage",
+            "cumulative_percentage",
+            "error"
+        ],
+        svec!["h1", "a", "3", "50.00", "50.00", "0"],
+        svec!["h1", "(NULL)", "1", "16.67", "66.67", "0"],
+        svec!["h1", "Other", "2", "33.33", "100.00", "0"],
+        svec!["h2", "z", "3", "50.00", "50.00", "0"],
+        svec!["h2", "y", "2", "33.33", "83.33", "0"],
+        svec!["h2", "Other", "1", "16.67", "100.00", "0"],
+    ];
+    assert_eq!(got, expected);
+}
+
+fn approx_rows() -> Vec<Vec<String>> {
+    let mut rows = vec![svec!["h"]];
+    for i in 0..300 {
+        rows.push(vec![format!("rare{}", i)]);
+        if i % 2 == 0 {
+            rows.push(svec!["frequent"]);
+        }
+        if i % 5 == 0 {
+            rows.push(svec!["common"]);
+        }
+    }
+    rows
+}
+
+fn assert_approx_bounds(got: &[Vec<String>]) {
+    assert_eq!(got[1][1], "frequent");
+    assert_eq!(got[2][1], "common");
+    assert_eq!(got[3][1], "Other");
+    for (row, actual) in got[1..].iter().zip([150, 60, 300]) {
+        let count: u64 = row[2].parse().unwrap();
+        let error: u64 = row[5].parse().unwrap();
+        assert!(count >= actual && count - error <= actual, "{:?}", row);
+    }
+}
+
+#[test]
+fn frequency_approx_bounded() {
+    let wrk = Workdir::new("frequency_approx_bounded");
+    wrk.create("in.csv", approx_rows());
+    let mut cmd = wrk.command("frequency");
+    cmd.args(["--approx", "--limit", "2", "--counters", "10"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got.len(), 4);
+    assert_approx_bounds(&got);
+}
+
+#[test]
+fn frequency_approx_indexed() {
+    let wrk = Workdir::new("frequency_approx_indexed");
+    wrk.create_indexed("in.csv", approx_rows());
+    let mut cmd = wrk.command("frequency");
+    cmd.args(["--approx", "--limit", "2", "--counters", "10"])
+        .args(["--jobs", "4"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got.len(), 4);
+    assert_approx_bounds(&got);
+}
+
+#[test]
+fn frequency_approx_no_limit() {
+    let (wrk, mut cmd) = setup("frequency_approx_no_limit");
+    cmd.arg("--approx").args(["--limit", "0"]);
+    wrk.assert_err(&mut cmd);
+}
@@ -0,0 +1,107 @@
+use crate::workdir::Workdir;
+
+fn setup(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["name", "n", "x"],
+            svec!["a", "1", "0.5"],
+            svec!["b", "2", "1.5"],
+            svec!["c", "2", "2.5"],
+            svec!["d", "3", "10"],
+            svec!["e", "10", "100"],
+            svec!["f", "", "3"],
+        ],
+    );
+    wrk
+}
+
+#[test]
+fn histogram_equal_width() {
+    let wrk = setup("histogram_equal_width");
+    let mut cmd = wrk.command("histogram");
+    cmd.args(["--bins", "3", "--select", "name,n"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "bin_start", "bin_end", "count", "percent"],
+        svec!["n", "1", "4", "4", "80.00"],
+        svec!["n", "4", "7", "0", "0.00"],
+        svec!["n", "7", "10", "1", "20.00"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn histogram_quantiles() {
+    let wrk = setup("histogram_quantiles");
+    let mut cmd = wrk.command("histogram");
+    cmd.args(["--bins", "4", "--quantiles", "--select", "x"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "bin_start", "bin_end", "count", "percent"],
+        svec!["x", "0.5", "1.5", "1", "16.67"],
+        svec!["x", "1.5", "3", "2", "33.33"],
+        svec!["x", "3", "10", "1", "16.67"],
+        svec!["x", "10", "100", "2", "33.33"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn histogram_edges() {
+    let wrk = setup("histogram_edges");
+    let mut cmd = wrk.command("histogram");
+    cmd.args(["--edges", "0,2,5"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "bin_start", "bin_end", "count", "percent"],
+        svec!["n", "0", "2", "1", "20.00"],
+        svec!["n", "2", "5", "3", "60.00"],
+        svec!["x", "0", "2", "2", "33.33"],
+        svec!["x", "2", "5", "2", "33.33"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn histogram_log() {
+    let wrk = setup("histogram_log");
+    let mut cmd = wrk.command("histogram");
+    cmd.args(["--bins", "2", "--log", "--select", "n"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "bin_start", "bin_end", "count", "percent"],
+        svec!["n", "1", "3.1622776601683795", "4", "80.00"],
+        svec!["n", "3.1622776601683795", "10", "1", "20.00"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn histogram_sparkline() {
+    let wrk = setup("histogram_sparkline");
+    let mut cmd = wrk.command("histogram");
+    cmd.args(["--bins", "3", "--sparkline", "--select", "n"])
+        .arg("in.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "field  min  max  histogram\nn      1    10   █ ▂";
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn histogram_invalid_edges() {
+    let wrk = setup("histogram_invalid_edges");
+    let mut cmd = wrk.command("histogram");
+    cmd.args(["--edges", "5,2"]).arg("in.csv");
+
+    wrk.assert_err(&mut cmd);
+}
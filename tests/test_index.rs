@@ -7,8 +7,37 @@ use crate::workdir::Workdir;
 #[test]
 fn index_outdated() {
     let wrk = Workdir::new("index_outdated");
+    wrk.create_indexed("in.csv", vec![svec!["a"], svec!["b"]]);
+    wrk.create("in.csv", vec![svec!["a"], svec!["b"], svec!["c"]]);
+
+    let mut cmd = wrk.command("count");
+    cmd.arg("--no-headers").arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    let got = wrk.output_stderr(&mut cmd);
+    assert!(got.contains("the CSV file size changed from 4 to 6 bytes"));
+    assert!(got.contains("Please re-create the index."));
+}
+
+#[test]
+fn index_outdated_same_size() {
+    let wrk = Workdir::new("index_outdated_same_size");
+    wrk.create_indexed("in.csv", vec![svec!["a"], svec!["b"]]);
+    wrk.create("in.csv", vec![svec!["c"], svec!["d"]]);
+
+    let mut cmd = wrk.command("count");
+    cmd.arg("--no-headers").arg("in.csv");
+    let got = wrk.output_stderr(&mut cmd);
+    assert!(got.contains("the start of the CSV file changed"));
+}
+
+#[test]
+fn index_touched() {
+    let wrk = Workdir::new("index_touched");
     wrk.create_indexed("in.csv", vec![svec![""]]);
 
+    // only the modification time changes, e.g. after a git checkout,
+    // so the index is still valid
     let md = fs::metadata(&wrk.path("in.csv.idx")).unwrap();
     set_file_times(
         &wrk.path("in.csv"),
@@ -19,10 +48,167 @@ fn index_outdated() {
 
     let mut cmd = wrk.command("count");
     cmd.arg("--no-headers").arg("in.csv");
-    wrk.assert_err(&mut cmd);
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "1");
+}
+
+#[test]
+fn index_other_delimiter() {
+    let wrk = Workdir::new("index_other_delimiter");
+    wrk.create_indexed("in.csv", vec![svec!["h1", "h2"], svec!["a", "b"]]);
+
+    let mut cmd = wrk.command("count");
+    cmd.args(["--delimiter", ";"]).arg("in.csv");
+    let got = wrk.output_stderr(&mut cmd);
+    assert!(got.contains("it was created with the delimiter ',', not ';'"));
+}
+
+#[test]
+fn index_without_header() {
+    let wrk = Workdir::new("index_without_header");
+    wrk.create("in.csv", vec![svec!["h1"], svec!["a"]]);
+    // an index as written by older versions of qsv, with only the offsets
+    // followed by their count
+    let offsets: Vec<u8> = [0u64, 3, 2].iter().flat_map(|n| n.to_be_bytes()).collect();
+    fs::write(wrk.path("in.csv.idx"), offsets).unwrap();
+
+    let mut cmd = wrk.command("count");
+    cmd.arg("in.csv");
+    let got = wrk.output_stderr(&mut cmd);
+    assert!(got.contains("it has no header, so it was likely created by an older version of qsv"));
 }
 
 fn future_time(ft: FileTime) -> FileTime {
     let secs = ft.unix_seconds();
     FileTime::from_unix_time(secs + 10_000, 0)
 }
+
+#[test]
+fn index_auto() {
+    let wrk = Workdir::new("index_auto");
+    wrk.create("in.csv", vec![svec!["h1"], svec!["a"], svec!["b"]]);
+
+    let mut cmd = wrk.command("count");
+    cmd.env("QSV_AUTOINDEX_SIZE", "5").arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "2");
+    assert!(wrk.path("in.csv.idx").exists());
+
+    let mut cmd = wrk.command("slice");
+    cmd.args(["--start", "1"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["b"]]);
+}
+
+#[test]
+fn index_auto_below_size() {
+    let wrk = Workdir::new("index_auto_below_size");
+    wrk.create("in.csv", vec![svec!["h1"], svec!["a"], svec!["b"]]);
+
+    let mut cmd = wrk.command("count");
+    cmd.env("QSV_AUTOINDEX_SIZE", "1000").arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "2");
+    assert!(!wrk.path("in.csv.idx").exists());
+}
+
+#[test]
+fn index_auto_rebuilds_outdated() {
+    let wrk = Workdir::new("index_auto_rebuilds_outdated");
+    wrk.create_indexed("in.csv", vec![svec!["a"], svec!["b"]]);
+    wrk.create("in.csv", vec![svec!["a"], svec!["b"], svec!["c"]]);
+
+    // stale indexes are rebuilt, whatever the size of the file
+    let mut cmd = wrk.command("count");
+    cmd.env("QSV_AUTOINDEX_SIZE", "1000")
+        .arg("--no-headers")
+        .arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "3");
+
+    let mut cmd = wrk.command("count");
+    cmd.arg("--no-headers").arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "3");
+}
+
+#[test]
+fn index_column() {
+    let wrk = Workdir::new("index_column");
+    wrk.create("in.csv", vec![svec!["h1", "h2"], svec!["a", "b"]]);
+
+    let mut cmd = wrk.command("index");
+    cmd.args(["--column", "h2"]).arg("in.csv");
+    wrk.run(&mut cmd);
+    assert!(wrk.path("in.csv.kidx").exists());
+    assert!(!wrk.path("in.csv.idx").exists());
+
+    let mut cmd = wrk.command("index");
+    cmd.args(["--column", "h3"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+/// Run `qsv index --append` and check that the updated index is the same as
+/// a re-created one.
+fn assert_index_append(wrk: &Workdir) {
+    let mut cmd = wrk.command("index");
+    cmd.arg("--append").arg("in.csv");
+    wrk.run(&mut cmd);
+    let appended = fs::read(wrk.path("in.csv.idx")).unwrap();
+
+    let mut cmd = wrk.command("index");
+    cmd.arg("in.csv");
+    wrk.run(&mut cmd);
+    let created = fs::read(wrk.path("in.csv.idx")).unwrap();
+    assert_eq!(appended, created);
+}
+
+#[test]
+fn index_append() {
+    let wrk = Workdir::new("index_append");
+    wrk.create_indexed("in.csv", vec![svec!["h1"], svec!["a"], svec!["b"]]);
+    wrk.create_from_string("in.csv", "h1\na\nb\nc\nd\n");
+    assert_index_append(&wrk);
+
+    let mut cmd = wrk.command("slice");
+    cmd.args(["--start", "-1"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["h1"], svec!["d"]]);
+}
+
+#[test]
+fn index_append_incomplete_row() {
+    let wrk = Workdir::new("index_append_incomplete_row");
+    wrk.create_from_string("in.csv", "h1\na\nb");
+    let mut cmd = wrk.command("index");
+    cmd.arg("in.csv");
+    wrk.run(&mut cmd);
+
+    // the last row was still being written when it was indexed
+    wrk.create_from_string("in.csv", "h1\na\nbc\nd\n");
+    assert_index_append(&wrk);
+
+    let mut cmd = wrk.command("count");
+    cmd.arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "3");
+}
+
+#[test]
+fn index_append_changed() {
+    let wrk = Workdir::new("index_append_changed");
+    wrk.create_indexed("in.csv", vec![svec!["h1"], svec!["a"], svec!["b"]]);
+    // not only appended to, so the index is re-created
+    wrk.create_from_string("in.csv", "h1\nx\nb\nc\n");
+    assert_index_append(&wrk);
+}
+
+#[test]
+fn index_append_column() {
+    let wrk = Workdir::new("index_append_column");
+    wrk.create("in.csv", vec![svec!["h1"], svec!["a"]]);
+
+    let mut cmd = wrk.command("index");
+    cmd.args(["--append", "--column", "h1"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
@@ -0,0 +1,109 @@
+use crate::workdir::Workdir;
+
+#[test]
+fn input_multi_byte_delimiter_terminator() {
+    let wrk = Workdir::new("input_multi_byte_delimiter_terminator");
+    wrk.create_from_string("in.txt", "h1~|~h2~~a~|~b\nc~~d~|~e~~");
+
+    let mut cmd = wrk.command("input");
+    cmd.args(["--delimiter", "~|~"])
+        .args(["--terminator", "~~"])
+        .arg("in.txt");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h1", "h2"], svec!["a", "b\nc"], svec!["d", "e"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn input_multi_byte_quote_error() {
+    let wrk = Workdir::new("input_multi_byte_quote_error");
+    wrk.create_from_string("in.csv", "h1,h2\na,b\n");
+
+    let mut cmd = wrk.command("input");
+    cmd.args(["--quote", "''"]).arg("in.csv");
+
+    let got: String = wrk.output_stderr(&mut cmd);
+    assert_eq!(
+        got,
+        "The quote and escape characters must be a single ASCII character.\n"
+    );
+}
+
+#[test]
+fn input_encoding() {
+    let wrk = Workdir::new("input_encoding");
+    std::fs::write(wrk.path("in.csv"), b"name,n\ncaf\xe9,1\nna\xefve,2\n").unwrap();
+
+    let mut cmd = wrk.command("input");
+    cmd.args(["--encoding", "windows-1252"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["name", "n"], svec!["café", "1"], svec!["naïve", "2"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn input_encoding_auto() {
+    let wrk = Workdir::new("input_encoding_auto");
+    std::fs::write(
+        wrk.path("in.csv"),
+        b"name,city\nJos\xe9,M\xfcnchen\nFran\xe7ois,Besan\xe7on\nRen\xe9e,G\xf6teborg\n",
+    )
+    .unwrap();
+
+    let mut cmd = wrk.command("input");
+    cmd.args(["--encoding", "auto"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "city"],
+        svec!["José", "München"],
+        svec!["François", "Besançon"],
+        svec!["Renée", "Göteborg"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn input_encoding_env() {
+    let wrk = Workdir::new("input_encoding_env");
+    std::fs::write(wrk.path("in.csv"), b"name,n\ncaf\xe9,1\nna\xefve,2\n").unwrap();
+
+    let mut cmd = wrk.command("stats");
+    cmd.env("QSV_INPUT_ENCODING", "windows-1252")
+        .args(["--select", "name"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][1], "String");
+    assert_eq!(got[1][3], "café");
+    assert_eq!(got[1][4], "naïve");
+}
+
+#[test]
+fn input_encoding_unknown() {
+    let wrk = Workdir::new("input_encoding_unknown");
+    wrk.create_from_string("in.csv", "h1,h2\na,b\n");
+
+    let mut cmd = wrk.command("input");
+    cmd.args(["--encoding", "klingon"]).arg("in.csv");
+
+    let got: String = wrk.output_stderr(&mut cmd);
+    assert_eq!(got, "Unknown character encoding 'klingon'.\n");
+}
+
+#[test]
+fn input_multi_byte_placeholder_error() {
+    let wrk = Workdir::new("input_multi_byte_placeholder_error");
+    wrk.create_from_string("in.txt", "h1||h2\na\x1fb||c\n");
+
+    let mut cmd = wrk.command("input");
+    cmd.args(["--delimiter", "||"]).arg("in.txt");
+
+    let got: String = wrk.output_stderr(&mut cmd);
+    assert!(
+        got.contains("contains the byte 0x1f outside of quotes"),
+        "{got}"
+    );
+}
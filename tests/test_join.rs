@@ -168,6 +168,111 @@ join_test!(join_left_anti, |wrk: Workdir,
     assert_eq!(got, expected);
 });
 
+fn create_key_index(wrk: &Workdir, headers: bool) {
+    let mut cmd = wrk.command("index");
+    if headers {
+        cmd.args(["--column", "city"]);
+    } else {
+        cmd.args(["--column", "1", "--no-headers"]);
+    }
+    cmd.arg("places.csv");
+    wrk.run(&mut cmd);
+}
+
+join_test!(
+    join_inner_use_index,
+    |wrk: Workdir, mut cmd: process::Command, headers: bool| {
+        super::create_key_index(&wrk, headers);
+        cmd.arg("--use-index");
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        let expected = make_rows(
+            headers,
+            false,
+            vec![
+                svec!["Boston", "MA", "Boston", "Logan Airport"],
+                svec!["Boston", "MA", "Boston", "Boston Garden"],
+                svec!["Buffalo", "NY", "Buffalo", "Ralph Wilson Stadium"],
+            ],
+        );
+        assert_eq!(got, expected);
+    }
+);
+
+join_test!(
+    join_outer_left_use_index,
+    |wrk: Workdir, mut cmd: process::Command, headers: bool| {
+        super::create_key_index(&wrk, headers);
+        cmd.arg("--left").arg("--use-index");
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        let expected = make_rows(
+            headers,
+            false,
+            vec![
+                svec!["Boston", "MA", "Boston", "Logan Airport"],
+                svec!["Boston", "MA", "Boston", "Boston Garden"],
+                svec!["New York", "NY", "", ""],
+                svec!["San Francisco", "CA", "", ""],
+                svec!["Buffalo", "NY", "Buffalo", "Ralph Wilson Stadium"],
+            ],
+        );
+        assert_eq!(got, expected);
+    }
+);
+
+join_test!(
+    join_left_semi_use_index,
+    |wrk: Workdir, mut cmd: process::Command, headers: bool| {
+        super::create_key_index(&wrk, headers);
+        cmd.arg("--left-semi").arg("--use-index");
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        let expected = make_rows(headers, true, vec![svec!["Buffalo", "NY"]]);
+        assert_eq!(got, expected);
+    }
+);
+
+join_test!(
+    join_left_anti_use_index,
+    |wrk: Workdir, mut cmd: process::Command, headers: bool| {
+        super::create_key_index(&wrk, headers);
+        cmd.arg("--left-anti").arg("--use-index");
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        let expected = make_rows(
+            headers,
+            true,
+            vec![svec!["New York", "NY"], svec!["San Francisco", "CA"]],
+        );
+        assert_eq!(got, expected);
+    }
+);
+
+#[test]
+fn join_use_index_errors() {
+    let wrk = setup("join_use_index_errors", true);
+
+    create_key_index(&wrk, true);
+
+    // there's no key index for cities.csv
+    let mut cmd = wrk.command("join");
+    cmd.args(["--use-index", "city", "places.csv", "city", "cities.csv"]);
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("join");
+    cmd.args([
+        "--use-index",
+        "--right",
+        "city",
+        "cities.csv",
+        "city",
+        "places.csv",
+    ]);
+    wrk.assert_err(&mut cmd);
+
+    // the key index is not on the join columns
+    let mut cmd = wrk.command("join");
+    cmd.args(["--use-index", "city", "cities.csv", "place", "places.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
 #[test]
 fn join_inner_issue11() {
     let a = vec![svec!["1", "2"], svec!["3", "4"], svec!["5", "6"]];
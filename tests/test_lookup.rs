@@ -0,0 +1,115 @@
+use crate::workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["id", "country", "city"],
+        svec!["1", "US", "Boston"],
+        svec!["2", "US", "New York"],
+        svec!["3", "FR", "Paris"],
+        svec!["4", "us", "Boston"],
+        svec!["5", "FR", "Lyon"],
+    ]
+}
+
+#[test]
+fn lookup() {
+    let wrk = Workdir::new("lookup");
+    wrk.create("data.csv", data());
+    let mut cmd = wrk.command("index");
+    cmd.args(["--column", "country"]).arg("data.csv");
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("lookup");
+    cmd.arg("data.csv").args(["FR", "XX", "US"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "country", "city"],
+        svec!["3", "FR", "Paris"],
+        svec!["5", "FR", "Lyon"],
+        svec!["1", "US", "Boston"],
+        svec!["2", "US", "New York"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn lookup_ignore_case() {
+    let wrk = Workdir::new("lookup_ignore_case");
+    wrk.create("data.csv", data());
+    let mut cmd = wrk.command("index");
+    cmd.args(["--column", "country"]).arg("data.csv");
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("lookup");
+    cmd.arg("--ignore-case").arg("data.csv").arg(" us ");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "country", "city"],
+        svec!["1", "US", "Boston"],
+        svec!["2", "US", "New York"],
+        svec!["4", "us", "Boston"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn lookup_multiple_columns() {
+    let wrk = Workdir::new("lookup_multiple_columns");
+    wrk.create("data.csv", data());
+    let mut cmd = wrk.command("index");
+    cmd.args(["--column", "country,city"]).arg("data.csv");
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("lookup");
+    cmd.arg("data.csv").arg("US,Boston");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["id", "country", "city"], svec!["1", "US", "Boston"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn lookup_no_headers() {
+    let wrk = Workdir::new("lookup_no_headers");
+    wrk.create("data.csv", data());
+    let mut cmd = wrk.command("index");
+    cmd.args(["--column", "2", "--no-headers"]).arg("data.csv");
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("lookup");
+    cmd.arg("--no-headers").arg("data.csv").arg("country");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["id", "country", "city"]];
+    assert_eq!(got, expected);
+
+    // the key index includes the header row, so it can't be used with headers
+    let mut cmd = wrk.command("lookup");
+    cmd.arg("data.csv").arg("FR");
+    let got = wrk.output_stderr(&mut cmd);
+    assert!(got.contains("it was created without a header row"));
+}
+
+#[test]
+fn lookup_no_index() {
+    let wrk = Workdir::new("lookup_no_index");
+    wrk.create("data.csv", data());
+
+    let mut cmd = wrk.command("lookup");
+    cmd.arg("data.csv").arg("FR");
+    let got = wrk.output_stderr(&mut cmd);
+    assert!(got.contains("No key index found for data.csv"));
+}
+
+#[test]
+fn lookup_outdated() {
+    let wrk = Workdir::new("lookup_outdated");
+    wrk.create("data.csv", data());
+    let mut cmd = wrk.command("index");
+    cmd.args(["--column", "country"]).arg("data.csv");
+    wrk.run(&mut cmd);
+    wrk.create("data.csv", vec![svec!["id", "country", "city"]]);
+
+    let mut cmd = wrk.command("lookup");
+    cmd.arg("data.csv").arg("FR");
+    let got = wrk.output_stderr(&mut cmd);
+    assert!(got.contains("Please re-create the key index."));
+}
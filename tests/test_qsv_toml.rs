@@ -0,0 +1,133 @@
+use crate::workdir::Workdir;
+
+fn data() -> &'static str {
+    "h1;h2\na;1\nb;2\n"
+}
+
+#[test]
+fn qsv_toml_env() {
+    let wrk = Workdir::new("qsv_toml_env");
+    wrk.create_from_string("in.txt", data());
+    wrk.create_from_string("qsv.toml", "[env]\nQSV_DEFAULT_DELIMITER = \";\"\n");
+
+    let mut cmd = wrk.command("select");
+    cmd.env("XDG_CONFIG_HOME", wrk.path("config"))
+        .arg("h2")
+        .arg("in.txt");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h2"], svec!["1"], svec!["2"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn qsv_toml_env_var_precedence() {
+    let wrk = Workdir::new("qsv_toml_env_var_precedence");
+    wrk.create_from_string("in.txt", data());
+    wrk.create_from_string("qsv.toml", "[env]\nQSV_DEFAULT_DELIMITER = \"|\"\n");
+
+    let mut cmd = wrk.command("select");
+    cmd.env("XDG_CONFIG_HOME", wrk.path("config"))
+        .env("QSV_DEFAULT_DELIMITER", ";")
+        .arg("h2")
+        .arg("in.txt");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h2"], svec!["1"], svec!["2"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn qsv_toml_command_defaults() {
+    let wrk = Workdir::new("qsv_toml_command_defaults");
+    wrk.create_from_string("in.txt", data());
+    wrk.create_from_string(
+        "qsv.toml",
+        "[commands.select]\ndelimiter = \";\"\nno-headers = true\n",
+    );
+
+    let mut cmd = wrk.command("select");
+    cmd.env("XDG_CONFIG_HOME", wrk.path("config"))
+        .arg("2")
+        .arg("in.txt");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h2"], svec!["1"], svec!["2"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn qsv_toml_command_defaults_flag_precedence() {
+    let wrk = Workdir::new("qsv_toml_command_defaults_flag_precedence");
+    wrk.create_from_string("in.txt", "h1|h2\na|1\nb|2\n");
+    wrk.create_from_string("qsv.toml", "[commands.select]\ndelimiter = \";\"\n");
+
+    let mut cmd = wrk.command("select");
+    cmd.env("XDG_CONFIG_HOME", wrk.path("config"))
+        .args(["-d", "|"])
+        .arg("h2")
+        .arg("in.txt");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h2"], svec!["1"], svec!["2"]];
+    assert_eq!(got, expected);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn qsv_toml_project_over_user() {
+    let wrk = Workdir::new("qsv_toml_project_over_user");
+    wrk.create_from_string("in.txt", data());
+    wrk.create_from_string("qsv.toml", "[env]\nQSV_DEFAULT_DELIMITER = \";\"\n");
+    std::fs::create_dir_all(wrk.path("config/qsv")).unwrap();
+    wrk.create_from_string(
+        "config/qsv/qsv.toml",
+        "[env]\nQSV_DEFAULT_DELIMITER = \"|\"\n\n[commands.select]\nno-headers = true\n",
+    );
+
+    let mut cmd = wrk.command("select");
+    cmd.env("XDG_CONFIG_HOME", wrk.path("config"))
+        .arg("2")
+        .arg("in.txt");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h2"], svec!["1"], svec!["2"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn qsv_toml_envlist() {
+    let wrk = Workdir::new("qsv_toml_envlist");
+    wrk.create_from_string(
+        "qsv.toml",
+        "[env]\nQSV_COMMENT_CHAR = \"#\"\n\n[commands.stats]\neverything = true\n",
+    );
+
+    let mut cmd = wrk.command("");
+    cmd.env("XDG_CONFIG_HOME", wrk.path("config"))
+        .arg("--envlist");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let qsv_toml = wrk.path("qsv.toml").display().to_string();
+    assert!(got.contains(&format!("QSV_COMMENT_CHAR: \"#\" ({qsv_toml})")));
+    assert!(got.contains(&format!("stats --everything: true ({qsv_toml})")));
+}
+
+#[test]
+fn qsv_toml_invalid_env_var() {
+    let wrk = Workdir::new("qsv_toml_invalid_env_var");
+    wrk.create_from_string("in.txt", data());
+    wrk.create_from_string("qsv.toml", "[env]\nPATH = \"/tmp\"\n");
+
+    let mut cmd = wrk.command("count");
+    cmd.env("XDG_CONFIG_HOME", wrk.path("config")).arg("in.txt");
+
+    let got: String = wrk.output_stderr(&mut cmd);
+    assert_eq!(
+        got,
+        format!(
+            "Invalid {}: PATH is not a qsv environment variable.\n",
+            wrk.path("qsv.toml").display()
+        )
+    );
+}
@@ -202,3 +202,28 @@ fn search_flag_invert_match() {
     ];
     assert_eq!(got, expected);
 }
+
+#[test]
+fn search_use_index() {
+    let wrk = Workdir::new("search_use_index");
+    wrk.create("data.csv", data(true));
+    let mut cmd = wrk.command("index");
+    cmd.args(["--column", "h2"]).arg("data.csv");
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("search");
+    cmd.arg("--use-index")
+        .args(["--select", "h2"])
+        .arg("foobar")
+        .arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h1", "h2"], svec!["barfoo", "foobar"]];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("search");
+    cmd.arg("--use-index")
+        .args(["--select", "h1"])
+        .arg("foobar")
+        .arg("data.csv");
+    wrk.assert_err(&mut cmd);
+}
@@ -184,3 +184,200 @@ select_test_err!(select_err_regex_nomatch, "/nomatch/");
 select_test_err!(select_err_regex_invalid, "/?/");
 select_test_err!(select_err_regex_empty, "//");
 select_test_err!(select_err_regex_triple_slash, "///");
+
+select_test!(
+    select_glob,
+    "h?",
+    "1,2,4,5",
+    ["h1", "h2", "h4", "h1"],
+    ["a", "b", "d", "e"]
+);
+select_test!(select_last, "_", "_", ["h1"], ["e"]);
+select_test!(select_from_last, "_-1", "_-1", ["h4"], ["d"]);
+select_test!(
+    select_range_from_last,
+    "3-_-1",
+    "3-_-1",
+    ["h[]3", "h4"],
+    ["c", "d"]
+);
+select_test!(
+    select_exclude,
+    "/^h/,!h2",
+    "1-5,!2",
+    ["h1", "h[]3", "h4", "h1"],
+    ["a", "c", "d", "e"]
+);
+select_test!(select_exclude_regex, "h4-_,!/1/", "4-5,!/e/", ["h4"], ["d"]);
+select_test!(
+    select_regex_exclude_regex,
+    "/^h/,!/[14]/",
+    "/[a-e]/,!/[ade]/",
+    ["h2", "h[]3"],
+    ["b", "c"]
+);
+select_test!(
+    select_exclude_only,
+    "!h1[0],!h1[1]",
+    "!1,!5",
+    ["h2", "h[]3", "h4"],
+    ["b", "c", "d"]
+);
+select_test!(
+    select_alias,
+    "h4 as four,h2",
+    "4 as four,2",
+    ["four", "h2"],
+    ["d", "b"]
+);
+
+select_test_err!(select_err_glob_nomatch, "x*");
+select_test_err!(select_err_from_last_oob, "_-5");
+select_test_err!(select_err_alias_many_columns, "h1-h2 as x");
+select_test_err!(select_err_alias_excluded, "h1,!h2 as x");
+select_test_err!(select_err_alias_empty, "h1 as ");
+
+#[test]
+fn select_exact_names() {
+    let wrk = Workdir::new("select_exact_names");
+    wrk.create(
+        "data.csv",
+        vec![
+            svec!["Price as Reported", "Total*", ":numeric", "Price"],
+            svec!["1", "2", "3", "4"],
+        ],
+    );
+
+    // headers that look like aliases, patterns or types are selected by name
+    let mut cmd = wrk.command("select");
+    cmd.arg("Total*,:numeric,Price as Reported").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["Total*", ":numeric", "Price as Reported"],
+        svec!["2", "3", "1"],
+    ];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("!Price as Reported").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["Total*", ":numeric", "Price"], svec!["2", "3", "4"]];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("Price as Cost,Tot*").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["Cost", "Total*"], svec!["4", "2"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn select_types_other_command() {
+    let wrk = Workdir::new("select_types_other_command");
+    wrk.create("data.csv", typed_data());
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--select", ":numeric", "--no-cache"])
+        .arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let fields: Vec<&str> = got.iter().skip(1).map(|row| &*row[0]).collect();
+    assert_eq!(fields, vec!["id", "price"]);
+
+    let mut cmd = wrk.command("frequency");
+    cmd.args(["--select", ":string"]).arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let fields: Vec<&str> = got.iter().skip(1).map(|row| &*row[0]).collect();
+    assert_eq!(fields, vec!["name", "name"]);
+}
+
+fn typed_data() -> Vec<Vec<String>> {
+    vec![
+        svec!["id", "name", "price", "sold"],
+        svec!["1", "apple", "1.5", "2022-01-01"],
+        svec!["2", "pear", "2", "2022-02-01"],
+    ]
+}
+
+#[test]
+fn select_types() {
+    let wrk = Workdir::new("select_types");
+    wrk.create("data.csv", typed_data());
+
+    let mut cmd = wrk.command("select");
+    cmd.arg(":numeric").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["id", "price"], svec!["1", "1.5"], svec!["2", "2"]];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("name,:date as date,:float").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "date", "price"],
+        svec!["apple", "2022-01-01", "1.5"],
+        svec!["pear", "2022-02-01", "2"],
+    ];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("select");
+    cmd.arg("!:numeric").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "sold"],
+        svec!["apple", "2022-01-01"],
+        svec!["pear", "2022-02-01"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn select_types_stdin() {
+    let wrk = Workdir::new("select_types_stdin");
+    wrk.create("data.csv", typed_data());
+
+    let mut cmd = wrk.command("select");
+    cmd.arg(":numeric")
+        .stdin(std::fs::File::open(wrk.path("data.csv")).unwrap());
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn select_types_stats_cache() {
+    let wrk = Workdir::new("select_types_stats_cache");
+    wrk.create("data.csv", typed_data());
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--dates", "--cache"]).arg("data.csv");
+    wrk.run(&mut cmd);
+
+    // the types are read from the stats cache
+    let path = wrk.path("data.csv.stats.json");
+    let mut cache: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    cache["fields"][1]["type"] = serde_json::json!("Integer");
+    std::fs::write(&path, cache.to_string()).unwrap();
+
+    let mut cmd = wrk.command("select");
+    cmd.arg(":integer").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["id", "name"], svec!["1", "apple"], svec!["2", "pear"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn select_types_boolean() {
+    let wrk = Workdir::new("select_types_boolean");
+    wrk.create(
+        "data.csv",
+        vec![
+            svec!["id", "active", "flag"],
+            svec!["1", "Yes", "1"],
+            svec!["2", "no", "0"],
+        ],
+    );
+
+    let mut cmd = wrk.command("select");
+    cmd.arg(":boolean").arg("data.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["active", "flag"], svec!["Yes", "1"], svec!["no", "0"]];
+    assert_eq!(got, expected);
+}
@@ -108,3 +108,52 @@ fn qsv_sniff_tab_delimiter_env() {
     let got: String = wrk.stdout(&mut cmd);
     assert_eq!(&*got, EXPECTED_TABLE)
 }
+
+#[test]
+fn qsv_sniff_dialect_preamble_env() {
+    let wrk = Workdir::new("qsv_sniff_dialect_preamble_env");
+    wrk.create_from_string(
+        "in.file",
+        "Title line here\nname;age;city\nSmith;42;Boston\nDoe;37;New York\nBob;25;Austin\n",
+    );
+
+    let mut cmd = wrk.command("select");
+    cmd.env("QSV_SNIFF_DIALECT", "1");
+    cmd.arg("city,name").arg("in.file");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["city", "name"],
+        svec!["Boston", "Smith"],
+        svec!["New York", "Doe"],
+        svec!["Austin", "Bob"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn qsv_sniff_dialect_preamble_indexed_env() {
+    let wrk = Workdir::new("qsv_sniff_dialect_preamble_indexed_env");
+    wrk.create_from_string(
+        "in.file",
+        "Title line here\nname;age;city\nSmith;42;Boston\nDoe;37;New York\nBob;25;Austin\n",
+    );
+
+    let mut cmd = wrk.command("index");
+    cmd.env("QSV_SNIFF_DIALECT", "1");
+    cmd.arg("in.file");
+    wrk.run(&mut cmd);
+
+    let mut cmd = wrk.command("count");
+    cmd.env("QSV_SNIFF_DIALECT", "1");
+    cmd.arg("in.file");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "3");
+
+    let mut cmd = wrk.command("slice");
+    cmd.env("QSV_SNIFF_DIALECT", "1");
+    cmd.args(["--index", "1"]).arg("in.file");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["name", "age", "city"], svec!["Doe", "37", "New York"]];
+    assert_eq!(got, expected);
+}
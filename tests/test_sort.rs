@@ -88,6 +88,34 @@ fn sort_numeric() {
     assert_eq!(got, expected);
 }
 
+#[test]
+fn sort_numeric_locale() {
+    let wrk = Workdir::new("sort_numeric_locale");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["N"],
+            svec!["1.000"],
+            svec!["(2,5)"],
+            svec!["12"],
+            svec!["3,25"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sort");
+    cmd.env("QSV_NUMERIC_LOCALE", ".,").arg("-N").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["N"],
+        svec!["(2,5)"],
+        svec!["3,25"],
+        svec!["12"],
+        svec!["1.000"],
+    ];
+    assert_eq!(got, expected);
+}
+
 #[test]
 fn sort_numeric_non_natural() {
     let wrk = Workdir::new("sort_numeric_non_natural");
@@ -448,3 +448,41 @@ fn split_custom_filename_padded() {
     assert!(wrk.path("prefix-002.csv").exists());
     assert!(wrk.path("prefix-004.csv").exists());
 }
+
+#[test]
+fn split_stdin_spooled() {
+    let wrk = Workdir::new("split_stdin_spooled");
+    wrk.create("in.csv", data(true));
+    std::fs::create_dir_all(wrk.path("tmp")).unwrap();
+
+    // spool stdin to disk, so it's indexed and split in parallel
+    let mut cmd = wrk.command("split");
+    cmd.env("QSV_SPOOL_SIZE", "0")
+        .env("TMPDIR", wrk.path("tmp"))
+        .args(["--size", "2"])
+        .arg(wrk.path("out"))
+        .stdin(std::fs::File::open(wrk.path("in.csv")).unwrap());
+    wrk.run(&mut cmd);
+
+    split_eq!(
+        wrk,
+        "out/0.csv",
+        "\
+h1,h2
+a,b
+c,d
+"
+    );
+    split_eq!(
+        wrk,
+        "out/4.csv",
+        "\
+h1,h2
+i,j
+k,l
+"
+    );
+    assert!(!wrk.path("out/6.csv").exists());
+    // the spooled file and its index are removed on exit
+    assert_eq!(std::fs::read_dir(wrk.path("tmp")).unwrap().count(), 0);
+}
@@ -146,6 +146,24 @@ fn get_field_value(wrk: &Workdir, cmd: &mut process::Command, field: &str) -> St
     if field == "dates" {
         cmd.arg("--dates");
     }
+    if field == "skewness" || field == "kurtosis" {
+        cmd.arg("--moments");
+    }
+    if field == "mad" {
+        cmd.arg("--mad");
+    }
+    if field == "cv" {
+        cmd.arg("--cv");
+    }
+    if field == "geometric_mean" || field == "harmonic_mean" {
+        cmd.arg("--means");
+    }
+    if field == "zeros" || field == "negatives" {
+        cmd.arg("--signs");
+    }
+    if field == "sort_order" {
+        cmd.arg("--sort-order");
+    }
 
     let mut rows: Vec<Vec<String>> = wrk.read_stdout(cmd);
     let headers = rows.remove(0);
@@ -273,6 +291,66 @@ stats_tests!(
     "String"
 );
 
+mod stats_infer_bool_words {
+    use super::test_stats;
+    stats_test_headers!(
+        stats_infer_bool_words,
+        "type",
+        &["yes", "No", "", "Y"],
+        "Boolean"
+    );
+}
+
+mod stats_infer_bool_ints {
+    use super::test_stats;
+    stats_test_headers!(stats_infer_bool_ints, "type", &["1", "0", "1"], "Boolean");
+}
+
+mod stats_infer_bool_int_true {
+    use super::test_stats;
+    stats_test_headers!(stats_infer_bool_int_true, "type", &["1", "1"], "Integer");
+}
+
+mod stats_infer_bool_word_no {
+    use super::test_stats;
+    stats_test_headers!(stats_infer_bool_word_no, "type", &["No", "no"], "String");
+}
+
+mod stats_infer_bool_literal {
+    use super::test_stats;
+    stats_test_headers!(stats_infer_bool_literal, "type", &["true", ""], "Boolean");
+}
+
+mod stats_infer_bool_mixed {
+    use super::test_stats;
+    stats_test_headers!(
+        stats_infer_bool_mixed,
+        "type",
+        &["true", "0", "1"],
+        "Boolean"
+    );
+}
+
+mod stats_infer_bool_string {
+    use super::test_stats;
+    stats_test_headers!(
+        stats_infer_bool_string,
+        "type",
+        &["true", "maybe"],
+        "String"
+    );
+}
+
+mod stats_infer_bool_int {
+    use super::test_stats;
+    stats_test_headers!(stats_infer_bool_int, "type", &["false", "2"], "String");
+}
+
+mod stats_infer_bool_sum {
+    use super::test_stats;
+    stats_test_headers!(stats_infer_bool_sum, "sum", &["1", "0", "1"], "2");
+}
+
 stats_tests!(stats_no_mean, "mean", &["a"], "");
 stats_tests!(stats_no_stddev, "stddev", &["a"], "");
 stats_tests!(stats_no_variance, "variance", &["a"], "");
@@ -449,6 +527,69 @@ stats_tests!(
     "-1.5,1.5,2.5,3.5,1,4.5"
 );
 
+stats_tests!(
+    stats_skewness,
+    "skewness",
+    &["1", "2", "3", "10"],
+    "1.018233764"
+);
+stats_tests!(
+    stats_kurtosis,
+    "kurtosis",
+    &["1", "2", "3", "10"],
+    "-0.7696"
+);
+stats_tests!(stats_skewness_constant, "skewness", &["1", "1"], "");
+stats_tests!(stats_skewness_string, "skewness", &["1", "a"], "");
+stats_tests!(stats_mad, "mad", &["1", "2", "3", "4", "100"], "1");
+stats_tests!(stats_mad_even, "mad", &["1", "2", "3", "4"], "1");
+stats_tests!(
+    stats_cv,
+    "cv",
+    &["2", "4", "4", "4", "5", "5", "7", "9"],
+    "0.4"
+);
+stats_tests!(
+    stats_geometric_mean,
+    "geometric_mean",
+    &["1", "2", "4"],
+    "2"
+);
+stats_tests!(
+    stats_harmonic_mean,
+    "harmonic_mean",
+    &["1", "2", "4"],
+    "1.714285714"
+);
+stats_tests!(
+    stats_geometric_mean_non_positive,
+    "geometric_mean",
+    &["1", "0", "4"],
+    ""
+);
+stats_tests!(stats_zeros, "zeros", &["0", "1", "-1", "0.0", ""], "2");
+stats_tests!(stats_negatives, "negatives", &["0", "1", "-1", "-2.5"], "2");
+stats_tests!(stats_zeros_string, "zeros", &["0", "a"], "");
+stats_tests!(
+    stats_sort_order_numbers,
+    "sort_order",
+    &["1", "2", "10"],
+    "Ascending"
+);
+stats_tests!(
+    stats_sort_order_strings,
+    "sort_order",
+    &["b", "a", "", "a"],
+    "Descending"
+);
+stats_tests!(
+    stats_sort_order_unsorted,
+    "sort_order",
+    &["1", "3", "2"],
+    "Unsorted"
+);
+stats_tests!(stats_sort_order_null, "sort_order", &[""], "");
+
 stats_tests!(stats_nullcount, "nullcount", &["", "1", "2"], "1");
 stats_tests!(stats_nullcount_none, "nullcount", &["a", "1", "2"], "0");
 stats_tests!(
@@ -496,3 +637,542 @@ mod stats_header_fields {
     stats_test_headers!(stats_header_field_name, "field", &["a"], "header");
     stats_test_no_headers!(stats_header_no_field_name, "field", &["a"], "0");
 }
+
+#[test]
+fn stats_stdin_spooled() {
+    let wrk = Workdir::new("stats_stdin_spooled");
+    let mut data = vec![svec!["n", "s"]];
+    for i in 0..100 {
+        data.push(vec![i.to_string(), format!("s{}", i % 7)]);
+    }
+    wrk.create("in.csv", data);
+
+    let mut cmd = wrk.command("stats");
+    cmd.arg("--everything").arg("in.csv");
+    let expected: String = wrk.stdout(&mut cmd);
+
+    // spool stdin to disk, so it's indexed and its stats computed in parallel
+    let mut cmd = wrk.command("stats");
+    cmd.env("QSV_SPOOL_SIZE", "0")
+        .args(["--everything", "--jobs", "4"])
+        .stdin(std::fs::File::open(wrk.path("in.csv")).unwrap());
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn stats_stdin_spooled_error() {
+    let wrk = Workdir::new("stats_stdin_spooled_error");
+    wrk.create("in.csv", vec![svec!["n"], svec!["1"], svec!["2"]]);
+    std::fs::create_dir_all(wrk.path("tmp")).unwrap();
+
+    let mut cmd = wrk.command("stats");
+    cmd.env("QSV_SPOOL_SIZE", "0")
+        .env("TMPDIR", wrk.path("tmp"))
+        .args(["--select", "nope", "--jobs", "2"])
+        .stdin(std::fs::File::open(wrk.path("in.csv")).unwrap());
+    wrk.assert_err(&mut cmd);
+    // the spooled file and its index are also removed when the command fails
+    assert_eq!(std::fs::read_dir(wrk.path("tmp")).unwrap().count(), 0);
+}
+
+#[test]
+fn stats_percentiles() {
+    let wrk = Workdir::new("stats_percentiles");
+    wrk.create(
+        "in.csv",
+        vec![svec!["n"], svec!["3"], svec!["1"], svec!["4"], svec!["2"]],
+    );
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--percentiles", "0,25,50,99.5"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(&got[0][10..], svec!["p0", "p25", "p50", "p99.5"]);
+    assert_eq!(&got[1][10..], svec!["1", "1.5", "2.5", "4"]);
+}
+
+#[test]
+fn stats_percentiles_invalid() {
+    let wrk = Workdir::new("stats_percentiles_invalid");
+    wrk.create("in.csv", vec![svec!["n"], svec!["1"]]);
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--percentiles", "50,101"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn stats_approx_small() {
+    let wrk = Workdir::new("stats_approx_small");
+    wrk.create(
+        "in.csv",
+        vec![svec!["n"], svec!["3"], svec!["1"], svec!["4"], svec!["2"]],
+    );
+
+    // small columns are kept exactly
+    let mut cmd = wrk.command("stats");
+    cmd.args([
+        "--cardinality",
+        "--quartiles",
+        "--mad",
+        "--percentiles",
+        "25,50",
+    ])
+    .arg("in.csv");
+    let expected: String = wrk.stdout(&mut cmd);
+
+    let mut cmd = wrk.command("stats");
+    cmd.args([
+        "--approx",
+        "--cardinality",
+        "--quartiles",
+        "--mad",
+        "--percentiles",
+        "25,50",
+    ])
+    .arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn stats_approx_parallel() {
+    let wrk = Workdir::new("stats_approx_parallel");
+    let mut data = vec![svec!["n"]];
+    for i in 0..20_000u64 {
+        data.push(vec![((i * 7919) % 20_011).to_string()]);
+    }
+    wrk.create_indexed("in.csv", data);
+
+    let quantiles = |approx: bool| -> Vec<f64> {
+        let mut cmd = wrk.command("stats");
+        if approx {
+            cmd.args(["--approx", "--jobs", "4"]);
+        } else {
+            cmd.args(["--jobs", "1"]);
+        }
+        cmd.args(["--quartiles", "--percentiles", "1,99"])
+            .arg("in.csv");
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        ["q1", "q2_median", "q3", "p1", "p99"]
+            .iter()
+            .map(|h| {
+                let i = got[0].iter().position(|f| f == h).unwrap();
+                got[1][i].parse().unwrap()
+            })
+            .collect()
+    };
+    let exact = quantiles(false);
+    let approx = quantiles(true);
+    // the numbers are spread evenly over 0..20_011, so their rank is about
+    // their value
+    for (e, a) in exact.iter().zip(approx.iter()) {
+        assert!((e - a).abs() / 20_011.0 < 0.001, "{a} is not close to {e}");
+    }
+}
+
+#[test]
+fn stats_approx_cardinality() {
+    let wrk = Workdir::new("stats_approx_cardinality");
+    let mut data = vec![svec!["n", "s"]];
+    for i in 0..20_000u64 {
+        data.push(vec![i.to_string(), format!("s{}", i % 7)]);
+    }
+    wrk.create_indexed("in.csv", data);
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--approx", "--cardinality", "--mode", "--jobs", "4"])
+        .arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let i = got[0].iter().position(|f| f == "cardinality").unwrap();
+    let n: f64 = got[1][i].parse().unwrap();
+    assert!(
+        (n - 20_000.0).abs() / 20_000.0 < 0.02,
+        "{n} is not close to 20000"
+    );
+    assert_eq!(got[2][i], "7");
+    // the modes are still exact
+    assert_eq!(got[2][i - 1], "s0");
+}
+
+#[test]
+fn stats_distribution_parallel() {
+    let wrk = Workdir::new("stats_distribution_parallel");
+    let mut data = vec![svec!["n", "s"]];
+    for i in 0..1000u64 {
+        data.push(vec![((i * i) % 97).to_string(), format!("s{:04}", i)]);
+    }
+    wrk.create_indexed("in.csv", data);
+
+    let flags = ["--moments", "--cv", "--means", "--signs", "--sort-order"];
+    let mut cmd = wrk.command("stats");
+    cmd.args(flags).args(["--jobs", "1"]).arg("in.csv");
+    let expected: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(expected[1].last().unwrap(), "Unsorted");
+    assert_eq!(expected[2].last().unwrap(), "Ascending");
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(flags).args(["--jobs", "4"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // floating point sums depend on the chunks, so only compare the first
+    // digits of the statistics
+    for (got, expected) in got.iter().flatten().zip(expected.iter().flatten()) {
+        let len = cmp::min(10, cmp::min(got.len(), expected.len()));
+        assert_eq!(&got[..len], &expected[..len]);
+    }
+}
+
+#[test]
+fn stats_output_json() {
+    let wrk = Workdir::new("stats_output_json");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["n", "s", "e"],
+            svec!["1", "b", ""],
+            svec!["2.5", "a", ""],
+        ],
+    );
+
+    let mut cmd = wrk.command("stats");
+    cmd.args([
+        "--output-format",
+        "json",
+        "--nullcount",
+        "--percentiles",
+        "50",
+    ])
+    .arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    let got: serde_json::Value = serde_json::from_str(&got).unwrap();
+
+    let parameters = &got["parameters"];
+    assert_eq!(parameters["input"], "in.csv");
+    assert_eq!(parameters["nullcount"], true);
+    assert_eq!(parameters["percentiles"], serde_json::json!([50.0]));
+
+    let fields = got["fields"].as_array().unwrap();
+    assert_eq!(fields.len(), 3);
+    assert_eq!(
+        fields[0],
+        serde_json::json!({
+            "field": "n",
+            "type": "Float",
+            "sum": 3.5,
+            "min": 1.0,
+            "max": 2.5,
+            "min_length": 1,
+            "max_length": 3,
+            "mean": 1.75,
+            "stddev": 0.75,
+            "variance": 0.5625,
+            "p50": 1.75,
+            "nullcount": 0,
+        })
+    );
+    assert_eq!(fields[1]["type"], "String");
+    assert_eq!(fields[1]["min"], "a");
+    assert_eq!(fields[1]["mean"], serde_json::Value::Null);
+    assert_eq!(fields[2]["type"], "NULL");
+    assert_eq!(fields[2]["nullcount"], 2);
+}
+
+/// Set the sum of the first field in the stats cache of `in.csv` to 42, to
+/// tell whether the cache is used.
+fn tamper_stats_cache(wrk: &Workdir) {
+    let path = wrk.path("in.csv.stats.json");
+    let mut cache: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(cache["rows"], 2);
+    cache["fields"][0]["sum"] = serde_json::json!(42);
+    std::fs::write(&path, cache.to_string()).unwrap();
+}
+
+#[test]
+fn stats_cache() {
+    let wrk = Workdir::new("stats_cache");
+    let _ = std::fs::remove_file(wrk.path("in.csv.stats.json"));
+    wrk.create(
+        "in.csv",
+        vec![svec!["n", "s"], svec!["1", "a"], svec!["2", "b"]],
+    );
+
+    // the cache is only written with --cache
+    let mut cmd = wrk.command("stats");
+    cmd.arg("--cardinality").arg("in.csv");
+    let expected: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert!(!wrk.path("in.csv.stats.json").exists());
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--cardinality", "--cache"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, expected);
+    tamper_stats_cache(&wrk);
+
+    // the cache is used for the same statistics or a subset of them
+    let mut cmd = wrk.command("stats");
+    cmd.arg("--cardinality").arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let mut tampered = expected.clone();
+    tampered[1][2] = "42".to_owned();
+    assert_eq!(got, tampered);
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--select", "n"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got.len(), 2);
+    assert_eq!(got[1][..3], svec!["n", "Integer", "42"][..]);
+
+    // but not with --no-cache
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--cardinality", "--no-cache"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, expected);
+
+    // nor for other statistics, which are cached instead
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--nullcount", "--cache"]).arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][2], "3");
+    tamper_stats_cache(&wrk);
+    let mut cmd = wrk.command("stats");
+    cmd.arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][2], "42");
+
+    // nor once the file changed
+    wrk.create(
+        "in.csv",
+        vec![svec!["n", "s"], svec!["1", "a"], svec!["3", "b"]],
+    );
+    let mut cmd = wrk.command("stats");
+    cmd.arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][2], "4");
+}
+
+#[test]
+fn stats_cache_edit_in_the_middle() {
+    let wrk = Workdir::new("stats_cache_edit_in_the_middle");
+    let _ = std::fs::remove_file(wrk.path("in.csv.stats.json"));
+    // large enough for the middle not to be hashed in the fingerprint
+    let mut data = "n\n".to_owned() + &"1\n".repeat(100_000);
+    wrk.create_from_string("in.csv", &data);
+
+    let mut cmd = wrk.command("stats");
+    cmd.arg("--cache").arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][2], "100000");
+
+    // the file keeps its size
+    data.replace_range(100_000..100_001, "5");
+    wrk.create_from_string("in.csv", &data);
+    let mut cmd = wrk.command("stats");
+    cmd.arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][2], "100004");
+}
+
+#[test]
+fn stats_boolean_values() {
+    let wrk = Workdir::new("stats_boolean_values");
+    wrk.create("in.csv", vec![svec!["b"], svec!["oui"], svec!["Non"]]);
+
+    let mut cmd = wrk.command("stats");
+    cmd.env("QSV_TRUTHY_VALUES", "oui,si")
+        .env("QSV_FALSY_VALUES", "non,no")
+        .arg("--cache")
+        .arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][1], "Boolean");
+
+    // the cached types depend on the boolean values
+    let mut cmd = wrk.command("stats");
+    cmd.arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][1], "String");
+}
+
+#[test]
+fn stats_numeric_locale() {
+    let wrk = Workdir::new("stats_numeric_locale");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["amount", "balance", "rate"],
+            svec!["1,234.50", "(500)", "45%"],
+            svec!["12,000", "1,000", "5%"],
+        ],
+    );
+
+    let mut cmd = wrk.command("stats");
+    cmd.env("QSV_NUMERIC_LOCALE", ",.")
+        .arg("--cache")
+        .arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let got: Vec<&[String]> = got.iter().skip(1).map(|row| &row[..5]).collect();
+    assert_eq!(
+        got[0],
+        svec!["amount", "Float", "13234.5", "1234.5", "12000"]
+    );
+    assert_eq!(got[1], svec!["balance", "Integer", "500", "-500", "1000"]);
+    assert_eq!(got[2], svec!["rate", "Float", "0.5", "0.05", "0.45"]);
+
+    // the cached types depend on the number format
+    let mut cmd = wrk.command("stats");
+    cmd.arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[1][1], "String");
+}
+
+#[test]
+fn stats_numeric_locale_auto() {
+    let wrk = Workdir::new("stats_numeric_locale_auto");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["amount"],
+            svec!["1.234,5"],
+            svec!["7,25"],
+            svec!["1.000.000"],
+        ],
+    );
+
+    let mut cmd = wrk.command("stats");
+    cmd.env("QSV_NUMERIC_LOCALE", "auto")
+        .arg("--no-cache")
+        .arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(
+        &got[1][..5],
+        svec!["amount", "Float", "1001241.75", "7.25", "1000000"]
+    );
+}
+
+#[test]
+fn stats_dates() {
+    let wrk = Workdir::new("stats_dates");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["d", "dt"],
+            svec!["2001-09-11", "June 27, 1968 12:30:00 UTC"],
+            svec!["12/25/1999", "2012-09-17T10:09:00.5+02:00"],
+            svec!["", "1968-06-27 12:30:01"],
+        ],
+    );
+
+    let mut cmd = wrk.command("stats");
+    cmd.arg("--dates").arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let field = |row: &[String], name: &str| {
+        let i = got[0].iter().position(|h| h == name).unwrap();
+        row[i].clone()
+    };
+
+    let d = &got[1];
+    assert_eq!(field(d, "type"), "Date");
+    assert_eq!(field(d, "min"), "1999-12-25");
+    assert_eq!(field(d, "max"), "2001-09-11");
+    assert_eq!(field(d, "date_span_days"), "626");
+    assert_eq!(field(d, "date_formats"), "%Y-%m-%d; %m/%d/%Y");
+    assert_eq!(field(d, "timezones"), "None");
+
+    let dt = &got[2];
+    assert_eq!(field(dt, "type"), "DateTime");
+    assert_eq!(field(dt, "min"), "1968-06-27T12:30:00Z");
+    assert_eq!(field(dt, "max"), "2012-09-17T08:09:00.500Z");
+    assert_eq!(
+        field(dt, "date_formats"),
+        "%B %d, %Y %H:%M:%S %Z; %Y-%m-%dT%H:%M:%S%.f%:z; %Y-%m-%d %H:%M:%S"
+    );
+    assert_eq!(field(dt, "timezones"), "Some");
+}
+
+fn stats_groupby_data() -> Vec<Vec<String>> {
+    vec![
+        svec!["state", "n"],
+        svec!["NY", "5"],
+        svec!["CA", "3"],
+        svec!["NY", "2"],
+        svec!["CA", "1"],
+        svec!["CA", "2"],
+    ]
+}
+
+fn stats_groupby_expected() -> Vec<Vec<String>> {
+    vec![
+        svec!["state", "field", "type", "sum", "min", "max", "sort_order"],
+        svec!["CA", "n", "Integer", "6", "1", "3", "Unsorted"],
+        svec!["NY", "n", "Integer", "7", "2", "5", "Descending"],
+    ]
+}
+
+#[test]
+fn stats_groupby() {
+    let wrk = Workdir::new("stats_groupby");
+    wrk.create("in.csv", stats_groupby_data());
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--groupby", "state", "--select", "!state", "--sort-order"])
+        .arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let got: Vec<Vec<String>> = got
+        .into_iter()
+        .map(|row| [&row[..6], &row[row.len() - 1..]].concat())
+        .collect();
+    assert_eq!(got, stats_groupby_expected());
+}
+
+#[test]
+fn stats_groupby_index() {
+    let wrk = Workdir::new("stats_groupby_index");
+    wrk.create_indexed("in.csv", stats_groupby_data());
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--groupby", "1", "--select", "2", "--sort-order"])
+        .args(["--jobs", "2"])
+        .arg("in.csv");
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let got: Vec<Vec<String>> = got
+        .into_iter()
+        .map(|row| [&row[..6], &row[row.len() - 1..]].concat())
+        .collect();
+    assert_eq!(got, stats_groupby_expected());
+}
+
+#[test]
+fn stats_groupby_stdin() {
+    let wrk = Workdir::new("stats_groupby_stdin");
+    wrk.create("in.csv", stats_groupby_data());
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--groupby", "state", "--select", "!state", "--sort-order"])
+        .stdin(std::fs::File::open(wrk.path("in.csv")).unwrap());
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let got: Vec<Vec<String>> = got
+        .into_iter()
+        .map(|row| [&row[..6], &row[row.len() - 1..]].concat())
+        .collect();
+    assert_eq!(got, stats_groupby_expected());
+}
+
+#[test]
+fn stats_groupby_json() {
+    let wrk = Workdir::new("stats_groupby_json");
+    wrk.create("in.csv", stats_groupby_data());
+
+    let mut cmd = wrk.command("stats");
+    cmd.args(["--groupby", "state", "--output-format", "json"])
+        .arg("in.csv");
+    let got: String = wrk.stdout(&mut cmd);
+    let got: serde_json::Value = serde_json::from_str(&got).unwrap();
+
+    let fields = got["fields"].as_array().unwrap();
+    assert_eq!(fields.len(), 4);
+    assert_eq!(fields[1]["group"], serde_json::json!({"state": "CA"}));
+    assert_eq!(fields[1]["field"], "n");
+    assert_eq!(fields[1]["sum"], 6);
+    assert_eq!(fields[3]["group"], serde_json::json!({"state": "NY"}));
+    assert_eq!(fields[3]["sum"], 7);
+}
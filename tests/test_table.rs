@@ -78,18 +78,32 @@ fn table_pipe_delimiter() {
     assert_eq!(&*got, EXPECTED_TABLE)
 }
 
+#[test]
+fn multi_byte_delimiter() {
+    let wrk = Workdir::new("multi_byte_delimiter");
+    wrk.create_from_string("in.file", "h1||h2||h3\nabcdefg||a||a\na||abc||z\n");
+
+    let mut cmd = wrk.command("table");
+    cmd.arg("--delimiter").arg("||").arg("in.file");
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(&*got, EXPECTED_TABLE)
+}
+
 #[test]
 fn invalid_delimiter_len() {
     let wrk = Workdir::new("invalid_delimiter_len");
     wrk.create_with_delim("in.file", data(), b'|');
 
     let mut cmd = wrk.command("table");
-    cmd.arg("--delimiter").arg("||").arg("in.file");
+    cmd.arg("--delimiter")
+        .arg("||||||||||||||||||")
+        .arg("in.file");
 
     let got: String = wrk.output_stderr(&mut cmd);
     assert_eq!(
         &*got,
-        "Could not convert '||' to a single ASCII character.\n"
+        "Could not use '||||||||||||||||||' as a delimiter. It is longer than 16 bytes.\n"
     )
 }
 
@@ -0,0 +1,115 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use crate::workdir::Workdir;
+
+/// Start a minimal HTTP server on a free port that serves `body` at `/{name}`
+/// (ignoring any query string) and responds with a 404 to any other path. Returns the base URL.
+fn serve(name: &'static str, body: Vec<u8>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut rdr = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            rdr.read_line(&mut request_line).unwrap();
+            let mut line = String::new();
+            while rdr.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let path = request_line.split(' ').nth(1).unwrap_or_default();
+            let (status, body) = if path.split('?').next() == Some(&format!("/{name}")) {
+                ("200 OK", &body[..])
+            } else {
+                ("404 Not Found", &b""[..])
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(body).unwrap();
+        }
+    });
+    format!("http://{addr}")
+}
+
+#[test]
+fn url_select() {
+    let wrk = Workdir::new("url_select");
+    let base = serve("data.csv", b"h1,h2\na,1\nb,2\n".to_vec());
+
+    let mut cmd = wrk.command("select");
+    cmd.env("NO_PROXY", "127.0.0.1")
+        .arg("h2")
+        .arg(format!("{base}/data.csv"));
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h2"], svec!["1"], svec!["2"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn url_tsv_delimiter() {
+    let wrk = Workdir::new("url_tsv_delimiter");
+    let base = serve("data.tsv", b"h1\th2\na\t1\nb\t2\n".to_vec());
+
+    let mut cmd = wrk.command("select");
+    cmd.env("NO_PROXY", "127.0.0.1")
+        .arg("h2")
+        .arg(format!("{base}/data.tsv?download=1"));
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h2"], svec!["1"], svec!["2"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn url_gzip() {
+    let wrk = Workdir::new("url_gzip");
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    enc.write_all(b"h1,h2\na,1\nb,2\nc,3\n").unwrap();
+    let base = serve("data.csv.gz", enc.finish().unwrap());
+
+    let mut cmd = wrk.command("count");
+    cmd.env("NO_PROXY", "127.0.0.1")
+        .arg(format!("{base}/data.csv.gz"));
+
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "3");
+}
+
+#[test]
+fn url_reader_file() {
+    let wrk = Workdir::new("url_reader_file");
+    let base = serve("data.csv", b"h1,h2\na,1\nb,2\nc,3\n".to_vec());
+    wrk.create("exclude.csv", vec![svec!["h1"], svec!["b"]]);
+
+    let mut cmd = wrk.command("exclude");
+    cmd.env("NO_PROXY", "127.0.0.1")
+        .arg("h1")
+        .arg(format!("{base}/data.csv"))
+        .arg("h1")
+        .arg("exclude.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["h1", "h2"], svec!["a", "1"], svec!["c", "3"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn url_not_found() {
+    let wrk = Workdir::new("url_not_found");
+    let base = serve("data.csv", b"h1,h2\na,1\n".to_vec());
+
+    let mut cmd = wrk.command("count");
+    cmd.env("NO_PROXY", "127.0.0.1")
+        .arg(format!("{base}/missing.csv"));
+
+    let got: String = wrk.output_stderr(&mut cmd);
+    assert!(got.starts_with(&format!("failed to open {base}/missing.csv")));
+    assert!(got.contains("404 Not Found"));
+}
@@ -36,6 +36,57 @@ fn validate_bad_csv() {
     wrk.assert_err(&mut cmd);
 }
 
+#[test]
+fn validate_boolean_values() {
+    let wrk = Workdir::new("validate_boolean_values").flexible(true);
+    wrk.create(
+        "data.csv",
+        vec![
+            svec!["name", "active"],
+            svec!["a", "true"],
+            svec!["b", "yes"],
+        ],
+    );
+    wrk.create_from_string(
+        "schema.json",
+        r#"{"type": "object", "properties": {"active": {"type": "boolean"}}}"#,
+    );
+
+    // only true and false are booleans
+    let mut cmd = wrk.command("validate");
+    cmd.arg("data.csv").arg("schema.json");
+    wrk.output(&mut cmd);
+    let invalid_output: String = wrk.from_str(&wrk.path("data.csv.invalid"));
+    assert_eq!(invalid_output, "name,active\nb,yes\n");
+
+    // but yes is a boolean with the schema inferred by qsv, even once its
+    // description is edited
+    let mut cmd = wrk.command("schema");
+    cmd.arg("data.csv");
+    wrk.output(&mut cmd);
+    let path = wrk.path("data.csv.schema.json");
+    let mut schema: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    schema["description"] = serde_json::json!("Active names");
+    std::fs::write(&path, schema.to_string()).unwrap();
+    let _ = std::fs::remove_file(wrk.path("data.csv.invalid"));
+    let mut cmd = wrk.command("validate");
+    cmd.arg("data.csv").arg("data.csv.schema.json");
+    wrk.output(&mut cmd);
+    assert!(!std::path::Path::new(&wrk.path("data.csv.invalid")).exists());
+
+    // and with other schemas marked like it
+    wrk.create_from_string(
+        "schema.json",
+        r#"{"type": "object", "x-qsv-truthy-falsy": true,
+            "properties": {"active": {"type": "boolean"}}}"#,
+    );
+    let mut cmd = wrk.command("validate");
+    cmd.arg("data.csv").arg("schema.json");
+    wrk.output(&mut cmd);
+    assert!(!std::path::Path::new(&wrk.path("data.csv.invalid")).exists());
+}
+
 fn adur_errors() -> &'static str {
     "row_number\tfield\terror\n\
     1\tExtractDate\tnull is not of type \"string\"\n\
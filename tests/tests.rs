@@ -39,6 +39,8 @@ mod test_behead;
 mod test_cat;
 mod test_combos;
 mod test_comments;
+mod test_compress;
+mod test_corr;
 mod test_count;
 mod test_dedup;
 mod test_enumerate;
@@ -54,15 +56,19 @@ mod test_fmt;
 mod test_foreach;
 mod test_frequency;
 mod test_headers;
+mod test_histogram;
 mod test_index;
+mod test_input;
 mod test_join;
 mod test_jsonl;
+mod test_lookup;
 #[cfg(feature = "lua")]
 mod test_lua;
 mod test_partition;
 mod test_pseudo;
 #[cfg(feature = "python")]
 mod test_py;
+mod test_qsv_toml;
 mod test_rename;
 mod test_replace;
 mod test_reverse;
@@ -78,6 +84,7 @@ mod test_split;
 mod test_stats;
 mod test_table;
 mod test_transpose;
+mod test_url;
 mod test_validate;
 
 fn qcheck<T: Testable>(p: T) {